@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Splitting long inputs into chunks that fit the model context
+//!
+//! Text is split at paragraph boundaries first, then at sentence boundaries when a
+//! paragraph alone is too long. Each chunk remembers the whitespace that followed it
+//! in the source so translated chunks can be stitched back with the same layout.
+
+use crate::Result;
+use crate::segmenter;
+
+/// A piece of source text translated on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Source text of the chunk
+    pub text: String,
+    /// Whitespace that followed the chunk in the source
+    pub separator: String,
+}
+
+/// Split `text` into chunks of at most `max_tokens` tokens as measured by `count_tokens`
+///
+/// Leading and trailing whitespace of `text` is dropped. Paragraphs are never merged,
+/// so the blank lines between them survive as chunk separators.
+pub fn split_into_chunks<F>(
+    text: &str,
+    max_tokens: usize,
+    mut count_tokens: F,
+) -> Result<Vec<Chunk>>
+where
+    F: FnMut(&str) -> Result<usize>,
+{
+    let max_tokens = max_tokens.max(1);
+    let mut chunks = Vec::new();
+
+    for paragraph in segmenter::paragraphs(text.trim()) {
+        if count_tokens(paragraph.text)? <= max_tokens {
+            chunks.push(Chunk {
+                text: paragraph.text.to_string(),
+                separator: paragraph.trailing.to_string(),
+            });
+            continue;
+        }
+
+        let mut pieces = Vec::new();
+        for sentence in segmenter::sentences(paragraph.text) {
+            split_oversized(
+                sentence.text,
+                sentence.trailing,
+                max_tokens,
+                &mut count_tokens,
+                &mut pieces,
+            )?;
+        }
+        pack_pieces(pieces, max_tokens, &mut chunks);
+        if let Some(last) = chunks.last_mut() {
+            last.separator = paragraph.trailing.to_string();
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// A sentence-sized span with its token count and trailing whitespace
+struct Piece<'a> {
+    text: &'a str,
+    separator: &'a str,
+    tokens: usize,
+}
+
+/// Greedily join consecutive pieces while they stay within `max_tokens`
+fn pack_pieces(pieces: Vec<Piece<'_>>, max_tokens: usize, chunks: &mut Vec<Chunk>) {
+    let mut current: Option<(String, &str, usize)> = None;
+    for piece in pieces {
+        current = match current.take() {
+            Some((mut text, separator, tokens)) if tokens + piece.tokens <= max_tokens => {
+                text.push_str(separator);
+                text.push_str(piece.text);
+                Some((text, piece.separator, tokens + piece.tokens))
+            }
+            Some((text, separator, _)) => {
+                chunks.push(Chunk {
+                    text,
+                    separator: separator.to_string(),
+                });
+                Some((piece.text.to_string(), piece.separator, piece.tokens))
+            }
+            None => Some((piece.text.to_string(), piece.separator, piece.tokens)),
+        };
+    }
+    if let Some((text, separator, _)) = current {
+        chunks.push(Chunk {
+            text,
+            separator: separator.to_string(),
+        });
+    }
+}
+
+/// Push `sentence` as one piece, or as several word-aligned pieces when it is too long
+fn split_oversized<'a, F>(
+    sentence: &'a str,
+    separator: &'a str,
+    max_tokens: usize,
+    count_tokens: &mut F,
+    pieces: &mut Vec<Piece<'a>>,
+) -> Result<()>
+where
+    F: FnMut(&str) -> Result<usize>,
+{
+    let tokens = count_tokens(sentence)?;
+    if tokens <= max_tokens {
+        pieces.push(Piece {
+            text: sentence,
+            separator,
+            tokens,
+        });
+        return Ok(());
+    }
+
+    let words = split_words(sentence);
+    if words.len() > 1 {
+        let last = words.len() - 1;
+        for (index, (word, word_separator)) in words.into_iter().enumerate() {
+            let word_separator = if index == last {
+                separator
+            } else {
+                word_separator
+            };
+            split_oversized(word, word_separator, max_tokens, count_tokens, pieces)?;
+        }
+        return Ok(());
+    }
+
+    // A single unbreakable run (e.g. CJK text without spaces): halve it until it fits
+    let mid = sentence
+        .char_indices()
+        .map(|(index, _)| index)
+        .nth(sentence.chars().count() / 2)
+        .unwrap_or(sentence.len());
+    if mid == 0 || mid == sentence.len() {
+        pieces.push(Piece {
+            text: sentence,
+            separator,
+            tokens,
+        });
+        return Ok(());
+    }
+    split_oversized(&sentence[..mid], "", max_tokens, count_tokens, pieces)?;
+    split_oversized(
+        &sentence[mid..],
+        separator,
+        max_tokens,
+        count_tokens,
+        pieces,
+    )
+}
+
+/// Split text into words, keeping the whitespace that follows each one
+fn split_words(text: &str) -> Vec<(&str, &str)> {
+    let mut words = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let after = &rest[word_end..];
+        let separator_len = after.len() - after.trim_start().len();
+        words.push((&rest[..word_end], &after[..separator_len]));
+        rest = &after[separator_len..];
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_words(text: &str) -> Result<usize> {
+        Ok(text.split_whitespace().count().max(1))
+    }
+
+    fn stitch(chunks: &[Chunk]) -> String {
+        chunks
+            .iter()
+            .map(|chunk| format!("{}{}", chunk.text, chunk.separator))
+            .collect()
+    }
+
+    #[test]
+    fn short_text_is_a_single_chunk() {
+        let chunks = split_into_chunks("  Hello world.\n", 10, count_words).unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![Chunk {
+                text: "Hello world.".to_string(),
+                separator: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn paragraphs_become_separate_chunks_with_original_separators() {
+        let text = "First paragraph here.\n\nSecond one.\n  \n\nThird.";
+
+        let chunks = split_into_chunks(text, 100, count_words).unwrap();
+
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, ["First paragraph here.", "Second one.", "Third."]);
+        assert_eq!(chunks[0].separator, "\n\n");
+        assert_eq!(chunks[1].separator, "\n  \n\n");
+        assert_eq!(stitch(&chunks), text);
+    }
+
+    #[test]
+    fn long_paragraph_is_split_at_sentence_boundaries() {
+        let text = "One two three. Four five six. Seven eight nine.";
+
+        let chunks = split_into_chunks(text, 6, count_words).unwrap();
+
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["One two three. Four five six.", "Seven eight nine."]
+        );
+        assert_eq!(chunks[0].separator, " ");
+        assert_eq!(stitch(&chunks), text);
+    }
+
+    #[test]
+    fn oversized_sentence_is_split_at_words() {
+        let text = "one two three four five";
+
+        let chunks = split_into_chunks(text, 2, count_words).unwrap();
+
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, ["one two", "three four", "five"]);
+        assert_eq!(stitch(&chunks), text);
+    }
+
+    #[test]
+    fn cjk_sentences_split_without_whitespace() {
+        let text = "今日は晴れです。明日は雨です。";
+        let count_chars = |text: &str| Ok(text.chars().count());
+
+        let chunks = split_into_chunks(text, 8, count_chars).unwrap();
+
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, ["今日は晴れです。", "明日は雨です。"]);
+        assert_eq!(stitch(&chunks), text);
+    }
+
+    #[test]
+    fn every_chunk_fits_the_budget() {
+        let text = "Lorem ipsum dolor sit amet. ".repeat(40);
+
+        let chunks = split_into_chunks(&text, 12, count_words).unwrap();
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(count_words(&chunk.text).unwrap() <= 12, "{chunk:?}");
+        }
+        assert_eq!(stitch(&chunks), text.trim());
+    }
+}
@@ -25,6 +25,160 @@ pub struct GlossaryConfig {
     pub max_matches: usize,
 }
 
+/// Token sampling configuration
+///
+/// The defaults select greedy decoding, which keeps translations deterministic.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SamplingConfig {
+    /// Sampling temperature (0 = greedy decoding)
+    pub temperature: f32,
+
+    /// Keep only the k most likely tokens (0 = disabled)
+    pub top_k: u32,
+
+    /// Keep the smallest token set whose cumulative probability reaches p (1.0 = disabled)
+    pub top_p: f32,
+
+    /// Drop tokens less likely than min_p times the most likely token (0.0 = disabled)
+    pub min_p: f32,
+
+    /// Penalty applied to recently generated tokens (1.0 = disabled)
+    pub repeat_penalty: f32,
+
+    /// Number of recent tokens considered by the repetition penalty
+    pub repeat_last_n: u32,
+
+    /// Fixed seed for the random sampler (random when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            top_k: 0,
+            top_p: 1.0,
+            min_p: 0.0,
+            repeat_penalty: 1.0,
+            repeat_last_n: 64,
+            seed: None,
+        }
+    }
+}
+
+impl SamplingConfig {
+    /// Whether these settings select greedy decoding
+    pub fn is_greedy(&self) -> bool {
+        self.temperature <= 0.0
+    }
+
+    /// Check that every value is within its valid range
+    pub fn validate(&self) -> crate::Result<()> {
+        if !self.temperature.is_finite() || self.temperature < 0.0 {
+            return Err(crate::Error::Config(format!(
+                "sampling.temperature must be a non-negative number, got {}",
+                self.temperature
+            )));
+        }
+        if !(self.top_p > 0.0 && self.top_p <= 1.0) {
+            return Err(crate::Error::Config(format!(
+                "sampling.top_p must be in (0, 1], got {}",
+                self.top_p
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.min_p) {
+            return Err(crate::Error::Config(format!(
+                "sampling.min_p must be in [0, 1], got {}",
+                self.min_p
+            )));
+        }
+        if !self.repeat_penalty.is_finite() || self.repeat_penalty <= 0.0 {
+            return Err(crate::Error::Config(format!(
+                "sampling.repeat_penalty must be positive, got {}",
+                self.repeat_penalty
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// OpenAI-compatible endpoint used by the HTTP backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpApi {
+    /// `POST /v1/completions` with the full TranslateGemma prompt
+    #[default]
+    Completions,
+    /// `POST /v1/chat/completions` with the user turn as a single message
+    Chat,
+}
+
+/// Configuration for a self-hosted OpenAI-compatible server (llama-server, vLLM)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Server base URL, e.g. `http://127.0.0.1:8080`
+    pub url: String,
+
+    /// Endpoint to call
+    pub api: HttpApi,
+
+    /// Model name sent with each request (single-model servers ignore it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Bearer token for servers started with an API key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    /// Timeout for a whole request, including generation
+    pub timeout_secs: u64,
+
+    /// Timeout for establishing the connection
+    pub connect_timeout_secs: u64,
+
+    /// Retries after connection errors, timeouts, 429 and 5xx responses
+    pub max_retries: u32,
+
+    /// Delay before the first retry; later retries wait proportionally longer
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:8080".to_string(),
+            api: HttpApi::Completions,
+            model: None,
+            api_key: None,
+            timeout_secs: 120,
+            connect_timeout_secs: 5,
+            max_retries: 2,
+            retry_backoff_ms: 500,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Check that every value is within its valid range
+    pub fn validate(&self) -> crate::Result<()> {
+        if !self.url.starts_with("http://") {
+            return Err(crate::Error::Config(format!(
+                "backend.url must be a plain http:// URL of a self-hosted server, got `{}`",
+                self.url
+            )));
+        }
+        if self.timeout_secs == 0 || self.connect_timeout_secs == 0 {
+            return Err(crate::Error::Config(
+                "backend timeouts must be at least 1 second".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Configuration for the translation engine
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct Config {
@@ -49,6 +203,10 @@ pub struct Config {
     /// Glossary configuration
     #[serde(default)]
     pub glossary: GlossaryConfig,
+
+    /// Token sampling configuration
+    #[serde(default)]
+    pub sampling: SamplingConfig,
 }
 
 impl Config {
@@ -99,6 +257,8 @@ log_path = "logs/llama.log"
         assert!(!config.log_to_file);
         assert_eq!(config.log_path, PathBuf::from("logs/llama.log"));
         assert_eq!(config.glossary, GlossaryConfig::default());
+        assert_eq!(config.sampling, SamplingConfig::default());
+        assert!(config.sampling.is_greedy());
     }
 
     #[test]
@@ -116,6 +276,15 @@ log_path = "logs/llama.log"
                 embedding_model_dir: PathBuf::from("/tmp/models/embeddinggemma-300m-ONNX"),
                 max_matches: 4,
             },
+            sampling: SamplingConfig {
+                temperature: 0.7,
+                top_k: 40,
+                top_p: 0.9,
+                min_p: 0.05,
+                repeat_penalty: 1.1,
+                repeat_last_n: 128,
+                seed: Some(42),
+            },
         };
 
         let toml_str = config.to_toml().expect("serialize should succeed");
@@ -215,6 +384,91 @@ max_matches = 0
         assert_eq!(config.glossary.max_matches, 0);
     }
 
+    #[test]
+    fn test_parse_partial_sampling_toml_keeps_defaults() {
+        let toml_str = r#"
+model_path = "/models/translategemma-12b-it.gguf"
+gpu_layers = 16
+context_size = 2048
+threads = 4
+log_to_file = false
+log_path = "/var/log/petit/llama.log"
+
+[sampling]
+temperature = 0.8
+repeat_penalty = 1.15
+seed = 7
+"#;
+
+        let config = Config::from_toml(toml_str).expect("parse should succeed");
+        assert!(!config.sampling.is_greedy());
+        assert_eq!(config.sampling.temperature, 0.8);
+        assert_eq!(config.sampling.repeat_penalty, 1.15);
+        assert_eq!(config.sampling.seed, Some(7));
+        assert_eq!(config.sampling.top_k, 0);
+        assert_eq!(config.sampling.top_p, 1.0);
+        assert_eq!(config.sampling.repeat_last_n, 64);
+    }
+
+    #[test]
+    fn test_sampling_validate_rejects_out_of_range_values() {
+        let valid = SamplingConfig::default();
+        assert!(valid.validate().is_ok());
+
+        let invalid = [
+            SamplingConfig {
+                temperature: -0.1,
+                ..SamplingConfig::default()
+            },
+            SamplingConfig {
+                top_p: 0.0,
+                ..SamplingConfig::default()
+            },
+            SamplingConfig {
+                min_p: 1.5,
+                ..SamplingConfig::default()
+            },
+            SamplingConfig {
+                repeat_penalty: 0.0,
+                ..SamplingConfig::default()
+            },
+        ];
+        for sampling in invalid {
+            assert!(
+                sampling.validate().is_err(),
+                "expected validation error for {sampling:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_partial_http_toml_keeps_defaults() {
+        let config: HttpConfig =
+            toml::from_str("url = \"http://10.0.0.5:8000\"\napi = \"chat\"\nmax_retries = 0")
+                .expect("parse should succeed");
+
+        assert_eq!(config.url, "http://10.0.0.5:8000");
+        assert_eq!(config.api, HttpApi::Chat);
+        assert_eq!(config.max_retries, 0);
+        assert_eq!(config.timeout_secs, HttpConfig::default().timeout_secs);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_http_validate_rejects_non_http_url_and_zero_timeout() {
+        let https = HttpConfig {
+            url: "https://api.example.com".to_string(),
+            ..HttpConfig::default()
+        };
+        let no_timeout = HttpConfig {
+            timeout_secs: 0,
+            ..HttpConfig::default()
+        };
+
+        assert!(https.validate().is_err());
+        assert!(no_timeout.validate().is_err());
+    }
+
     #[test]
     fn test_parse_invalid_toml() {
         let toml_str = "this is not valid toml {{{{";
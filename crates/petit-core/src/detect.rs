@@ -0,0 +1,519 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Lightweight source language detection
+//!
+//! TranslateGemma infers the source language itself when asked to, but never reports it.
+//! Frontends that must name the source (e.g. LibreTranslate's `/detect`) use this detector:
+//! the writing system settles most languages, and stopword and diacritic counts separate
+//! languages that share the Latin, Cyrillic, Arabic or Devanagari scripts.
+
+use crate::language::{is_auto_source, normalize_lang};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Detected language with a confidence between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DetectedLanguage {
+    /// Supported ISO 639-1 code
+    pub language: &'static str,
+    /// Confidence between 0 and 1
+    pub confidence: f32,
+}
+
+/// Writing system of a letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Arabic,
+    Devanagari,
+    Han,
+    Kana,
+    Hangul,
+    /// Script used by exactly one supported language
+    Unique(&'static str),
+}
+
+/// Stopwords and characteristic letters of languages written in the Latin script
+const LATIN_PROFILES: &[(&str, &[&str], &str)] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "it", "you", "for", "with", "this",
+            "are", "was", "be", "have", "not",
+        ],
+        "",
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "pour", "dans", "pas",
+            "vous", "je", "ce", "sur", "avec", "il",
+        ],
+        "çèêœ",
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "zu", "den", "mit",
+            "sie", "es", "auf", "für", "sich", "auch",
+        ],
+        "ßü",
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "con",
+            "para", "no", "del", "se", "lo", "está",
+        ],
+        "ñ¿¡",
+    ),
+    (
+        "it",
+        &[
+            "il", "la", "di", "che", "e", "è", "un", "una", "per", "non", "sono", "con", "del",
+            "della", "gli", "le", "mi", "ti",
+        ],
+        "ìò",
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "os", "as", "e", "é", "que", "não", "de", "um", "uma", "para", "com", "em",
+            "do", "da", "você", "está",
+        ],
+        "ãõ",
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "van", "niet", "ik", "dat", "je", "op", "te", "zijn",
+            "met", "voor", "hij", "wat",
+        ],
+        "ĳ",
+    ),
+    (
+        "sv",
+        &[
+            "och", "det", "att", "är", "en", "som", "på", "jag", "inte", "med", "för", "har", "av",
+            "till", "den", "du", "vi",
+        ],
+        "",
+    ),
+    (
+        "da",
+        &[
+            "og", "det", "er", "at", "en", "af", "til", "jeg", "ikke", "på", "med", "for", "har",
+            "den", "de", "som", "du",
+        ],
+        "æø",
+    ),
+    (
+        "no",
+        &[
+            "og", "det", "er", "at", "en", "ikke", "jeg", "på", "til", "med", "for", "som", "har",
+            "av", "den", "du", "ei",
+        ],
+        "æø",
+    ),
+    (
+        "fi",
+        &[
+            "ja", "on", "ei", "se", "että", "hän", "minä", "sinä", "mutta", "tämä", "kanssa",
+            "ovat", "oli", "kun",
+        ],
+        "",
+    ),
+    (
+        "et",
+        &[
+            "ja", "on", "ei", "et", "see", "ma", "sa", "mis", "kui", "aga", "oma", "seda", "ning",
+            "või", "olen",
+        ],
+        "õ",
+    ),
+    (
+        "pl",
+        &[
+            "i", "w", "nie", "na", "się", "jest", "to", "że", "z", "do", "jak", "co", "ale", "tak",
+            "już", "czy",
+        ],
+        "ąęłńśźż",
+    ),
+    (
+        "cs",
+        &[
+            "a", "je", "se", "na", "to", "že", "v", "jsem", "ale", "jak", "co", "tak", "pro",
+            "není", "jsou", "by",
+        ],
+        "řěů",
+    ),
+    (
+        "sk",
+        &[
+            "a", "je", "sa", "na", "to", "že", "v", "som", "ale", "ako", "čo", "tak", "pre", "nie",
+            "sú", "by",
+        ],
+        "ľĺŕô",
+    ),
+    (
+        "sl",
+        &[
+            "in", "je", "se", "na", "da", "v", "za", "ki", "so", "pa", "ne", "jaz", "to", "ali",
+            "kot", "sem",
+        ],
+        "",
+    ),
+    (
+        "hr",
+        &[
+            "i", "je", "se", "u", "na", "da", "za", "su", "to", "ne", "ali", "kao", "sam", "što",
+            "od", "biti",
+        ],
+        "ćđ",
+    ),
+    (
+        "hu",
+        &[
+            "a", "az", "és", "hogy", "nem", "egy", "is", "van", "meg", "de", "ez", "csak", "már",
+            "vagy", "volt",
+        ],
+        "őű",
+    ),
+    (
+        "ro",
+        &[
+            "și", "în", "de", "la", "nu", "este", "un", "o", "că", "pe", "cu", "sunt", "mai",
+            "care", "pentru",
+        ],
+        "șțăâî",
+    ),
+    (
+        "tr",
+        &[
+            "ve", "bir", "bu", "da", "de", "için", "ne", "ile", "çok", "ben", "sen", "değil", "mi",
+            "var", "gibi",
+        ],
+        "ğşı",
+    ),
+    (
+        "id",
+        &[
+            "dan", "yang", "di", "ini", "itu", "dengan", "untuk", "tidak", "dari", "saya", "ada",
+            "akan", "adalah", "ke",
+        ],
+        "",
+    ),
+    (
+        "ms",
+        &[
+            "dan", "yang", "di", "ini", "itu", "dengan", "untuk", "tidak", "dari", "saya", "ada",
+            "akan", "ialah", "kepada", "tak",
+        ],
+        "",
+    ),
+    (
+        "sw",
+        &[
+            "na", "ya", "wa", "kwa", "ni", "za", "katika", "hii", "la", "kuwa", "si", "hiyo",
+            "yake", "sana",
+        ],
+        "",
+    ),
+    (
+        "tl",
+        &[
+            "ang", "ng", "mga", "sa", "na", "ay", "at", "ako", "hindi", "ko", "ka", "siya", "ito",
+            "po",
+        ],
+        "",
+    ),
+    (
+        "ca",
+        &[
+            "el", "la", "els", "les", "i", "és", "que", "de", "en", "un", "una", "per", "amb",
+            "no", "del", "això", "són",
+        ],
+        "ŀ",
+    ),
+    (
+        "gl",
+        &[
+            "o", "a", "os", "as", "e", "é", "que", "de", "un", "unha", "para", "con", "non", "do",
+            "da", "está", "isto",
+        ],
+        "",
+    ),
+    (
+        "lt",
+        &[
+            "ir", "yra", "kad", "ne", "tai", "su", "į", "kaip", "bet", "aš", "jis", "buvo", "ar",
+        ],
+        "ėįų",
+    ),
+    (
+        "lv",
+        &[
+            "un", "ir", "ka", "ar", "ne", "tas", "uz", "par", "bet", "es", "viņš", "no", "kā",
+            "bija",
+        ],
+        "āēīģķļņ",
+    ),
+    (
+        "sq",
+        &[
+            "dhe", "në", "është", "një", "të", "për", "me", "nuk", "që", "nga", "unë", "ju", "janë",
+        ],
+        "ë",
+    ),
+    (
+        "af",
+        &[
+            "die", "en", "is", "van", "nie", "het", "ek", "vir", "dat", "met", "op", "sy", "ons",
+            "wat",
+        ],
+        "",
+    ),
+    (
+        "vi",
+        &[
+            "và", "là", "của", "có", "không", "tôi", "được", "những", "một", "các", "người",
+            "trong", "cho", "này",
+        ],
+        "ơưạảấầậắặếệịọộờợụữđ",
+    ),
+];
+
+/// Stopwords of languages written in Devanagari
+const DEVANAGARI_PROFILES: &[(&str, &[&str], &str)] = &[
+    (
+        "hi",
+        &["है", "का", "की", "के", "में", "और", "यह", "नहीं", "हैं"],
+        "",
+    ),
+    ("mr", &["आहे", "आणि", "या", "हे", "नाही", "आहेत", "ते"], "ळ"),
+    ("ne", &["छ", "र", "को", "मा", "हो", "यो", "छैन", "गर्न"], ""),
+];
+
+/// Detect the language of `text`
+///
+/// Returns `None` when the text has no letters or gives no usable signal.
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let mut scripts: HashMap<Script, usize> = HashMap::new();
+    for ch in text.chars().filter(|ch| ch.is_alphabetic()) {
+        if let Some(script) = script_of(ch) {
+            *scripts.entry(script).or_default() += 1;
+        }
+    }
+    let letters: usize = scripts.values().sum();
+    let kana = scripts.get(&Script::Kana).copied().unwrap_or(0);
+    let han = scripts.get(&Script::Han).copied().unwrap_or(0);
+    let (dominant, count) = scripts
+        .iter()
+        .map(|(script, count)| match script {
+            // Japanese mixes kana with kanji, so both count towards it
+            Script::Han | Script::Kana if kana > 0 => (Script::Kana, kana + han),
+            _ => (*script, *count),
+        })
+        .max_by_key(|(_, count)| *count)?;
+    let share = count as f32 / letters as f32;
+
+    let found = |language: &'static str, certainty: f32| DetectedLanguage {
+        language,
+        confidence: share * certainty,
+    };
+    match dominant {
+        Script::Kana => Some(found("ja", 1.0)),
+        Script::Han => Some(found("zh", 1.0)),
+        Script::Hangul => Some(found("ko", 1.0)),
+        Script::Unique(language) => Some(found(language, 1.0)),
+        Script::Arabic => Some(found(arabic_language(text), 0.9)),
+        Script::Cyrillic => {
+            let (language, certainty) = cyrillic_language(text);
+            Some(found(language, certainty))
+        }
+        Script::Devanagari => Some(
+            score_profiles(text, DEVANAGARI_PROFILES)
+                .map(|(language, certainty)| found(language, certainty))
+                .unwrap_or_else(|| found("hi", 0.5)),
+        ),
+        Script::Latin => score_profiles(text, LATIN_PROFILES)
+            .map(|(language, certainty)| found(language, certainty)),
+    }
+}
+
+/// Source language to report for `text`, detecting it when the request asked for `auto`
+///
+/// Stays `auto` when the detector finds no usable signal.
+pub(crate) fn resolve_source_lang(source_lang: &str, text: &str) -> String {
+    if !is_auto_source(source_lang) {
+        return normalize_lang(source_lang);
+    }
+    detect_language(text).map_or_else(
+        || normalize_lang(source_lang),
+        |detected| detected.language.to_string(),
+    )
+}
+
+fn script_of(ch: char) -> Option<Script> {
+    let script = match u32::from(ch) {
+        0x0041..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+        0x0370..=0x03FF => Script::Unique("el"),
+        0x0400..=0x04FF => Script::Cyrillic,
+        0x0590..=0x05FF => Script::Unique("he"),
+        0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0980..=0x09FF => Script::Unique("bn"),
+        0x0A80..=0x0AFF => Script::Unique("gu"),
+        0x0B80..=0x0BFF => Script::Unique("ta"),
+        0x0C00..=0x0C7F => Script::Unique("te"),
+        0x0D00..=0x0D7F => Script::Unique("ml"),
+        0x0E00..=0x0E7F => Script::Unique("th"),
+        0x10A0..=0x10FF => Script::Unique("ka"),
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+        0x3040..=0x30FF => Script::Kana,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF => Script::Han,
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn arabic_language(text: &str) -> &'static str {
+    if text.chars().any(|ch| "ٹڈڑںے".contains(ch)) {
+        "ur"
+    } else if text.chars().any(|ch| "پچژگکی".contains(ch)) {
+        "fa"
+    } else {
+        "ar"
+    }
+}
+
+fn cyrillic_language(text: &str) -> (&'static str, f32) {
+    let lower = text.to_lowercase();
+    let has = |letters: &str| lower.chars().any(|ch| letters.contains(ch));
+    if has("әғқңөұүһ") {
+        ("kk", 0.95)
+    } else if has("їєґі") {
+        ("uk", 0.95)
+    } else if has("ѓќѕ") {
+        ("mk", 0.95)
+    } else if has("ђћј") {
+        ("sr", 0.9)
+    } else if has("ыэё") {
+        ("ru", 0.9)
+    } else if has("ъ") {
+        ("bg", 0.7)
+    } else {
+        ("ru", 0.5)
+    }
+}
+
+/// Score `text` against stopword profiles and return the best language with its margin
+fn score_profiles(
+    text: &str,
+    profiles: &[(&'static str, &[&str], &str)],
+) -> Option<(&'static str, f32)> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|ch: char| !ch.is_alphabetic() && !is_combining_mark(ch))
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut scores: Vec<(&'static str, usize)> = profiles
+        .iter()
+        .map(|(language, stopwords, markers)| {
+            let stopword_hits = words.iter().filter(|word| stopwords.contains(word)).count();
+            let marker_hits = lower.chars().filter(|ch| markers.contains(*ch)).count();
+            (*language, 2 * stopword_hits + marker_hits)
+        })
+        .collect();
+    // Stable sort keeps the table order between tied languages
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    let (language, best) = scores[0];
+    if best == 0 {
+        return None;
+    }
+    let second = scores.get(1).map_or(0, |(_, score)| *score);
+    let margin = best as f32 / (best + second) as f32;
+    let evidence = (best as f32 / 6.0).min(1.0);
+    Some((language, margin * evidence))
+}
+
+/// Devanagari vowel signs and viramas are marks, not letters, but belong inside words
+fn is_combining_mark(ch: char) -> bool {
+    matches!(u32::from(ch), 0x0900..=0x0903 | 0x093A..=0x094F | 0x0962..=0x0963)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> &'static str {
+        detect_language(text)
+            .unwrap_or_else(|| panic!("no language detected for {text:?}"))
+            .language
+    }
+
+    #[test]
+    fn resolves_auto_source_to_the_detected_language() {
+        assert_eq!(
+            resolve_source_lang("auto", "Le chat est sur la table."),
+            "fr"
+        );
+        assert_eq!(resolve_source_lang("AUTO", "12345"), "auto");
+        assert_eq!(resolve_source_lang("en-US", "Le chat dort."), "en-us");
+    }
+
+    #[test]
+    fn detects_languages_with_their_own_script() {
+        assert_eq!(detect("こんにちは、世界"), "ja");
+        assert_eq!(detect("你好，世界"), "zh");
+        assert_eq!(detect("안녕하세요"), "ko");
+        assert_eq!(detect("Γεια σου κόσμε"), "el");
+        assert_eq!(detect("שלום עולם"), "he");
+        assert_eq!(detect("สวัสดีชาวโลก"), "th");
+    }
+
+    #[test]
+    fn separates_languages_sharing_a_script() {
+        assert_eq!(detect("Привет, как дела? Мы вышли."), "ru");
+        assert_eq!(detect("Привіт, як справи?"), "uk");
+        assert_eq!(detect("مرحبا بالعالم"), "ar");
+        assert_eq!(detect("سلام دنیا"), "fa");
+        assert_eq!(detect("यह मेरा घर है और वह उसका है"), "hi");
+    }
+
+    #[test]
+    fn scores_latin_script_languages_by_stopwords() {
+        assert_eq!(detect("The cat is on the table and it is sleeping."), "en");
+        assert_eq!(detect("Le chat est sur la table et il dort."), "fr");
+        assert_eq!(
+            detect("Der Hund ist nicht im Garten, und die Katze schläft."),
+            "de"
+        );
+        assert_eq!(
+            detect("¿Dónde está la biblioteca? Está cerca del parque."),
+            "es"
+        );
+        assert_eq!(
+            detect("Eu não sei onde está o livro que você comprou."),
+            "pt"
+        );
+        assert_eq!(
+            detect("Ik weet niet wat het is, maar het is een mooi huis."),
+            "nl"
+        );
+        assert_eq!(detect("Nie wiem, czy to jest dobry pomysł."), "pl");
+    }
+
+    #[test]
+    fn reports_confidence_and_no_signal() {
+        let strong = detect_language("The cat is on the table and it is sleeping.").unwrap();
+        let weak = detect_language("Taxi").map_or(0.0, |detected| detected.confidence);
+
+        assert!(strong.confidence > 0.5 && strong.confidence <= 1.0);
+        assert!(weak < strong.confidence);
+        assert_eq!(detect_language("1234 !?"), None);
+    }
+}
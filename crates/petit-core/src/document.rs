@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Whole-document translation
+//!
+//! A document format decides which parts of a file are translated; everything else is
+//! written back unchanged.
+
+use std::path::Path;
+
+use crate::markdown::translate_markdown;
+use crate::po::translate_po;
+use crate::resource::{ResourceFormat, translate_resource};
+use crate::subtitle::{SubtitleFormat, translate_subtitles};
+use crate::xliff::translate_xliff;
+use crate::{Error, PlaceholderMode, Result, TranslationOptions, Translator};
+
+/// How the input of a document translation is interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// Plain text, translated as a whole
+    #[default]
+    Text,
+    /// Markdown, translating prose and keeping code, links and structure
+    Markdown,
+    /// SubRip subtitles, translating cue text and keeping numbers and timings
+    Srt,
+    /// WebVTT subtitles, translating cue text and keeping the header, timings and settings
+    WebVtt,
+    /// Gettext catalogs, filling in untranslated entries as fuzzy
+    Po,
+    /// XLIFF 1.2 and 2.0, filling in missing targets for review
+    Xliff,
+    /// JSON i18n resources, translating string values only
+    Json,
+    /// YAML i18n resources, translating string values only
+    Yaml,
+}
+
+impl DocumentFormat {
+    /// Pick the format from a file extension, if it has a known one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::WebVtt),
+            "po" | "pot" => Some(Self::Po),
+            "xlf" | "xliff" => Some(Self::Xliff),
+            "json" => Some(Self::Json),
+            "yml" | "yaml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for DocumentFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "text" | "txt" => Ok(Self::Text),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "srt" => Ok(Self::Srt),
+            "vtt" | "webvtt" => Ok(Self::WebVtt),
+            "po" | "pot" | "gettext" => Ok(Self::Po),
+            "xliff" | "xlf" => Ok(Self::Xliff),
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            other => Err(Error::Config(format!(
+                "document format must be `text`, `markdown`, `srt`, `vtt`, `po`, `xliff`, `json` \
+                 or `yaml`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// Translate a whole document in the given format
+///
+/// Formats other than plain text protect placeholders in `warn` mode unless `options` sets
+/// a mode.
+pub fn translate_document(
+    translator: &dyn Translator,
+    format: DocumentFormat,
+    source: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let options = &match format {
+        DocumentFormat::Text => options.clone(),
+        _ => document_options(options),
+    };
+    match format {
+        DocumentFormat::Text => translator
+            .translate_with_options(source, source_lang, target_lang, options)
+            .map(|output| output.text),
+        DocumentFormat::Markdown => {
+            translate_markdown(translator, source, source_lang, target_lang, options)
+        }
+        DocumentFormat::Srt => translate_subtitles(
+            translator,
+            SubtitleFormat::Srt,
+            source,
+            source_lang,
+            target_lang,
+            options,
+        ),
+        DocumentFormat::WebVtt => translate_subtitles(
+            translator,
+            SubtitleFormat::WebVtt,
+            source,
+            source_lang,
+            target_lang,
+            options,
+        ),
+        DocumentFormat::Po => translate_po(translator, source, source_lang, target_lang, options),
+        DocumentFormat::Xliff => {
+            translate_xliff(translator, source, source_lang, target_lang, options)
+        }
+        DocumentFormat::Json | DocumentFormat::Yaml => update_document(
+            translator,
+            format,
+            source,
+            None,
+            source_lang,
+            target_lang,
+            options,
+        ),
+    }
+}
+
+/// Translate a document, keeping the strings `existing`, an earlier translation of it, has
+///
+/// Only JSON and YAML resources look at `existing`; the other formats carry their
+/// translations in the source file itself, or are translated in full.
+pub fn update_document(
+    translator: &dyn Translator,
+    format: DocumentFormat,
+    source: &str,
+    existing: Option<&str>,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let resource = match format {
+        DocumentFormat::Json => ResourceFormat::Json,
+        DocumentFormat::Yaml => ResourceFormat::Yaml,
+        _ => {
+            return translate_document(
+                translator,
+                format,
+                source,
+                source_lang,
+                target_lang,
+                options,
+            );
+        }
+    };
+    translate_resource(
+        translator,
+        resource,
+        source,
+        existing,
+        source_lang,
+        target_lang,
+        &document_options(options),
+    )
+}
+
+/// `options` with placeholders protected unless they set a mode
+fn document_options(options: &TranslationOptions) -> TranslationOptions {
+    let mut options = options.clone();
+    options.placeholders.get_or_insert(PlaceholderMode::Warn);
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Recorder;
+
+    #[test]
+    fn parses_names_and_extensions() {
+        assert_eq!(
+            " Markdown ".parse::<DocumentFormat>().unwrap(),
+            DocumentFormat::Markdown
+        );
+        assert_eq!(
+            "txt".parse::<DocumentFormat>().unwrap(),
+            DocumentFormat::Text
+        );
+        assert!(matches!(
+            "docx".parse::<DocumentFormat>(),
+            Err(Error::Config(_))
+        ));
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("docs/README.MD")),
+            Some(DocumentFormat::Markdown)
+        );
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("episode.fr.vtt")),
+            Some(DocumentFormat::WebVtt)
+        );
+        assert_eq!(
+            "webvtt".parse::<DocumentFormat>().unwrap(),
+            DocumentFormat::WebVtt
+        );
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("locale/app.pot")),
+            Some(DocumentFormat::Po)
+        );
+        assert_eq!(DocumentFormat::from_path(Path::new("notes")), None);
+    }
+
+    #[test]
+    fn documents_protect_placeholders_unless_options_say_otherwise() {
+        let mode = |format, options: &TranslationOptions| {
+            let recorder = Recorder::default();
+            translate_document(&recorder, format, "Delete %s?", "en", "fr", options)
+                .expect("document should translate");
+            recorder.options()[0].1.placeholder_mode()
+        };
+        let defaults = TranslationOptions::default();
+        let off = TranslationOptions::new().with_placeholders(PlaceholderMode::Off);
+
+        assert_eq!(mode(DocumentFormat::Text, &defaults), PlaceholderMode::Off);
+        assert_eq!(
+            mode(DocumentFormat::Markdown, &defaults),
+            PlaceholderMode::Warn
+        );
+        assert_eq!(mode(DocumentFormat::Markdown, &off), PlaceholderMode::Off);
+    }
+}
@@ -43,6 +43,18 @@ pub enum Error {
     #[error("Inference error: {0}")]
     Inference(String),
 
+    /// Translation was cancelled before it finished
+    #[error("Translation cancelled")]
+    Cancelled,
+
+    /// Placeholder missing or duplicated in a translation
+    #[error("Placeholder error: {0}")]
+    Placeholder(String),
+
+    /// Document that cannot be parsed in the requested format
+    #[error("Document error: {0}")]
+    Document(String),
+
     /// Unsupported language
     #[error("Unsupported language: {0}")]
     UnsupportedLanguage(String),
@@ -2,13 +2,42 @@
 
 //! GemmaTranslator implementation for TranslateGemma models
 
+use crate::chunking::split_into_chunks;
+use crate::detect::resolve_source_lang;
 use crate::language::{is_auto_source, normalize_lang, supported_languages, validate_pair};
-use crate::{Config, GlossaryCandidate, GlossaryStore, ModelManager, Result, Translator};
+use crate::protect::{StreamRestorer, marker_options, protect_with_mode};
+use crate::{
+    CancellationToken, Config, Error, Formality, Generation, GlossaryCandidate, GlossaryStore,
+    ModelManager, PlaceholderMode, ProtectedText, Result, TranslationOptions, TranslationOutput,
+    TranslationRequest, Translator,
+};
+use std::collections::BTreeMap;
 
 /// Default maximum tokens for translation output
 const DEFAULT_MAX_NEW_TOKENS: u32 = 256;
+/// Default number of requests decoded together by `translate_batch`
+const DEFAULT_MAX_BATCH_SIZE: usize = 8;
+/// Prompt tokens reserved per glossary line when sizing chunks
+const GLOSSARY_TOKENS_PER_MATCH: usize = 24;
 const TRANSLATION_ONLY_INSTRUCTION: &str = "Return only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.";
 
+fn append_options(prompt: &mut String, options: &TranslationOptions) {
+    match options.formality {
+        Some(Formality::Formal) => prompt.push_str("Use a formal register.\n"),
+        Some(Formality::Informal) => prompt.push_str("Use an informal register.\n"),
+        None => {}
+    }
+    if let Some(domain) = options.domain_hint() {
+        prompt.push_str(&format!(
+            "Domain: {domain}. Use the terminology and conventions of this domain.\n"
+        ));
+    }
+    if let Some(instructions) = options.extra_instructions() {
+        prompt.push_str(instructions);
+        prompt.push('\n');
+    }
+}
+
 fn append_glossary_terms(prompt: &mut String, glossary_terms: &[(&str, &str)]) {
     if glossary_terms.is_empty() {
         return;
@@ -25,10 +54,12 @@ fn build_explicit_prompt(
     source_lang: &str,
     target_lang: &str,
     glossary_terms: &[(&str, &str)],
+    options: &TranslationOptions,
 ) -> String {
     let mut prompt = format!(
         "<start_of_turn>user\n[{source_lang}->{target_lang}]\n{TRANSLATION_ONLY_INSTRUCTION}\n"
     );
+    append_options(&mut prompt, options);
     append_glossary_terms(&mut prompt, glossary_terms);
     prompt.push_str("\nText:\n");
     prompt.push_str(text);
@@ -36,10 +67,16 @@ fn build_explicit_prompt(
     prompt
 }
 
-fn build_auto_prompt(text: &str, target_lang: &str, glossary_terms: &[(&str, &str)]) -> String {
+fn build_auto_prompt(
+    text: &str,
+    target_lang: &str,
+    glossary_terms: &[(&str, &str)],
+    options: &TranslationOptions,
+) -> String {
     let mut prompt = format!(
         "<start_of_turn>user\nTranslate the text below into {target_lang}.\nInfer the source language from the text itself.\n{TRANSLATION_ONLY_INSTRUCTION}\n"
     );
+    append_options(&mut prompt, options);
     append_glossary_terms(&mut prompt, glossary_terms);
     prompt.push_str("\nText:\n");
     prompt.push_str(text);
@@ -52,27 +89,53 @@ fn build_prompt(
     source_lang: &str,
     target_lang: &str,
     glossary_terms: &[(&str, &str)],
+    options: &TranslationOptions,
 ) -> String {
     let src = normalize_lang(source_lang);
     let tgt = normalize_lang(target_lang);
     if is_auto_source(&src) {
-        return build_auto_prompt(text, &tgt, glossary_terms);
+        return build_auto_prompt(text, &tgt, glossary_terms, options);
     }
 
-    build_explicit_prompt(text, &src, &tgt, glossary_terms)
+    build_explicit_prompt(text, &src, &tgt, glossary_terms, options)
+}
+
+/// Put the request's own glossary terms ahead of the store matches
+///
+/// Store matches for a source term the request already fixes are dropped.
+fn with_request_terms(
+    options: &TranslationOptions,
+    looked_up: Vec<GlossaryCandidate>,
+) -> Vec<GlossaryCandidate> {
+    let mut candidates = options.glossary_terms.clone();
+    for candidate in looked_up {
+        if !candidates
+            .iter()
+            .any(|term| term.source_term.to_lowercase() == candidate.source_term.to_lowercase())
+        {
+            candidates.push(candidate);
+        }
+    }
+    candidates
 }
 
 fn build_prompt_with_lookup<F>(
     text: &str,
     source_lang: &str,
     target_lang: &str,
+    options: &TranslationOptions,
     lookup: F,
 ) -> Result<String>
 where
     F: FnOnce(&str, &str, &str) -> Result<Vec<GlossaryCandidate>>,
 {
     validate_pair(source_lang, target_lang)?;
-    let glossary_candidates = lookup(source_lang, target_lang, text)?;
+    let looked_up = if options.use_glossary() {
+        lookup(source_lang, target_lang, text)?
+    } else {
+        Vec::new()
+    };
+    let glossary_candidates = with_request_terms(options, looked_up);
     let glossary_terms = glossary_candidates
         .iter()
         .map(|candidate| {
@@ -87,14 +150,74 @@ where
         source_lang,
         target_lang,
         &glossary_terms,
+        options,
     ))
 }
 
+/// Build the prompt for `text` and return it with the glossary candidates it includes
+///
+/// Placeholders and markup are replaced with markers first; the returned
+/// [`ProtectedText`] puts them back into the output. Shared by every backend that sends
+/// TranslateGemma prompts.
+pub(crate) fn build_translation_prompt(
+    glossary_store: Option<&GlossaryStore>,
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<(String, Vec<GlossaryCandidate>, ProtectedText)> {
+    let protected = protect_with_mode(text, options.placeholder_mode());
+    let prompt_options = marker_options(options, protected.has_spans());
+    let mut selected = Vec::new();
+    let prompt = build_prompt_with_lookup(
+        &protected.text,
+        source_lang,
+        target_lang,
+        &prompt_options,
+        |source_lang, target_lang, text| {
+            let candidates = match glossary_store {
+                Some(glossary_store) => {
+                    glossary_store.select_candidates(source_lang, target_lang, text)?
+                }
+                None => Vec::new(),
+            };
+            selected.clone_from(&candidates);
+            Ok(candidates)
+        },
+    )?;
+    Ok((prompt, with_request_terms(options, selected), protected))
+}
+
+/// Clean the model output by stripping whitespace and any echo artifacts
+pub(crate) fn clean_output(output: &str) -> String {
+    let cleaned = output.trim();
+
+    // Remove any trailing <end_of_turn> if present
+    let cleaned = cleaned
+        .strip_suffix("<end_of_turn>")
+        .unwrap_or(cleaned)
+        .trim();
+
+    cleaned.to_string()
+}
+
+/// Clean the model output and put the protected spans back
+///
+/// Returns the translation with any placeholder warnings.
+pub(crate) fn restore_output(
+    output: &str,
+    protected: &ProtectedText,
+    options: &TranslationOptions,
+) -> Result<(String, Vec<String>)> {
+    protected.restore(&clean_output(output), options.placeholder_mode())
+}
+
 /// TranslateGemma-based translator using llama.cpp
 pub struct GemmaTranslator {
     model_manager: ModelManager,
     glossary_store: Option<GlossaryStore>,
     max_new_tokens: u32,
+    max_batch_size: usize,
 }
 
 impl GemmaTranslator {
@@ -106,6 +229,7 @@ impl GemmaTranslator {
             model_manager,
             glossary_store,
             max_new_tokens: DEFAULT_MAX_NEW_TOKENS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
         })
     }
 
@@ -115,6 +239,7 @@ impl GemmaTranslator {
             model_manager,
             glossary_store: None,
             max_new_tokens: DEFAULT_MAX_NEW_TOKENS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
         }
     }
 
@@ -124,39 +249,290 @@ impl GemmaTranslator {
         self
     }
 
-    /// Clean the model output by stripping whitespace and any echo artifacts
-    fn clean_output(&self, output: &str) -> String {
-        let cleaned = output.trim();
+    /// Set how many requests `translate_batch` decodes together
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
 
-        // Remove any trailing <end_of_turn> if present
-        let cleaned = cleaned
-            .strip_suffix("<end_of_turn>")
-            .unwrap_or(cleaned)
-            .trim();
+    /// Get the model manager used for inference
+    pub fn model_manager(&self) -> &ModelManager {
+        &self.model_manager
+    }
 
-        cleaned.to_string()
+    /// Turn a generation into a translation output with cleaned and restored text
+    ///
+    /// `source_lang` is the reported source, already resolved when the request used `auto`.
+    fn to_output(
+        &self,
+        generation: Generation,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+        glossary_candidates: Vec<GlossaryCandidate>,
+        protected: &ProtectedText,
+    ) -> Result<TranslationOutput> {
+        let (text, warnings) = restore_output(&generation.text, protected, options)?;
+        Ok(TranslationOutput {
+            text,
+            source_lang: source_lang.to_string(),
+            target_lang: normalize_lang(target_lang),
+            glossary_candidates,
+            stats: generation.stats,
+            warnings,
+        })
     }
 }
 
-impl Translator for GemmaTranslator {
-    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> Result<String> {
-        let prompt = build_prompt_with_lookup(
+impl GemmaTranslator {
+    /// Generation limit for a request, preferring the per-request override
+    fn max_new_tokens_for(&self, options: &TranslationOptions) -> u32 {
+        options.max_new_tokens.unwrap_or(self.max_new_tokens)
+    }
+
+    /// Maximum source tokens per chunk for this language pair and options
+    ///
+    /// Chunks must fit the context next to the prompt template, glossary block and
+    /// generated tokens. They are also kept below `max_new_tokens`, since a translation
+    /// is usually about as long as its source.
+    fn chunk_token_budget(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<usize> {
+        // Assume the text has markers, so their instruction fits too
+        let protects = options.placeholder_mode() != PlaceholderMode::Off;
+        let template_options = marker_options(options, protects);
+        let template = build_prompt("", source_lang, target_lang, &[], &template_options);
+        // The template count excludes BOS
+        let mut overhead = self.model_manager.count_tokens(&template)? + 1;
+        if let Some(glossary_store) = &self.glossary_store
+            && options.use_glossary()
+        {
+            overhead += GLOSSARY_TOKENS_PER_MATCH * (glossary_store.max_matches() + 1);
+        }
+        overhead += GLOSSARY_TOKENS_PER_MATCH * options.glossary_terms.len();
+
+        let max_new_tokens = self.max_new_tokens_for(options) as usize;
+        let context_size = self.model_manager.config().context_size as usize;
+        let available = context_size.saturating_sub(overhead + max_new_tokens);
+        if available == 0 {
+            return Err(Error::Config(format!(
+                "context_size {context_size} leaves no room for input text with max_new_tokens {max_new_tokens}"
+            )));
+        }
+
+        Ok(available.min(max_new_tokens * 3 / 4).max(1))
+    }
+
+    /// Build the prompt for a batched request, or `None` when the text needs chunking
+    fn prepare_batch_prompt(
+        &self,
+        request: &TranslationRequest,
+    ) -> Result<Option<(String, Vec<GlossaryCandidate>, ProtectedText)>> {
+        validate_pair(&request.source_lang, &request.target_lang)?;
+        request.options.validate()?;
+        let budget =
+            self.chunk_token_budget(&request.source_lang, &request.target_lang, &request.options)?;
+        if self.model_manager.count_tokens(&request.text)? > budget {
+            return Ok(None);
+        }
+        build_translation_prompt(
+            self.glossary_store.as_ref(),
+            &request.text,
+            &request.source_lang,
+            &request.target_lang,
+            &request.options,
+        )
+        .map(Some)
+    }
+
+    /// Translate text that fits in a single prompt
+    fn translate_chunk(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+        cancel: &CancellationToken,
+        on_piece: &mut dyn FnMut(&str),
+    ) -> Result<TranslationOutput> {
+        let (prompt, glossary_candidates, protected) = build_translation_prompt(
+            self.glossary_store.as_ref(),
             text,
             source_lang,
             target_lang,
-            |source_lang, target_lang, text| match &self.glossary_store {
-                Some(glossary_store) => {
-                    glossary_store.select_candidates(source_lang, target_lang, text)
+            options,
+        )?;
+
+        // Run inference, forwarding pieces as they are generated with markers restored
+        let mut restorer = StreamRestorer::new(&protected.spans);
+        let generation = self.model_manager.infer_streaming(
+            &prompt,
+            self.max_new_tokens_for(options),
+            cancel,
+            &mut |piece: &str| {
+                let ready = restorer.push(piece);
+                if !ready.is_empty() {
+                    on_piece(&ready);
                 }
-                None => Ok(Vec::new()),
             },
         )?;
+        let held_back = restorer.finish();
+        if !held_back.is_empty() {
+            on_piece(&held_back);
+        }
 
-        // Run inference
-        let output = self.model_manager.infer(&prompt, self.max_new_tokens)?;
+        // Clean, restore and return
+        self.to_output(
+            generation,
+            &resolve_source_lang(source_lang, text),
+            target_lang,
+            options,
+            glossary_candidates,
+            &protected,
+        )
+    }
+}
 
-        // Clean and return
-        Ok(self.clean_output(&output))
+impl Translator for GemmaTranslator {
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<TranslationOutput> {
+        self.translate_streaming(
+            text,
+            source_lang,
+            target_lang,
+            options,
+            &CancellationToken::new(),
+            &mut |_| {},
+        )
+    }
+
+    fn translate_streaming(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+        cancel: &CancellationToken,
+        on_piece: &mut dyn FnMut(&str),
+    ) -> Result<TranslationOutput> {
+        validate_pair(source_lang, target_lang)?;
+        options.validate()?;
+
+        // Split long inputs at paragraph and sentence boundaries
+        let budget = self.chunk_token_budget(source_lang, target_lang, options)?;
+        let chunks =
+            split_into_chunks(text, budget, |chunk| self.model_manager.count_tokens(chunk))?;
+        if chunks.len() <= 1 {
+            return self.translate_chunk(text, source_lang, target_lang, options, cancel, on_piece);
+        }
+
+        // Translate chunk by chunk and restore the original layout between them
+        let mut output = TranslationOutput::from_text(
+            String::new(),
+            resolve_source_lang(source_lang, text),
+            normalize_lang(target_lang),
+        );
+        for chunk in &chunks {
+            let translated = self.translate_chunk(
+                &chunk.text,
+                source_lang,
+                target_lang,
+                options,
+                cancel,
+                on_piece,
+            )?;
+            output.text.push_str(&translated.text);
+            output.text.push_str(&chunk.separator);
+            on_piece(&chunk.separator);
+            output.stats.merge(&translated.stats);
+            output.warnings.extend(translated.warnings);
+            for candidate in translated.glossary_candidates {
+                if !output.glossary_candidates.contains(&candidate) {
+                    output.glossary_candidates.push(candidate);
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    fn translate_batch(&self, requests: &[TranslationRequest]) -> Vec<Result<TranslationOutput>> {
+        let mut results: Vec<Option<Result<TranslationOutput>>> =
+            Vec::with_capacity(requests.len());
+        // Requests decoded together must share a generation limit
+        let mut batched: BTreeMap<u32, Vec<_>> = BTreeMap::new();
+
+        // Short requests share multi-sequence decoding; long ones go through chunking
+        for (index, request) in requests.iter().enumerate() {
+            match self.prepare_batch_prompt(request) {
+                Ok(Some((prompt, glossary_candidates, protected))) => {
+                    batched
+                        .entry(self.max_new_tokens_for(&request.options))
+                        .or_default()
+                        .push((index, prompt, glossary_candidates, protected));
+                    results.push(None);
+                }
+                Ok(None) => results.push(Some(self.translate_with_options(
+                    &request.text,
+                    &request.source_lang,
+                    &request.target_lang,
+                    &request.options,
+                ))),
+                Err(err) => results.push(Some(Err(err))),
+            }
+        }
+
+        let groups = batched.iter().flat_map(|(max_new_tokens, entries)| {
+            entries
+                .chunks(self.max_batch_size)
+                .map(move |group| (*max_new_tokens, group))
+        });
+        for (max_new_tokens, group) in groups {
+            let prompts: Vec<&str> = group
+                .iter()
+                .map(|(_, prompt, _, _)| prompt.as_str())
+                .collect();
+            match self.model_manager.infer_batch(
+                &prompts,
+                max_new_tokens,
+                &CancellationToken::new(),
+            ) {
+                Ok(generations) => {
+                    for ((index, _, glossary_candidates, protected), generation) in
+                        group.iter().zip(generations)
+                    {
+                        let request = &requests[*index];
+                        results[*index] = Some(self.to_output(
+                            generation,
+                            &resolve_source_lang(&request.source_lang, &request.text),
+                            &request.target_lang,
+                            &request.options,
+                            glossary_candidates.clone(),
+                            protected,
+                        ));
+                    }
+                }
+                Err(err) => {
+                    for (index, _, _, _) in group {
+                        results[*index] = Some(Err(Error::Inference(err.to_string())));
+                    }
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(Error::Inference("Missing batch result".to_string())))
+            })
+            .collect()
     }
 
     fn supported_languages(&self) -> &[&str] {
@@ -172,7 +548,13 @@ mod tests {
 
     #[test]
     fn test_build_prompt_simple() {
-        let prompt = build_prompt("Hello, how are you?", "en", "fr", &[]);
+        let prompt = build_prompt(
+            "Hello, how are you?",
+            "en",
+            "fr",
+            &[],
+            &TranslationOptions::default(),
+        );
         assert_eq!(
             prompt,
             "<start_of_turn>user\n[en->fr]\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\n\nText:\nHello, how are you?<end_of_turn>\n<start_of_turn>model\n"
@@ -184,7 +566,7 @@ mod tests {
         let src = normalize_lang("EN");
         let tgt = normalize_lang("FR");
         let text = "Hello";
-        let prompt = build_prompt(text, &src, &tgt, &[]);
+        let prompt = build_prompt(text, &src, &tgt, &[], &TranslationOptions::default());
         assert_eq!(
             prompt,
             "<start_of_turn>user\n[en->fr]\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\n\nText:\nHello<end_of_turn>\n<start_of_turn>model\n"
@@ -196,7 +578,7 @@ mod tests {
         let src = normalize_lang("en-US");
         let tgt = normalize_lang("pt-BR");
         let text = "Good morning";
-        let prompt = build_prompt(text, &src, &tgt, &[]);
+        let prompt = build_prompt(text, &src, &tgt, &[], &TranslationOptions::default());
         assert_eq!(
             prompt,
             "<start_of_turn>user\n[en-us->pt-br]\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\n\nText:\nGood morning<end_of_turn>\n<start_of_turn>model\n"
@@ -205,7 +587,7 @@ mod tests {
 
     #[test]
     fn test_build_prompt_auto_without_glossary() {
-        let prompt = build_prompt("Hello", "auto", "fr", &[]);
+        let prompt = build_prompt("Hello", "auto", "fr", &[], &TranslationOptions::default());
         assert_eq!(
             prompt,
             "<start_of_turn>user\nTranslate the text below into fr.\nInfer the source language from the text itself.\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\n\nText:\nHello<end_of_turn>\n<start_of_turn>model\n"
@@ -214,7 +596,7 @@ mod tests {
 
     #[test]
     fn test_build_prompt_without_glossary_matches_current_format() {
-        let prompt = build_prompt("Hello", "en", "fr", &[]);
+        let prompt = build_prompt("Hello", "en", "fr", &[], &TranslationOptions::default());
         assert_eq!(
             prompt,
             "<start_of_turn>user\n[en->fr]\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\n\nText:\nHello<end_of_turn>\n<start_of_turn>model\n"
@@ -231,6 +613,7 @@ mod tests {
                 ("account balance", "solde du compte"),
                 ("savings account", "compte d'epargne"),
             ],
+            &TranslationOptions::default(),
         );
 
         assert_eq!(
@@ -249,6 +632,7 @@ mod tests {
                 ("account balance", "solde du compte"),
                 ("savings account", "compte d'epargne"),
             ],
+            &TranslationOptions::default(),
         );
 
         assert_eq!(
@@ -265,6 +649,7 @@ mod tests {
             "Your balance is available in the savings account.",
             "en",
             "fr",
+            &TranslationOptions::default(),
             move |source_lang, target_lang, text| {
                 assert_eq!(source_lang, "en");
                 assert_eq!(target_lang, "fr");
@@ -299,6 +684,7 @@ mod tests {
             "Hello",
             "en",
             "fr",
+            &TranslationOptions::default(),
             move |source_lang, target_lang, text| {
                 assert_eq!(source_lang, "en");
                 assert_eq!(target_lang, "fr");
@@ -324,6 +710,7 @@ mod tests {
             "Your balance is available in the savings account.",
             "auto",
             "fr",
+            &TranslationOptions::default(),
             move |source_lang, target_lang, text| {
                 assert_eq!(source_lang, "auto");
                 assert_eq!(target_lang, "fr");
@@ -358,6 +745,7 @@ mod tests {
             "Hello",
             "xx",
             "fr",
+            &TranslationOptions::default(),
             move |_source_lang, _target_lang, _text| {
                 lookup_calls_ref.set(lookup_calls_ref.get() + 1);
                 Ok(vec![GlossaryCandidate {
@@ -372,6 +760,117 @@ mod tests {
         assert!(err.to_string().contains("Unsupported language"));
     }
 
+    #[test]
+    fn test_build_prompt_renders_options_before_glossary() {
+        let options = TranslationOptions::new()
+            .with_formality(Formality::Formal)
+            .with_domain("legal")
+            .with_instructions("Keep defined terms capitalized.");
+        let prompt = build_prompt(
+            "The Tenant shall pay rent.",
+            "en",
+            "de",
+            &[("Tenant", "Mieter")],
+            &options,
+        );
+
+        assert_eq!(
+            prompt,
+            "<start_of_turn>user\n[en->de]\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\nUse a formal register.\nDomain: legal. Use the terminology and conventions of this domain.\nKeep defined terms capitalized.\nUse the glossary terms exactly when they match the source text:\n- Tenant -> Mieter\n\nText:\nThe Tenant shall pay rent.<end_of_turn>\n<start_of_turn>model\n"
+        );
+    }
+
+    #[test]
+    fn test_build_prompt_auto_source_renders_options() {
+        let options = TranslationOptions::new()
+            .with_formality(Formality::Informal)
+            .with_domain("UI strings");
+        let prompt = build_prompt("Save changes?", "auto", "fr", &[], &options);
+
+        assert_eq!(
+            prompt,
+            "<start_of_turn>user\nTranslate the text below into fr.\nInfer the source language from the text itself.\nReturn only the translation of source text.\nDo not explain the source language.\nDo not add notes, quotes, or extra formatting.\nUse an informal register.\nDomain: UI strings. Use the terminology and conventions of this domain.\n\nText:\nSave changes?<end_of_turn>\n<start_of_turn>model\n"
+        );
+    }
+
+    #[test]
+    fn test_glossary_override_skips_lookup() {
+        let lookup_calls = Rc::new(Cell::new(0));
+        let lookup_calls_ref = Rc::clone(&lookup_calls);
+        let prompt = build_prompt_with_lookup(
+            "Hello",
+            "en",
+            "fr",
+            &TranslationOptions::new().with_glossary(false),
+            move |_source_lang, _target_lang, _text| {
+                lookup_calls_ref.set(lookup_calls_ref.get() + 1);
+                Ok(vec![GlossaryCandidate {
+                    source_term: "hello".into(),
+                    target_term: "bonjour".into(),
+                }])
+            },
+        )
+        .expect("translation should build a prompt");
+
+        assert_eq!(lookup_calls.get(), 0);
+        assert!(!prompt.contains("glossary"));
+    }
+
+    #[test]
+    fn test_request_glossary_terms_take_precedence() {
+        let options = TranslationOptions::new().with_glossary_terms(vec![GlossaryCandidate {
+            source_term: "Balance".into(),
+            target_term: "Kontostand".into(),
+        }]);
+        let prompt = build_prompt_with_lookup("Your balance", "en", "de", &options, |_, _, _| {
+            Ok(vec![
+                GlossaryCandidate {
+                    source_term: "balance".into(),
+                    target_term: "Saldo".into(),
+                },
+                GlossaryCandidate {
+                    source_term: "your".into(),
+                    target_term: "Ihr".into(),
+                },
+            ])
+        })
+        .expect("translation should build a prompt");
+
+        assert!(prompt.contains("- Balance -> Kontostand\n- your -> Ihr\n"));
+        assert!(!prompt.contains("Saldo"));
+    }
+
+    #[test]
+    fn test_translation_prompt_masks_placeholders() {
+        let options = TranslationOptions::new().with_placeholders(PlaceholderMode::Warn);
+        let (prompt, _, protected) =
+            build_translation_prompt(None, "Delete %s from <b>trash</b>?", "en", "fr", &options)
+                .expect("translation should build a prompt");
+
+        assert!(prompt.contains("Text:\nDelete ⟦0⟧ from ⟦1⟧trash⟦2⟧?<end_of_turn>"));
+        assert!(prompt.contains("Copy every marker like ⟦0⟧"));
+
+        let (text, warnings) = restore_output(
+            "Supprimer ⟦0⟧ de la ⟦1⟧corbeille⟦2⟧ ?<end_of_turn>",
+            &protected,
+            &options,
+        )
+        .expect("markers should be restored");
+        assert_eq!(text, "Supprimer %s de la <b>corbeille</b> ?");
+        assert!(warnings.is_empty());
+
+        // Protection is opt-in, so the default prompt is the one without markers
+        let defaults = TranslationOptions::default();
+        let (prompt, _, protected) =
+            build_translation_prompt(None, "Delete %s?", "en", "fr", &defaults)
+                .expect("translation should build a prompt");
+        assert_eq!(
+            prompt,
+            build_prompt("Delete %s?", "en", "fr", &[], &defaults)
+        );
+        assert!(!protected.has_spans());
+    }
+
     #[test]
     fn test_clean_output_simple() {
         let output = "  Bonjour, comment allez-vous?  ";
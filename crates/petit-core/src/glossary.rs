@@ -12,6 +12,7 @@ use fastembed::{
 };
 use hnsw_rs::anndists::dist::distances::DistCosine;
 use hnsw_rs::hnsw::Hnsw;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
@@ -34,7 +35,7 @@ const EMBEDDING_TOKENIZER_CONFIG_FILE: &str = "tokenizer_config.json";
 
 type LangPairKey = (String, String);
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GlossaryCandidate {
     pub source_term: String,
     pub target_term: String,
@@ -69,7 +70,8 @@ struct GlossaryRow {
 
 struct PairGlossaryIndex {
     entries: Vec<GlossaryEntry>,
-    hnsw: Hnsw<'static, f32, DistCosine>,
+    /// Semantic index, absent for exact-match-only stores
+    hnsw: Option<Hnsw<'static, f32, DistCosine>>,
 }
 
 struct GlossaryEntry {
@@ -157,6 +159,75 @@ impl GlossaryStore {
         build_store_from_rows(rows, provider, config.max_matches)
     }
 
+    /// Build an exact-match-only store for one language pair
+    ///
+    /// No embedding model is loaded; a term matches when it appears in the source text,
+    /// ignoring case and whitespace runs. Used for glossaries supplied at runtime.
+    pub fn from_terms<I, S, T>(
+        source_lang: &str,
+        target_lang: &str,
+        terms: I,
+        max_matches: usize,
+    ) -> Result<Self>
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        for (source_term, target_term) in terms {
+            let source_term = source_term.into().trim().to_string();
+            let target_term = target_term.into().trim().to_string();
+            if source_term.is_empty() || target_term.is_empty() {
+                return Err(Error::GlossaryParse(
+                    "glossary terms must not be empty".to_string(),
+                ));
+            }
+            let source_term_norm = normalize_source_text(&source_term);
+            if seen.insert((source_term_norm.clone(), target_term.clone())) {
+                entries.push(GlossaryEntry {
+                    source_term,
+                    target_term,
+                    source_term_norm,
+                });
+            }
+        }
+
+        let mut pair_indices = HashMap::new();
+        if !entries.is_empty() {
+            pair_indices.insert(
+                lang_pair_key(source_lang, target_lang),
+                PairGlossaryIndex {
+                    entries,
+                    hnsw: None,
+                },
+            );
+        }
+        Ok(Self {
+            pair_indices,
+            max_matches,
+            provider: None,
+        })
+    }
+
+    /// Number of terms across every language pair
+    pub fn len(&self) -> usize {
+        self.pair_indices
+            .values()
+            .map(|index| index.entries.len())
+            .sum()
+    }
+
+    /// Whether the store holds no terms
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn max_matches(&self) -> usize {
+        self.max_matches
+    }
+
     pub fn select_candidates(
         &self,
         source_lang: &str,
@@ -167,11 +238,6 @@ impl GlossaryStore {
             return Ok(Vec::new());
         }
 
-        let provider = match &self.provider {
-            Some(provider) => provider,
-            None => return Ok(Vec::new()),
-        };
-
         let normalized_source_lang = normalize_lang(source_lang);
         let normalized_target_lang = normalize_lang(target_lang);
 
@@ -180,13 +246,20 @@ impl GlossaryStore {
             return Ok(Vec::new());
         }
 
-        let query_embedding = provider.embed_query(source_text)?;
+        if self.provider.is_none() && self.pair_indices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query_embedding = match &self.provider {
+            Some(provider) => Some(provider.embed_query(source_text)?),
+            None => None,
+        };
 
         if is_auto_source(&normalized_source_lang) {
             return self.select_candidates_for_auto_source(
                 &normalized_target_lang,
                 &normalized_source_text,
-                query_embedding.as_slice(),
+                query_embedding.as_deref(),
             );
         }
 
@@ -199,7 +272,7 @@ impl GlossaryStore {
         let ranked_candidates = collect_ranked_candidates(
             index,
             normalized_source_text.as_str(),
-            query_embedding.as_slice(),
+            query_embedding.as_deref(),
         )?;
 
         Ok(shortlist_ranked_candidates(
@@ -212,7 +285,7 @@ impl GlossaryStore {
         &self,
         target_lang: &str,
         normalized_source_text: &str,
-        query_embedding: &[f32],
+        query_embedding: Option<&[f32]>,
     ) -> Result<Vec<GlossaryCandidate>> {
         let mut matching_pairs = self
             .pair_indices
@@ -252,7 +325,7 @@ impl GlossaryStore {
 fn collect_ranked_candidates(
     index: &PairGlossaryIndex,
     normalized_source_text: &str,
-    query_embedding: &[f32],
+    query_embedding: Option<&[f32]>,
 ) -> Result<Vec<RankedCandidate>> {
     let mut exact = Vec::new();
     for entry in &index.entries {
@@ -263,10 +336,12 @@ fn collect_ranked_candidates(
     exact.sort_by(compare_exact_candidates);
 
     let mut ann = Vec::new();
-    if !index.entries.is_empty() {
+    if let (Some(hnsw), Some(query_embedding)) = (&index.hnsw, query_embedding)
+        && !index.entries.is_empty()
+    {
         let knbn = ANN_SEARCH_K.min(index.entries.len()).max(1);
         let ef = ANN_SEARCH_EF.max(knbn + 1);
-        let neighbours = index.hnsw.search(query_embedding, knbn, ef);
+        let neighbours = hnsw.search(query_embedding, knbn, ef);
 
         for neighbour in neighbours {
             let origin_id = neighbour.get_origin_id();
@@ -455,7 +530,13 @@ where
         }
         hnsw.set_searching_mode(true);
 
-        pair_indices.insert(pair, PairGlossaryIndex { entries, hnsw });
+        pair_indices.insert(
+            pair,
+            PairGlossaryIndex {
+                entries,
+                hnsw: Some(hnsw),
+            },
+        );
     }
 
     let provider: Arc<dyn EmbeddingProvider> = provider;
@@ -1202,4 +1283,40 @@ en\tfr\taccount balance\tsolde du compte\n",
             Error::GlossaryIndexBuild(message) if message == "embedding dimensions mismatch"
         ));
     }
+
+    #[test]
+    fn glossary_from_terms_matches_exact_terms_without_embeddings() {
+        let store = GlossaryStore::from_terms(
+            "EN",
+            "DE",
+            [("Account balance", "Kontostand"), ("invoice", "Rechnung")],
+            4,
+        )
+        .expect("terms should build a store");
+
+        let candidates = store
+            .select_candidates("en", "de", "Check the account   balance first.")
+            .expect("lookup should succeed");
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            candidates,
+            vec![GlossaryCandidate {
+                source_term: "Account balance".into(),
+                target_term: "Kontostand".into(),
+            }]
+        );
+        assert!(
+            store
+                .select_candidates("en", "fr", "account balance")
+                .expect("other pairs should not fail")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn glossary_from_terms_rejects_empty_terms() {
+        let err = GlossaryStore::from_terms("en", "de", [("invoice", " ")], 4)
+            .expect_err("empty target term should fail");
+        assert!(matches!(err, Error::GlossaryParse(_)));
+    }
 }
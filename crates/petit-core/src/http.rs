@@ -0,0 +1,505 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Translator backed by a self-hosted OpenAI-compatible server
+//!
+//! `HttpTranslator` builds the same TranslateGemma prompt as `GemmaTranslator` and sends it
+//! to `/v1/completions` or `/v1/chat/completions` of a server such as llama-server or vLLM.
+
+use crate::config::{HttpApi, HttpConfig, SamplingConfig};
+use crate::detect::resolve_source_lang;
+use crate::gemma::{build_translation_prompt, restore_output};
+use crate::language::{normalize_lang, supported_languages, validate_pair};
+use crate::{
+    Error, GenerationStats, GlossaryStore, Result, StopReason, TranslationOptions,
+    TranslationOutput, Translator,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default maximum tokens to generate per request
+const DEFAULT_MAX_NEW_TOKENS: u32 = 256;
+
+/// Token that ends the model turn in TranslateGemma prompts
+const END_OF_TURN: &str = "<end_of_turn>";
+
+/// Prefix of the user turn in TranslateGemma prompts
+const USER_TURN_PREFIX: &str = "<start_of_turn>user\n";
+
+/// Suffix that closes the user turn and opens the model turn
+const MODEL_TURN_SUFFIX: &str = "<end_of_turn>\n<start_of_turn>model\n";
+
+/// Translator that calls an OpenAI-compatible HTTP server
+pub struct HttpTranslator {
+    config: HttpConfig,
+    agent: ureq::Agent,
+    endpoint: String,
+    sampling: SamplingConfig,
+    glossary_store: Option<GlossaryStore>,
+    max_new_tokens: u32,
+}
+
+impl HttpTranslator {
+    /// Create a translator for the server described by `config`
+    ///
+    /// No request is sent until the first translation.
+    pub fn new(config: HttpConfig) -> Result<Self> {
+        config.validate()?;
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(config.connect_timeout_secs))
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build();
+        let endpoint = endpoint_url(&config.url, config.api);
+
+        Ok(Self {
+            config,
+            agent,
+            endpoint,
+            sampling: SamplingConfig::default(),
+            glossary_store: None,
+            max_new_tokens: DEFAULT_MAX_NEW_TOKENS,
+        })
+    }
+
+    /// Set the sampling parameters sent with each request
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Inject glossary terms into prompts
+    pub fn with_glossary_store(mut self, glossary_store: Option<GlossaryStore>) -> Self {
+        self.glossary_store = glossary_store;
+        self
+    }
+
+    /// Set the maximum tokens to generate
+    pub fn with_max_new_tokens(mut self, max_new_tokens: u32) -> Self {
+        self.max_new_tokens = max_new_tokens;
+        self
+    }
+
+    /// Full URL requests are sent to
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Build the JSON request body for `prompt`
+    fn request_body(&self, prompt: &str, max_tokens: u32) -> Value {
+        let mut body = Map::new();
+        if let Some(model) = &self.config.model {
+            body.insert("model".to_string(), json!(model));
+        }
+        match self.config.api {
+            HttpApi::Completions => {
+                body.insert("prompt".to_string(), json!(prompt));
+            }
+            HttpApi::Chat => {
+                body.insert(
+                    "messages".to_string(),
+                    json!([{ "role": "user", "content": user_turn(prompt) }]),
+                );
+            }
+        }
+        body.insert("max_tokens".to_string(), json!(max_tokens));
+        body.insert("temperature".to_string(), json!(self.sampling.temperature));
+        body.insert("top_p".to_string(), json!(self.sampling.top_p));
+        if self.sampling.top_k > 0 {
+            body.insert("top_k".to_string(), json!(self.sampling.top_k));
+        }
+        if self.sampling.min_p > 0.0 {
+            body.insert("min_p".to_string(), json!(self.sampling.min_p));
+        }
+        if self.sampling.repeat_penalty != 1.0 {
+            body.insert(
+                "repeat_penalty".to_string(),
+                json!(self.sampling.repeat_penalty),
+            );
+        }
+        if let Some(seed) = self.sampling.seed {
+            body.insert("seed".to_string(), json!(seed));
+        }
+        body.insert("stop".to_string(), json!([END_OF_TURN]));
+        body.insert("stream".to_string(), json!(false));
+        Value::Object(body)
+    }
+
+    /// Send `body`, retrying connection errors, timeouts, 429 and 5xx responses
+    fn send(&self, body: &Value) -> Result<CompletionResponse> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.agent.post(&self.endpoint);
+            if let Some(api_key) = &self.config.api_key {
+                request = request.set("Authorization", &format!("Bearer {api_key}"));
+            }
+
+            let error = match request.send_json(body) {
+                Ok(response) => {
+                    return response.into_json().map_err(|e| {
+                        Error::Inference(format!("HTTP backend returned invalid JSON: {e}"))
+                    });
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    let detail = response.into_string().unwrap_or_default();
+                    let error = Error::Inference(format!(
+                        "HTTP backend returned status {status}: {}",
+                        detail.trim()
+                    ));
+                    if status != 429 && status < 500 {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(ureq::Error::Transport(transport)) => Error::Inference(format!(
+                    "HTTP backend request to {} failed: {transport}",
+                    self.endpoint
+                )),
+            };
+
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            thread::sleep(Duration::from_millis(
+                self.config.retry_backoff_ms * u64::from(attempt),
+            ));
+        }
+    }
+}
+
+impl Translator for HttpTranslator {
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<TranslationOutput> {
+        validate_pair(source_lang, target_lang)?;
+        options.validate()?;
+
+        let (prompt, glossary_candidates, protected) = build_translation_prompt(
+            self.glossary_store.as_ref(),
+            text,
+            source_lang,
+            target_lang,
+            options,
+        )?;
+        let reported_source = resolve_source_lang(source_lang, text);
+        let body = self.request_body(
+            &prompt,
+            options.max_new_tokens.unwrap_or(self.max_new_tokens),
+        );
+
+        let start = Instant::now();
+        let mut response = self.send(&body)?;
+        let elapsed = start.elapsed();
+
+        let choice = std::mem::take(&mut response.choices)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Inference("HTTP backend returned no choices".to_string()))?;
+        let text = choice
+            .text
+            .or(choice.message.and_then(|message| message.content))
+            .unwrap_or_default();
+
+        let (text, warnings) = restore_output(&text, &protected, options)?;
+        let mut output =
+            TranslationOutput::from_text(text, reported_source, normalize_lang(target_lang));
+        output.glossary_candidates = glossary_candidates;
+        output.warnings = warnings;
+        output.stats = response.stats(choice.finish_reason.as_deref(), elapsed);
+        Ok(output)
+    }
+
+    fn supported_languages(&self) -> &[&str] {
+        supported_languages()
+    }
+}
+
+/// Response of `/v1/completions` and `/v1/chat/completions`
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+    /// llama-server extension with prompt and generation timings
+    #[serde(default)]
+    timings: Option<Timings>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    message: Option<Message>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: usize,
+    #[serde(default)]
+    completion_tokens: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Timings {
+    #[serde(default)]
+    prompt_ms: f64,
+    #[serde(default)]
+    predicted_ms: f64,
+    #[serde(default)]
+    cache_n: usize,
+}
+
+impl CompletionResponse {
+    /// Convert usage and timings into generation stats
+    ///
+    /// Without server timings the whole round trip counts as generation time.
+    fn stats(&self, finish_reason: Option<&str>, elapsed: Duration) -> GenerationStats {
+        let usage = self.usage.as_ref();
+        let mut stats = GenerationStats {
+            prompt_tokens: usage.map_or(0, |usage| usage.prompt_tokens),
+            generated_tokens: usage.map_or(0, |usage| usage.completion_tokens),
+            stop_reason: match finish_reason {
+                Some("length") => StopReason::MaxTokens,
+                _ => StopReason::EndOfText,
+            },
+            generation: elapsed,
+            ..GenerationStats::default()
+        };
+        if let Some(timings) = &self.timings {
+            stats.cached_prompt_tokens = timings.cache_n;
+            stats.prompt_eval = Duration::from_secs_f64(timings.prompt_ms.max(0.0) / 1000.0);
+            stats.generation = Duration::from_secs_f64(timings.predicted_ms.max(0.0) / 1000.0);
+        }
+        stats
+    }
+}
+
+/// Resolve the endpoint URL, accepting base URLs with or without a `/v1` suffix
+fn endpoint_url(url: &str, api: HttpApi) -> String {
+    let base = url.trim().trim_end_matches('/');
+    let base = base.strip_suffix("/v1").unwrap_or(base);
+    match api {
+        HttpApi::Completions => format!("{base}/v1/completions"),
+        HttpApi::Chat => format!("{base}/v1/chat/completions"),
+    }
+}
+
+/// Content of the user turn, for servers that apply the chat template themselves
+fn user_turn(prompt: &str) -> &str {
+    let prompt = prompt.strip_prefix(USER_TURN_PREFIX).unwrap_or(prompt);
+    prompt.strip_suffix(MODEL_TURN_SUFFIX).unwrap_or(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Scripted reply of the stub server
+    enum Reply {
+        Json(u16, &'static str),
+        Stall(Duration),
+    }
+
+    /// Serve `replies` in order on a local port and report every request body received
+    fn stub_server(replies: Vec<Reply>) -> (String, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for reply in replies {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let _ = sender.send(serde_json::from_slice(&body).unwrap());
+
+                let mut stream = reader.into_inner();
+                match reply {
+                    Reply::Json(status, body) => {
+                        let _ = write!(
+                            stream,
+                            "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        );
+                    }
+                    Reply::Stall(duration) => thread::sleep(duration),
+                }
+            }
+        });
+
+        (url, receiver)
+    }
+
+    fn test_config(url: String) -> HttpConfig {
+        HttpConfig {
+            url,
+            retry_backoff_ms: 1,
+            ..HttpConfig::default()
+        }
+    }
+
+    #[test]
+    fn completions_request_carries_prompt_and_sampling() {
+        let (url, requests) = stub_server(vec![Reply::Json(
+            200,
+            r#"{"choices":[{"text":" Bonjour<end_of_turn>","finish_reason":"stop"}],"usage":{"prompt_tokens":30,"completion_tokens":2},"timings":{"prompt_ms":12.0,"predicted_ms":40.0,"cache_n":10}}"#,
+        )]);
+        let translator = HttpTranslator::new(HttpConfig {
+            model: Some("translategemma".to_string()),
+            ..test_config(url)
+        })
+        .unwrap()
+        .with_sampling(SamplingConfig {
+            top_k: 40,
+            seed: Some(7),
+            ..SamplingConfig::default()
+        });
+
+        let output = translator.translate("Hello", "en", "fr").unwrap();
+        let body = requests.recv().unwrap();
+
+        assert_eq!(output.text, "Bonjour");
+        assert_eq!(output.stats.prompt_tokens, 30);
+        assert_eq!(output.stats.cached_prompt_tokens, 10);
+        assert_eq!(output.stats.generated_tokens, 2);
+        assert_eq!(output.stats.generation, Duration::from_millis(40));
+        assert_eq!(body["model"], "translategemma");
+        assert!(body["prompt"].as_str().unwrap().contains("[en->fr]"));
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["top_k"], 40);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["stop"][0], END_OF_TURN);
+        assert!(body.get("min_p").is_none());
+    }
+
+    #[test]
+    fn chat_request_sends_user_turn_only() {
+        let (url, requests) = stub_server(vec![Reply::Json(
+            200,
+            r#"{"choices":[{"message":{"role":"assistant","content":"Hallo"},"finish_reason":"stop"}]}"#,
+        )]);
+        let translator = HttpTranslator::new(HttpConfig {
+            url: format!("{url}/v1/"),
+            api: HttpApi::Chat,
+            ..HttpConfig::default()
+        })
+        .unwrap();
+
+        let options = TranslationOptions::new().with_max_new_tokens(16);
+        let output = translator
+            .translate_with_options("Hello", "en", "de", &options)
+            .unwrap();
+        let body = requests.recv().unwrap();
+        let content = body["messages"][0]["content"].as_str().unwrap();
+
+        assert_eq!(translator.endpoint(), format!("{url}/v1/chat/completions"));
+        assert_eq!(output.text, "Hallo");
+        assert_eq!(body["max_tokens"], 16);
+        assert!(content.starts_with("[en->de]"));
+        assert!(content.ends_with("Text:\nHello"));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let (url, requests) = stub_server(vec![
+            Reply::Json(503, r#"{"error":"loading model"}"#),
+            Reply::Json(
+                200,
+                r#"{"choices":[{"text":"Bonjour","finish_reason":"length"}]}"#,
+            ),
+        ]);
+        let translator = HttpTranslator::new(test_config(url)).unwrap();
+
+        let output = translator.translate("Hello", "en", "fr").unwrap();
+
+        assert_eq!(requests.iter().take(2).count(), 2);
+        assert_eq!(output.text, "Bonjour");
+        assert_eq!(output.stats.stop_reason, StopReason::MaxTokens);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, _requests) = stub_server(vec![Reply::Json(400, r#"{"error":"bad request"}"#)]);
+        let translator = HttpTranslator::new(test_config(url)).unwrap();
+
+        let err = translator.translate("Hello", "en", "fr").unwrap_err();
+
+        assert!(matches!(err, Error::Inference(message) if message.contains("status 400")));
+    }
+
+    #[test]
+    fn timeout_is_reported_after_retries() {
+        let (url, requests) = stub_server(vec![
+            Reply::Stall(Duration::from_secs(3)),
+            Reply::Stall(Duration::from_secs(3)),
+        ]);
+        let translator = HttpTranslator::new(HttpConfig {
+            timeout_secs: 1,
+            max_retries: 1,
+            ..test_config(url)
+        })
+        .unwrap();
+
+        let err = translator.translate("Hello", "en", "fr").unwrap_err();
+
+        assert_eq!(requests.iter().take(2).count(), 2);
+        assert!(matches!(err, Error::Inference(message) if message.contains("failed")));
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let err = HttpTranslator::new(HttpConfig {
+            url: "https://api.example.com".to_string(),
+            ..HttpConfig::default()
+        })
+        .err()
+        .unwrap();
+
+        assert!(matches!(err, Error::Config(_)));
+    }
+
+    #[test]
+    fn endpoint_accepts_base_url_with_or_without_v1() {
+        assert_eq!(
+            endpoint_url("http://host:8080/", HttpApi::Completions),
+            "http://host:8080/v1/completions"
+        );
+        assert_eq!(
+            endpoint_url("http://host:8080/v1", HttpApi::Chat),
+            "http://host:8080/v1/chat/completions"
+        );
+    }
+}
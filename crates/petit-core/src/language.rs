@@ -91,7 +91,7 @@ pub fn is_auto_source(code: &str) -> bool {
 /// - `"en"` -> `"en"`
 /// - `"en-us"` -> `"en"`
 /// - `"pt-br"` -> `"pt"`
-fn base_lang(code: &str) -> &str {
+pub fn base_lang(code: &str) -> &str {
     code.split('-').next().unwrap_or(code)
 }
 
@@ -118,6 +118,115 @@ pub fn validate_pair(source: &str, target: &str) -> Result<()> {
     Ok(())
 }
 
+/// English name of a supported language, ignoring any region suffix.
+///
+/// Returns `None` for unsupported codes and for the `auto` sentinel.
+pub fn language_name(code: &str) -> Option<&'static str> {
+    let normalized = normalize_lang(code);
+    let name = match base_lang(&normalized) {
+        "af" => "Afrikaans",
+        "ar" => "Arabic",
+        "bg" => "Bulgarian",
+        "bn" => "Bengali",
+        "ca" => "Catalan",
+        "cs" => "Czech",
+        "da" => "Danish",
+        "de" => "German",
+        "el" => "Greek",
+        "en" => "English",
+        "es" => "Spanish",
+        "et" => "Estonian",
+        "fa" => "Persian",
+        "fi" => "Finnish",
+        "fr" => "French",
+        "gl" => "Galician",
+        "gu" => "Gujarati",
+        "he" => "Hebrew",
+        "hi" => "Hindi",
+        "hr" => "Croatian",
+        "hu" => "Hungarian",
+        "id" => "Indonesian",
+        "it" => "Italian",
+        "ja" => "Japanese",
+        "ka" => "Georgian",
+        "kk" => "Kazakh",
+        "ko" => "Korean",
+        "lt" => "Lithuanian",
+        "lv" => "Latvian",
+        "mk" => "Macedonian",
+        "ml" => "Malayalam",
+        "mr" => "Marathi",
+        "ms" => "Malay",
+        "ne" => "Nepali",
+        "nl" => "Dutch",
+        "no" => "Norwegian",
+        "pl" => "Polish",
+        "pt" => "Portuguese",
+        "ro" => "Romanian",
+        "ru" => "Russian",
+        "sk" => "Slovak",
+        "sl" => "Slovenian",
+        "sq" => "Albanian",
+        "sr" => "Serbian",
+        "sv" => "Swedish",
+        "sw" => "Swahili",
+        "ta" => "Tamil",
+        "te" => "Telugu",
+        "th" => "Thai",
+        "tl" => "Tagalog",
+        "tr" => "Turkish",
+        "uk" => "Ukrainian",
+        "ur" => "Urdu",
+        "vi" => "Vietnamese",
+        "zh" => "Chinese",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Number of gettext plural forms of a language and its `plural=` expression.
+///
+/// Uses the conventional `Plural-Forms` values of GNU gettext; languages not listed fall back
+/// to the English rule.
+pub fn plural_forms(code: &str) -> (usize, &'static str) {
+    let normalized = normalize_lang(code).replace('_', "-");
+    if normalized == "pt-br" {
+        return (2, "(n > 1)");
+    }
+    match base_lang(&normalized) {
+        "id" | "ja" | "ko" | "ms" | "th" | "vi" | "zh" => (1, "0"),
+        "fr" | "tl" => (2, "(n > 1)"),
+        "mk" => (2, "(n % 10 == 1 && n % 100 != 11) ? 0 : 1"),
+        "ru" | "uk" | "sr" | "hr" => (
+            3,
+            "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+        ),
+        "pl" => (
+            3,
+            "(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+        ),
+        "cs" | "sk" => (3, "(n==1) ? 0 : (n>=2 && n<=4) ? 1 : 2"),
+        "lt" => (
+            3,
+            "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2)",
+        ),
+        "lv" => (3, "(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2)"),
+        "ro" => (
+            3,
+            "(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2)",
+        ),
+        "sl" => (
+            4,
+            "(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3)",
+        ),
+        "ar" => (
+            6,
+            "(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5)",
+        ),
+        _ => (2, "(n != 1)"),
+    }
+}
+
 /// Get the list of supported language codes.
 pub fn supported_languages() -> &'static [&'static str] {
     SUPPORTED_LANGUAGES
@@ -155,6 +264,29 @@ mod tests {
         assert_eq!(base_lang("pt-br"), "pt");
     }
 
+    #[test]
+    fn test_language_name() {
+        assert_eq!(language_name("fr"), Some("French"));
+        assert_eq!(language_name("PT-BR"), Some("Portuguese"));
+        assert_eq!(language_name("auto"), None);
+        assert!(
+            supported_languages()
+                .iter()
+                .all(|code| language_name(code).is_some())
+        );
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        assert_eq!(plural_forms("ja").0, 1);
+        assert_eq!(plural_forms("de"), (2, "(n != 1)"));
+        assert_eq!(plural_forms("fr"), (2, "(n > 1)"));
+        assert_eq!(plural_forms("pt_BR"), (2, "(n > 1)"));
+        assert_eq!(plural_forms("pt"), (2, "(n != 1)"));
+        assert_eq!(plural_forms("RU").0, 3);
+        assert_eq!(plural_forms("ar").0, 6);
+    }
+
     #[test]
     fn test_is_supported_simple() {
         assert!(is_supported("en"));
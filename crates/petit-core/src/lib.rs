@@ -5,30 +5,219 @@
 //! This crate provides the core translation functionality using TranslateGemma
 //! via llama.cpp bindings.
 
+pub mod cancel;
+pub mod chunking;
 pub mod config;
+pub mod detect;
+pub mod document;
 pub mod error;
 pub mod gemma;
 pub mod glossary;
+pub mod http;
 pub mod language;
+pub mod markdown;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod model_manager;
+pub mod options;
+pub mod output;
+pub mod po;
+pub mod protect;
+pub mod resource;
+pub mod segmenter;
+pub mod subtitle;
+#[cfg(test)]
+mod test_support;
+pub mod xliff;
 
-pub use config::Config;
+pub use cancel::CancellationToken;
+pub use config::{Config, HttpApi, HttpConfig};
+pub use detect::{DetectedLanguage, detect_language};
+pub use document::{DocumentFormat, translate_document, update_document};
 pub use error::Error;
 pub use gemma::GemmaTranslator;
 pub use glossary::{GlossaryCandidate, GlossaryStore};
-pub use model_manager::ModelManager;
+pub use http::HttpTranslator;
+#[cfg(feature = "mock")]
+pub use mock::{MockMode, MockTranslator};
+pub use model_manager::{Generation, ModelManager};
+pub use options::{Formality, PlaceholderMode, TranslationOptions};
+pub use output::{GenerationStats, StopReason, TranslationOutput};
+pub use protect::{ProtectedText, protect};
 
 /// Result type for petit-core operations
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// A single text to translate, used by [`Translator::translate_batch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslationRequest {
+    /// Source text
+    pub text: String,
+    /// Source language code, or `auto`
+    pub source_lang: String,
+    /// Target language code
+    pub target_lang: String,
+    /// Options for this request
+    pub options: TranslationOptions,
+}
+
+impl TranslationRequest {
+    /// Create a request for `text` in the given language pair
+    pub fn new(
+        text: impl Into<String>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+    ) -> Self {
+        Self {
+            text: text.into(),
+            source_lang: source_lang.into(),
+            target_lang: target_lang.into(),
+            options: TranslationOptions::default(),
+        }
+    }
+
+    /// Set the options for this request
+    pub fn with_options(mut self, options: TranslationOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
 /// Translator trait defining the translation interface
 ///
 /// This trait allows different backends (llama-cpp, candle, mock) to be used
 /// interchangeably.
 pub trait Translator {
     /// Translate text from source language to target language
-    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> Result<String>;
+    fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<TranslationOutput> {
+        self.translate_with_options(
+            text,
+            source_lang,
+            target_lang,
+            &TranslationOptions::default(),
+        )
+    }
+
+    /// Translate text using per-request options
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<TranslationOutput>;
+
+    /// Translate text, passing partial output to `on_piece` as it is generated
+    ///
+    /// Backends without incremental output report the whole translation as a single piece.
+    /// Implementations return [`Error::Cancelled`] once `cancel` is triggered.
+    fn translate_streaming(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+        cancel: &CancellationToken,
+        on_piece: &mut dyn FnMut(&str),
+    ) -> Result<TranslationOutput> {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let output = self.translate_with_options(text, source_lang, target_lang, options)?;
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        on_piece(&output.text);
+        Ok(output)
+    }
+
+    /// Translate several requests, returning one result per request in the same order
+    ///
+    /// Backends that can decode requests together override this; the default translates
+    /// them one after another.
+    fn translate_batch(&self, requests: &[TranslationRequest]) -> Vec<Result<TranslationOutput>> {
+        requests
+            .iter()
+            .map(|request| {
+                self.translate_with_options(
+                    &request.text,
+                    &request.source_lang,
+                    &request.target_lang,
+                    &request.options,
+                )
+            })
+            .collect()
+    }
 
     /// Get list of supported language codes
     fn supported_languages(&self) -> &[&str];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTranslator;
+
+    impl Translator for EchoTranslator {
+        fn translate_with_options(
+            &self,
+            text: &str,
+            source_lang: &str,
+            target_lang: &str,
+            _options: &TranslationOptions,
+        ) -> Result<TranslationOutput> {
+            if text.is_empty() {
+                return Err(Error::Inference("empty".to_string()));
+            }
+            Ok(TranslationOutput::from_text(
+                format!("{source_lang}->{target_lang}:{text}"),
+                source_lang,
+                target_lang,
+            ))
+        }
+
+        fn supported_languages(&self) -> &[&str] {
+            &["en", "fr"]
+        }
+    }
+
+    #[test]
+    fn default_translate_batch_keeps_request_order_and_errors() {
+        let requests = [
+            TranslationRequest::new("one", "en", "fr"),
+            TranslationRequest::new("", "en", "fr"),
+            TranslationRequest::new("two", "fr", "en"),
+        ];
+
+        let results = EchoTranslator.translate_batch(&requests);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().text, "en->fr:one");
+        assert!(matches!(results[1], Err(Error::Inference(_))));
+        assert_eq!(results[2].as_ref().unwrap().text, "fr->en:two");
+        assert_eq!(results[2].as_ref().unwrap().source_lang, "fr");
+    }
+
+    #[test]
+    fn default_translate_streaming_reports_cancellation() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let result = EchoTranslator.translate_streaming(
+            "one",
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+            &cancel,
+            &mut |_| {},
+        );
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
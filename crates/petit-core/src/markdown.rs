@@ -0,0 +1,390 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Markdown-aware translation
+//!
+//! The document is parsed with pulldown-cmark and only prose is sent to the translator:
+//! headings, paragraphs, list items, table cells and image alt text. Each prose block is
+//! replaced in place, so front matter, code blocks, HTML blocks, indentation and every other
+//! byte outside prose stay as they were.
+//!
+//! Inside a block, code spans, inline HTML, autolinks, link destinations and hard breaks are
+//! swapped for `⟦n⟧` markers before translation and restored afterwards. Soft line breaks
+//! become spaces, so a translated paragraph is written on one line.
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+use crate::protect::{marker, marker_options, restore_markers};
+use crate::{Result, TranslationOptions, TranslationRequest, Translator};
+
+/// A prose block and the text sent for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    /// Source bytes replaced by the translation
+    range: Range<usize>,
+    /// Prose with markers in place of protected spans
+    template: String,
+    /// Source text of each marker, by marker number
+    protected: Vec<String>,
+    /// Table cells need `|` escaped in the translation
+    in_table: bool,
+}
+
+/// Translate the prose of a Markdown document, keeping its structure
+pub fn translate_markdown(
+    translator: &dyn Translator,
+    source: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let segments = prose_segments(source);
+    let requests: Vec<TranslationRequest> = segments
+        .iter()
+        .map(|segment| {
+            TranslationRequest::new(segment.template.clone(), source_lang, target_lang)
+                .with_options(marker_options(options, !segment.protected.is_empty()))
+        })
+        .collect();
+    let outputs = translator
+        .translate_batch(&requests)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut document = source.to_string();
+    for (segment, output) in segments.iter().zip(outputs).rev() {
+        document.replace_range(segment.range.clone(), &restore(segment, &output.text));
+    }
+    Ok(document)
+}
+
+/// Options for the Markdown flavours READMEs use
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS
+}
+
+fn prose_segments(source: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut block: Option<BlockBuilder> = None;
+    // Code blocks, HTML blocks and front matter hold no prose
+    let mut verbatim_depth = 0usize;
+    let mut in_autolink = false;
+    let mut table_depth = 0usize;
+
+    for (event, range) in Parser::new_ext(source, parser_options()).into_offset_iter() {
+        match &event {
+            Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_)) => {
+                verbatim_depth += 1;
+                continue;
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)) => {
+                verbatim_depth = verbatim_depth.saturating_sub(1);
+                continue;
+            }
+            _ if verbatim_depth > 0 => continue,
+            Event::Start(Tag::Table(_)) => table_depth += 1,
+            Event::End(TagEnd::Table) => table_depth = table_depth.saturating_sub(1),
+            _ => {}
+        }
+
+        if in_autolink {
+            in_autolink = !matches!(event, Event::End(TagEnd::Link));
+            continue;
+        }
+        if !is_inline(&event) {
+            segments.extend(block.take().and_then(|block| block.finish(source)));
+            continue;
+        }
+
+        let builder = block.get_or_insert_with(|| BlockBuilder::new(range.start, table_depth > 0));
+        match event {
+            Event::Text(_) => builder.text(source, range),
+            Event::SoftBreak => builder.soft_break(source, range),
+            Event::HardBreak => builder.hard_break(source, range),
+            Event::Start(Tag::Link {
+                link_type: LinkType::Autolink | LinkType::Email,
+                ..
+            }) => {
+                builder.protect(source, range);
+                in_autolink = true;
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => builder.link_end(source, range),
+            Event::Start(_) | Event::End(_) => builder.extend(range),
+            _ => builder.protect(source, range),
+        }
+    }
+    segments.extend(block.and_then(|block| block.finish(source)));
+    segments
+}
+
+/// Events that belong to the inline content of a block
+fn is_inline(event: &Event<'_>) -> bool {
+    match event {
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::InlineMath(_)
+        | Event::DisplayMath(_)
+        | Event::Html(_)
+        | Event::InlineHtml(_)
+        | Event::FootnoteReference(_)
+        | Event::SoftBreak
+        | Event::HardBreak => true,
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript
+                | Tag::Link { .. }
+                | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link
+                | TagEnd::Image
+        ),
+        Event::Rule | Event::TaskListMarker(_) => false,
+    }
+}
+
+/// Collects the inline content of one block into a template
+struct BlockBuilder {
+    start: usize,
+    end: usize,
+    /// End of the source already copied into the template
+    cursor: usize,
+    template: String,
+    protected: Vec<String>,
+    /// Set after a line break; the next gap starts with the container's line prefix
+    after_break: Option<Break>,
+    has_prose: bool,
+    in_table: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Break {
+    Soft,
+    /// Marker number of the hard break, which keeps its line prefix
+    Hard(usize),
+}
+
+impl BlockBuilder {
+    fn new(start: usize, in_table: bool) -> Self {
+        Self {
+            start,
+            end: start,
+            cursor: start,
+            template: String::new(),
+            protected: Vec::new(),
+            after_break: None,
+            has_prose: false,
+            in_table,
+        }
+    }
+
+    fn extend(&mut self, range: Range<usize>) {
+        self.end = self.end.max(range.end);
+    }
+
+    /// Copy the markup between the previous event and `upto`
+    fn gap(&mut self, source: &str, upto: usize) {
+        if upto <= self.cursor {
+            return;
+        }
+        let mut gap = &source[self.cursor..upto];
+        if let Some(line_break) = self.after_break.take() {
+            // Blockquote markers and indentation of the continuation line
+            let prefix_len = gap.len() - gap.trim_start_matches([' ', '\t', '>']).len();
+            if let Break::Hard(marker) = line_break {
+                self.protected[marker].push_str(&gap[..prefix_len]);
+            }
+            gap = &gap[prefix_len..];
+        }
+        self.template.push_str(gap);
+        self.cursor = upto;
+    }
+
+    fn text(&mut self, source: &str, range: Range<usize>) {
+        self.gap(source, range.start);
+        let text = &source[range.clone()];
+        self.has_prose |= text.chars().any(char::is_alphabetic);
+        self.template.push_str(text);
+        self.cursor = range.end;
+        self.extend(range);
+    }
+
+    fn soft_break(&mut self, source: &str, range: Range<usize>) {
+        self.gap(source, range.start);
+        self.template.push(' ');
+        self.cursor = range.end;
+        self.after_break = Some(Break::Soft);
+        self.extend(range);
+    }
+
+    fn hard_break(&mut self, source: &str, range: Range<usize>) {
+        self.protect(source, range);
+        self.after_break = Some(Break::Hard(self.protected.len() - 1));
+    }
+
+    fn protect(&mut self, source: &str, range: Range<usize>) {
+        self.gap(source, range.start);
+        self.template.push_str(&marker(self.protected.len()));
+        self.protected.push(source[range.clone()].to_string());
+        self.cursor = range.end;
+        self.extend(range);
+    }
+
+    /// Keep the closing `]` as text and protect the destination after it
+    fn link_end(&mut self, source: &str, range: Range<usize>) {
+        if range.end > self.cursor {
+            let tail = &source[self.cursor..range.end];
+            let bracket = usize::from(tail.starts_with(']'));
+            self.gap(source, self.cursor + bracket);
+            if range.end > self.cursor {
+                self.protect(source, self.cursor..range.end);
+            }
+        }
+        self.extend(range);
+    }
+
+    fn finish(mut self, source: &str) -> Option<Segment> {
+        self.gap(source, self.end);
+        self.has_prose.then_some(Segment {
+            range: self.start..self.end,
+            template: self.template,
+            protected: self.protected,
+            in_table: self.in_table,
+        })
+    }
+}
+
+/// Put the protected spans back into a translation
+fn restore(segment: &Segment, translation: &str) -> String {
+    let mut text = translation.trim().replace(['\r', '\n'], " ");
+    if segment.in_table {
+        text = escape_pipes(&text);
+    }
+    restore_markers(text, &segment.protected)
+}
+
+/// Escape `|` that would end a table cell
+fn escape_pipes(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+    for ch in text.chars() {
+        if ch == '|' && previous != Some('\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+        previous = Some(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Recorder;
+
+    fn translate(source: &str) -> (String, Vec<String>) {
+        let recorder = Recorder::default();
+        let output = translate_markdown(
+            &recorder,
+            source,
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("markdown should translate");
+        (output, recorder.texts())
+    }
+
+    #[test]
+    fn translates_prose_and_keeps_code_and_front_matter() {
+        let source = "---\ntitle: Guide\n---\n\n# Install the tool\n\nRun `cargo build` first.\n\n```bash\necho hello\n```\n\n<div>raw html</div>\n";
+
+        let (output, texts) = translate(source);
+
+        assert_eq!(
+            output,
+            "---\ntitle: Guide\n---\n\n# INSTALL THE TOOL\n\nRUN `cargo build` FIRST.\n\n```bash\necho hello\n```\n\n<div>raw html</div>\n"
+        );
+        assert_eq!(texts, vec!["Install the tool", "Run ⟦0⟧ first."]);
+    }
+
+    #[test]
+    fn protects_link_destinations_and_keeps_alt_text() {
+        let source = "See [the docs](https://example.com/docs \"Docs\") and <https://example.com>.\n\n![a red fox](img/fox.png)\n";
+
+        let (output, texts) = translate(source);
+
+        assert_eq!(
+            output,
+            "SEE [THE DOCS](https://example.com/docs \"Docs\") AND <https://example.com>.\n\n![A RED FOX](img/fox.png)\n"
+        );
+        assert_eq!(texts, vec!["See [the docs]⟦0⟧ and ⟦1⟧.", "![a red fox]⟦0⟧"]);
+    }
+
+    #[test]
+    fn keeps_list_markers_blockquotes_and_emphasis() {
+        let source = "- first **item**\n- [ ] second item\n  1. nested one\n\n> quoted line\n> continues here\n";
+
+        let (output, texts) = translate(source);
+
+        assert_eq!(
+            output,
+            "- FIRST **ITEM**\n- [ ] SECOND ITEM\n  1. NESTED ONE\n\n> QUOTED LINE CONTINUES HERE\n"
+        );
+        assert!(texts.contains(&"quoted line continues here".to_string()));
+    }
+
+    #[test]
+    fn hard_breaks_keep_their_line_prefix() {
+        let (output, texts) = translate("> one  \n> two\n");
+
+        assert_eq!(output, "> ONE  \n> TWO\n");
+        assert_eq!(texts, vec!["one⟦0⟧two"]);
+    }
+
+    #[test]
+    fn translates_table_cells_and_escapes_pipes() {
+        let segment = Segment {
+            range: 0..0,
+            template: "a or b".to_string(),
+            protected: Vec::new(),
+            in_table: true,
+        };
+        let (output, texts) =
+            translate("| Name | Value |\n|------|------:|\n| cache size | 42 |\n");
+
+        assert_eq!(
+            output,
+            "| NAME | VALUE |\n|------|------:|\n| CACHE SIZE | 42 |\n"
+        );
+        assert_eq!(texts, vec!["Name", "Value", "cache size"]);
+        assert_eq!(restore(&segment, "a | b"), "a \\| b");
+    }
+
+    #[test]
+    fn restore_leaves_out_markers_the_model_dropped() {
+        let segment = Segment {
+            range: 0..0,
+            template: "Run ⟦0⟧ and ⟦1⟧".to_string(),
+            protected: vec!["`a`".to_string(), "`b`".to_string()],
+            in_table: false,
+        };
+
+        assert_eq!(restore(&segment, "Lancez ⟦1⟧\npuis"), "Lancez `b` puis");
+    }
+}
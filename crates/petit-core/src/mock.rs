@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Model-free translator for tests and demos
+//!
+//! `MockTranslator` answers with scripted responses or a deterministic transformation of
+//! the input, so the full translation flow can run without a GGUF model.
+
+use crate::language::{normalize_lang, supported_languages, validate_pair};
+use crate::{
+    CancellationToken, Error, GenerationStats, Result, StopReason, TranslationOptions,
+    TranslationOutput, Translator,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest sleep between cancellation checks
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Transformation applied to input without a scripted response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MockMode {
+    /// Return the input unchanged
+    Echo,
+    /// Return the input with its characters in reverse order
+    Reverse,
+    /// Return the input in upper case
+    #[default]
+    Uppercase,
+}
+
+impl std::str::FromStr for MockMode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "echo" => Ok(MockMode::Echo),
+            "reverse" => Ok(MockMode::Reverse),
+            "uppercase" => Ok(MockMode::Uppercase),
+            other => Err(Error::Config(format!(
+                "mock mode must be `echo`, `reverse` or `uppercase`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// Scripted answer for a source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MockResponse {
+    Text(String),
+    Error(String),
+}
+
+/// Translator that never loads a model
+///
+/// Output is streamed one word at a time. Each streamed word counts as one generated
+/// token, so `max_new_tokens` truncates the output like a real model would.
+#[derive(Debug, Default)]
+pub struct MockTranslator {
+    mode: MockMode,
+    responses: HashMap<String, MockResponse>,
+    delay: Duration,
+    calls: AtomicUsize,
+}
+
+impl MockTranslator {
+    /// Create a mock translator that upper-cases its input
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the transformation used for input without a scripted response
+    pub fn with_mode(mut self, mode: MockMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Answer `source` with `translation`
+    pub fn with_response(
+        mut self,
+        source: impl Into<String>,
+        translation: impl Into<String>,
+    ) -> Self {
+        self.responses
+            .insert(source.into(), MockResponse::Text(translation.into()));
+        self
+    }
+
+    /// Fail translations of `source` with an inference error carrying `message`
+    pub fn with_error(mut self, source: impl Into<String>, message: impl Into<String>) -> Self {
+        self.responses
+            .insert(source.into(), MockResponse::Error(message.into()));
+        self
+    }
+
+    /// Wait `delay` before each streamed piece
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Number of translations requested so far
+    pub fn call_count(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Produce the full translation of `text` before truncation
+    fn respond(&self, text: &str) -> Result<String> {
+        match self.responses.get(text) {
+            Some(MockResponse::Text(translation)) => Ok(translation.clone()),
+            Some(MockResponse::Error(message)) => Err(Error::Inference(message.clone())),
+            None => Ok(match self.mode {
+                MockMode::Echo => text.to_string(),
+                MockMode::Reverse => text.chars().rev().collect(),
+                MockMode::Uppercase => text.to_uppercase(),
+            }),
+        }
+    }
+
+    /// Sleep for the configured delay, returning early once `cancel` is triggered
+    fn wait(&self, cancel: &CancellationToken) -> Result<()> {
+        let deadline = Instant::now() + self.delay;
+        loop {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            thread::sleep((deadline - now).min(CANCEL_POLL_INTERVAL));
+        }
+    }
+}
+
+impl Translator for MockTranslator {
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<TranslationOutput> {
+        self.translate_streaming(
+            text,
+            source_lang,
+            target_lang,
+            options,
+            &CancellationToken::new(),
+            &mut |_| {},
+        )
+    }
+
+    fn translate_streaming(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+        cancel: &CancellationToken,
+        on_piece: &mut dyn FnMut(&str),
+    ) -> Result<TranslationOutput> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        validate_pair(source_lang, target_lang)?;
+        options.validate()?;
+
+        let translation = self.respond(text.trim())?;
+        let max_pieces = options
+            .max_new_tokens
+            .map_or(usize::MAX, |max| max as usize);
+        let start = Instant::now();
+        let mut output = TranslationOutput::from_text(
+            String::new(),
+            normalize_lang(source_lang),
+            normalize_lang(target_lang),
+        );
+        output.stats = GenerationStats {
+            prompt_tokens: text.split_whitespace().count(),
+            ..GenerationStats::default()
+        };
+
+        for piece in translation.split_inclusive(char::is_whitespace) {
+            if output.stats.generated_tokens == max_pieces {
+                output.stats.stop_reason = StopReason::MaxTokens;
+                break;
+            }
+            self.wait(cancel)?;
+            on_piece(piece);
+            output.text.push_str(piece);
+            output.stats.generated_tokens += 1;
+        }
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        output.text = output.text.trim().to_string();
+        // Report request terms as used, like a prompt-based translator would
+        output.glossary_candidates = options.glossary_terms.clone();
+        output.stats.generation = start.elapsed();
+        Ok(output)
+    }
+
+    fn supported_languages(&self) -> &[&str] {
+        supported_languages()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TranslationRequest;
+
+    #[test]
+    fn modes_transform_unscripted_input() {
+        let uppercase = MockTranslator::new();
+        let reverse = MockTranslator::new().with_mode(MockMode::Reverse);
+        let echo = MockTranslator::new().with_mode(MockMode::Echo);
+
+        assert_eq!(
+            uppercase.translate("Hello", "en", "fr").unwrap().text,
+            "HELLO"
+        );
+        assert_eq!(
+            reverse.translate("Hello", "en", "fr").unwrap().text,
+            "olleH"
+        );
+        assert_eq!(echo.translate(" Hello ", "en", "fr").unwrap().text, "Hello");
+    }
+
+    #[test]
+    fn scripted_responses_and_errors_take_precedence() {
+        let translator = MockTranslator::new()
+            .with_response("Hello", "Bonjour")
+            .with_error("Boom", "scripted failure");
+
+        let output = translator.translate("Hello", "EN", "FR").unwrap();
+        let err = translator.translate("Boom", "en", "fr").unwrap_err();
+
+        assert_eq!(output.text, "Bonjour");
+        assert_eq!(output.source_lang, "en");
+        assert_eq!(output.target_lang, "fr");
+        assert!(matches!(err, Error::Inference(message) if message == "scripted failure"));
+        assert_eq!(translator.call_count(), 2);
+    }
+
+    #[test]
+    fn streaming_reports_word_pieces_and_token_counts() {
+        let translator = MockTranslator::new().with_response("Hi there", "Salut tout le monde");
+        let mut pieces = Vec::new();
+
+        let output = translator
+            .translate_streaming(
+                "Hi there",
+                "en",
+                "fr",
+                &TranslationOptions::default(),
+                &CancellationToken::new(),
+                &mut |piece| pieces.push(piece.to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(pieces, ["Salut ", "tout ", "le ", "monde"]);
+        assert_eq!(output.stats.prompt_tokens, 2);
+        assert_eq!(output.stats.generated_tokens, 4);
+        assert_eq!(output.stats.stop_reason, StopReason::EndOfText);
+    }
+
+    #[test]
+    fn max_new_tokens_truncates_output() {
+        let translator = MockTranslator::new().with_mode(MockMode::Echo);
+        let options = TranslationOptions::new().with_max_new_tokens(2);
+
+        let output = translator
+            .translate_with_options("one two three", "en", "fr", &options)
+            .unwrap();
+
+        assert_eq!(output.text, "one two");
+        assert_eq!(output.stats.stop_reason, StopReason::MaxTokens);
+    }
+
+    #[test]
+    fn cancellation_interrupts_delayed_streaming() {
+        let translator = MockTranslator::new().with_delay(Duration::from_secs(5));
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            canceller.cancel();
+        });
+
+        let start = Instant::now();
+        let result = translator.translate_streaming(
+            "Hello",
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+            &cancel,
+            &mut |_| {},
+        );
+        handle.join().unwrap();
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn invalid_language_pair_is_rejected() {
+        let err = MockTranslator::new()
+            .translate("Hello", "xx", "fr")
+            .unwrap_err();
+
+        assert!(matches!(err, Error::UnsupportedLanguage(_)));
+    }
+
+    #[test]
+    fn batch_keeps_order_with_injected_errors() {
+        let translator = MockTranslator::new().with_error("bad", "nope");
+        let requests = [
+            TranslationRequest::new("a", "en", "fr"),
+            TranslationRequest::new("bad", "en", "fr"),
+            TranslationRequest::new("b", "en", "fr"),
+        ];
+
+        let results = translator.translate_batch(&requests);
+
+        assert_eq!(results[0].as_ref().unwrap().text, "A");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().text, "B");
+    }
+}
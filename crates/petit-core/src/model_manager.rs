@@ -3,17 +3,23 @@
 // Unsafe is required for the llama.cpp FFI logging callbacks (`llama_log_set`,
 // `ggml_log_set`, and `CStr::from_ptr`). There is no safe abstraction for these
 // C function pointer registration APIs in the current llama-cpp-2 crate.
+// It is also used to keep a llama context alive next to the model it borrows
+// (see `ModelManager::new_cached_context`).
 #![allow(unsafe_code)]
 
 //! Model management for llama.cpp inference
 
-use crate::{Config, Error, Result};
+use crate::config::SamplingConfig;
+use crate::output::{GenerationStats, StopReason};
+use crate::{CancellationToken, Config, Error, Result};
+use llama_cpp_2::context::LlamaContext;
 use llama_cpp_2::context::params::LlamaContextParams;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::llama_batch::LlamaBatch;
 use llama_cpp_2::model::LlamaModel;
 use llama_cpp_2::model::params::LlamaModelParams;
-use llama_cpp_2::token::data_array::LlamaTokenDataArray;
+use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::LlamaToken;
 use std::ffi::CStr;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
@@ -21,14 +27,100 @@ use std::num::NonZeroU32;
 use std::os::raw::c_char;
 use std::path::Path;
 use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// Manages model loading and inference via llama.cpp
+///
+/// One llama context is kept alive between calls. Its KV cache still holds the
+/// previous prompt, so a prompt sharing a prefix with it (such as the fixed
+/// translation instructions) only decodes the tokens after that prefix.
 pub struct ModelManager {
-    model: LlamaModel,
+    // Declared before `model` so the cached context is dropped before the model it borrows.
+    prompt_cache: Mutex<PromptCache>,
+    model: Box<LlamaModel>,
     backend: LlamaBackend,
     config: Config,
 }
 
+/// Text generated for one prompt, with its token counts and timings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generation {
+    /// Generated text, trimmed of surrounding whitespace
+    pub text: String,
+    /// Token counts, stop reason and timings
+    pub stats: GenerationStats,
+}
+
+/// Persistent llama context and the tokens currently held in its KV cache
+#[derive(Default)]
+struct PromptCache {
+    context: Option<LlamaContext<'static>>,
+    tokens: Vec<LlamaToken>,
+}
+
+// SAFETY: a llama context is not tied to the thread that created it; it only
+// must not be used concurrently. `PromptCache` is only reachable through the
+// `Mutex` owned by `ModelManager`.
+unsafe impl Send for PromptCache {}
+
+impl PromptCache {
+    /// Drop KV entries that do not match `prompt` and return how many prompt tokens remain cached
+    fn retain_prefix_of(&mut self, prompt: &[LlamaToken]) -> usize {
+        let Some(ctx) = self.context.as_mut() else {
+            self.tokens.clear();
+            return 0;
+        };
+
+        let mut reused = reusable_prefix_len(&self.tokens, prompt);
+        let truncated = matches!(
+            ctx.clear_kv_cache_seq(Some(0), Some(reused as u32), None),
+            Ok(true)
+        );
+        if !truncated {
+            // Some cache layouts (e.g. sliding-window attention) cannot drop a suffix
+            ctx.clear_kv_cache();
+            reused = 0;
+        }
+        self.tokens.truncate(reused);
+        reused
+    }
+
+    /// Forget everything held in the KV cache
+    fn reset(&mut self) {
+        if let Some(ctx) = self.context.as_mut() {
+            ctx.clear_kv_cache();
+        }
+        self.tokens.clear();
+    }
+}
+
+/// Decoding state of one prompt in `ModelManager::infer_batch`
+struct BatchSequence {
+    seq_id: i32,
+    next_pos: i32,
+    logits_idx: i32,
+    sampler: LlamaSampler,
+    decoder: encoding_rs::Decoder,
+    output: String,
+    prompt_tokens: usize,
+    /// Generation limit, lower than requested when the sequence's context runs out
+    max_new_tokens: usize,
+    generated_tokens: usize,
+    stop_reason: Option<StopReason>,
+}
+
+/// Length of the shared prefix of `cached` and `prompt` that can be reused
+///
+/// The last prompt token is always decoded again so fresh logits are available for sampling.
+fn reusable_prefix_len(cached: &[LlamaToken], prompt: &[LlamaToken]) -> usize {
+    let common = cached
+        .iter()
+        .zip(prompt)
+        .take_while(|(cached, prompt)| cached == prompt)
+        .count();
+    common.min(prompt.len().saturating_sub(1))
+}
+
 static LOG_FILE: OnceLock<Mutex<std::fs::File>> = OnceLock::new();
 
 impl ModelManager {
@@ -42,6 +134,8 @@ impl ModelManager {
             )));
         }
 
+        config.sampling.validate()?;
+
         // Initialize backend
         let mut backend =
             LlamaBackend::init().map_err(|e| Error::ModelLoad(format!("Backend init: {e}")))?;
@@ -55,60 +149,154 @@ impl ModelManager {
             .map_err(|e| Error::ModelLoad(format!("Model load: {e}")))?;
 
         Ok(Self {
-            model,
+            prompt_cache: Mutex::new(PromptCache::default()),
+            model: Box::new(model),
             backend,
             config,
         })
     }
 
     /// Run inference on a prompt and return the generated text
-    pub fn infer(&self, prompt: &str, max_new_tokens: u32) -> Result<String> {
-        // Create context for this inference
-        let ctx_params = LlamaContextParams::default()
-            .with_n_ctx(NonZeroU32::new(self.config.context_size))
-            .with_n_threads(self.config.threads as i32)
-            .with_n_threads_batch(self.config.threads as i32);
-
-        let mut ctx = self
-            .model
-            .new_context(&self.backend, ctx_params)
-            .map_err(|e| Error::Inference(format!("Context creation: {e}")))?;
+    pub fn infer(&self, prompt: &str, max_new_tokens: u32) -> Result<Generation> {
+        self.infer_streaming(prompt, max_new_tokens, &CancellationToken::new(), |_| {})
+    }
 
+    /// Run inference on a prompt, passing each detokenized piece to `on_piece` as it is generated
+    ///
+    /// Returns the full generation once it stops. `cancel` is checked between
+    /// decode steps, and [`Error::Cancelled`] is returned as soon as it is triggered.
+    pub fn infer_streaming<F>(
+        &self,
+        prompt: &str,
+        max_new_tokens: u32,
+        cancel: &CancellationToken,
+        mut on_piece: F,
+    ) -> Result<Generation>
+    where
+        F: FnMut(&str),
+    {
         // Tokenize prompt
         let tokens = self
             .model
             .str_to_token(prompt, llama_cpp_2::model::AddBos::Always)
             .map_err(|e| Error::Inference(format!("Tokenization: {e}")))?;
 
-        // Create batch and add tokens
+        // Leave room for the prompt and stop generating once the context is full
+        let context_size = self.config.context_size as usize;
+        if tokens.len() >= context_size {
+            return Err(Error::Inference(format!(
+                "Prompt has {} tokens but the context size is {context_size}",
+                tokens.len()
+            )));
+        }
+        let context_limit = (context_size - tokens.len()) as u32;
+        let limit_reason = if max_new_tokens > context_limit {
+            StopReason::ContextFull
+        } else {
+            StopReason::MaxTokens
+        };
+        let max_new_tokens = max_new_tokens.min(context_limit);
+
+        let mut cache = self
+            .prompt_cache
+            .lock()
+            .map_err(|_| Error::Inference("Prompt cache lock poisoned".to_string()))?;
+        if cache.context.is_none() {
+            cache.context = Some(self.new_cached_context()?);
+            cache.tokens.clear();
+        }
+
+        // Keep the KV entries shared with the previous prompt
+        cache.retain_prefix_of(&tokens);
+
+        let result = self
+            .generate(&mut cache, &tokens, max_new_tokens, cancel, &mut on_piece)
+            .map(|mut generation| {
+                if generation.stats.stop_reason == StopReason::MaxTokens {
+                    generation.stats.stop_reason = limit_reason;
+                }
+                generation
+            });
+        if let Err(Error::Inference(_)) = &result {
+            // The KV cache may be partially updated after a failed decode
+            cache.reset();
+        }
+        result
+    }
+
+    /// Decode the uncached part of `tokens` and generate up to `max_new_tokens`
+    fn generate<F>(
+        &self,
+        cache: &mut PromptCache,
+        tokens: &[LlamaToken],
+        max_new_tokens: u32,
+        cancel: &CancellationToken,
+        on_piece: &mut F,
+    ) -> Result<Generation>
+    where
+        F: FnMut(&str),
+    {
+        let ctx = cache
+            .context
+            .as_mut()
+            .ok_or_else(|| Error::Inference("Context not initialized".to_string()))?;
+        let reused = cache.tokens.len();
+
+        // Create batch and add the tokens after the cached prefix
         let mut batch = LlamaBatch::new(self.config.context_size as usize, 1);
         let last_idx = tokens.len() - 1;
-        for (i, token) in tokens.iter().enumerate() {
+        for (i, token) in tokens.iter().enumerate().skip(reused) {
             batch
                 .add(*token, i as i32, &[0], i == last_idx)
                 .map_err(|e| Error::Inference(format!("Batch add: {e}")))?;
         }
 
         // Process prompt
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let prompt_start = Instant::now();
         ctx.decode(&mut batch)
             .map_err(|e| Error::Inference(format!("Decode prompt: {e}")))?;
+        cache.tokens.extend_from_slice(&tokens[reused..]);
+        let mut stats = GenerationStats {
+            prompt_tokens: tokens.len(),
+            cached_prompt_tokens: reused,
+            stop_reason: StopReason::MaxTokens,
+            prompt_eval: prompt_start.elapsed(),
+            ..GenerationStats::default()
+        };
 
-        // Generate tokens
-        let mut output_tokens = Vec::new();
-        let mut n_cur = tokens.len();
+        // Generate tokens, detokenizing as we go so partial output can be streamed
+        let mut sampler = build_sampler(&self.config.sampling);
+        let mut output = String::new();
+        let mut decoder = encoding_rs::UTF_8.new_decoder();
+        let first_pos = tokens.len();
+        let generation_start = Instant::now();
 
-        for _ in 0..max_new_tokens {
-            // Sample next token (greedy: temperature 0)
-            let candidates = ctx.candidates_ith(batch.n_tokens() - 1);
-            let mut candidates_array = LlamaTokenDataArray::from_iter(candidates, false);
-            let next_token = candidates_array.sample_token_greedy();
+        for n_cur in first_pos..first_pos + max_new_tokens as usize {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            // Sample next token; the sampler also records it for the repetition penalty
+            let next_token = sampler.sample(ctx, batch.n_tokens() - 1);
 
             // Check for end of generation
             if self.model.is_eog_token(next_token) {
+                stats.stop_reason = StopReason::EndOfText;
                 break;
             }
+            stats.generated_tokens += 1;
 
-            output_tokens.push(next_token);
+            let piece = self
+                .model
+                .token_to_piece(next_token, &mut decoder, true, None)
+                .map_err(|e| Error::Inference(format!("Detokenization: {e}")))?;
+            if !piece.is_empty() {
+                on_piece(&piece);
+                output.push_str(&piece);
+            }
 
             // Prepare next batch
             batch.clear();
@@ -116,25 +304,225 @@ impl ModelManager {
                 .add(next_token, n_cur as i32, &[0], true)
                 .map_err(|e| Error::Inference(format!("Batch add: {e}")))?;
 
-            n_cur += 1;
-
             // Decode
             ctx.decode(&mut batch)
                 .map_err(|e| Error::Inference(format!("Decode: {e}")))?;
+            cache.tokens.push(next_token);
         }
+        stats.generation = generation_start.elapsed();
 
-        // Convert tokens to string
-        let mut output = String::new();
-        let mut decoder = encoding_rs::UTF_8.new_decoder();
-        for token in output_tokens {
-            let piece = self
-                .model
-                .token_to_piece(token, &mut decoder, true, None)
-                .map_err(|e| Error::Inference(format!("Detokenization: {e}")))?;
-            output.push_str(&piece);
+        Ok(Generation {
+            text: output.trim().to_string(),
+            stats,
+        })
+    }
+
+    /// Run inference on several prompts at once and return the generations in order
+    ///
+    /// Each prompt gets its own sequence id in a shared batch, so prompt evaluation and
+    /// every generation step are decoded together. Every sequence holds its whole prompt,
+    /// since llama.cpp's per-sequence KV streams cannot share tokens between sequences.
+    /// The sequences of one batch share the configured context size, so prompts that do not
+    /// fit together are decoded in several batches. Timings cover the batch a prompt was
+    /// decoded in. `cancel` is checked between decode steps like in
+    /// [`ModelManager::infer_streaming`].
+    pub fn infer_batch(
+        &self,
+        prompts: &[&str],
+        max_new_tokens: u32,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Generation>> {
+        let prompt_tokens = prompts
+            .iter()
+            .map(|prompt| {
+                self.model
+                    .str_to_token(prompt, llama_cpp_2::model::AddBos::Always)
+                    .map_err(|e| Error::Inference(format!("Tokenization: {e}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let context_size = self.config.context_size as usize;
+        if let Some(tokens) = prompt_tokens
+            .iter()
+            .find(|tokens| tokens.len() >= context_size)
+        {
+            return Err(Error::Inference(format!(
+                "Prompt has {} tokens but the context size is {context_size}",
+                tokens.len()
+            )));
         }
 
-        Ok(output.trim().to_string())
+        // Room a sequence needs for its prompt and generated tokens, capped at the context size
+        let sequence_size =
+            |tokens: &Vec<LlamaToken>| (tokens.len() + max_new_tokens as usize).min(context_size);
+        let mut generations = Vec::with_capacity(prompts.len());
+        let mut start = 0;
+        while start < prompt_tokens.len() {
+            let mut end = start + 1;
+            let mut size = sequence_size(&prompt_tokens[start]);
+            while let Some(tokens) = prompt_tokens.get(end) {
+                let grown = size.max(sequence_size(tokens));
+                if grown * (end + 1 - start) > context_size {
+                    break;
+                }
+                size = grown;
+                end += 1;
+            }
+            generations.extend(self.decode_batch(
+                &prompt_tokens[start..end],
+                size,
+                max_new_tokens,
+                cancel,
+            )?);
+            start = end;
+        }
+        Ok(generations)
+    }
+
+    /// Decode `prompt_tokens` as one batch with `sequence_size` context tokens per sequence
+    fn decode_batch(
+        &self,
+        prompt_tokens: &[Vec<LlamaToken>],
+        sequence_size: usize,
+        max_new_tokens: u32,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Generation>> {
+        let n_seq = prompt_tokens.len();
+        let total_tokens: usize = prompt_tokens.iter().map(Vec::len).sum();
+
+        // The context is split evenly between sequences
+        let n_ctx = n_seq * sequence_size;
+        let n_batch = total_tokens.max(n_seq);
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(n_ctx as u32))
+            .with_n_batch(n_batch as u32)
+            .with_n_seq_max(n_seq as u32)
+            .with_n_threads(self.config.threads as i32)
+            .with_n_threads_batch(self.config.threads as i32);
+        let mut ctx = self
+            .model
+            .new_context(&self.backend, ctx_params)
+            .map_err(|e| Error::Inference(format!("Context creation: {e}")))?;
+
+        // Each prompt belongs to its own sequence only
+        let mut batch = LlamaBatch::new(n_batch, 1);
+        let mut sequences = Vec::with_capacity(n_seq);
+        for (seq_id, tokens) in prompt_tokens.iter().enumerate() {
+            let last_idx = tokens.len() - 1;
+            for (pos, token) in tokens.iter().enumerate() {
+                batch
+                    .add(*token, pos as i32, &[seq_id as i32], pos == last_idx)
+                    .map_err(|e| Error::Inference(format!("Batch add: {e}")))?;
+            }
+            sequences.push(BatchSequence {
+                seq_id: seq_id as i32,
+                next_pos: tokens.len() as i32,
+                logits_idx: batch.n_tokens() - 1,
+                sampler: build_sampler(&self.config.sampling),
+                decoder: encoding_rs::UTF_8.new_decoder(),
+                output: String::new(),
+                prompt_tokens: tokens.len(),
+                max_new_tokens: (max_new_tokens as usize).min(sequence_size - tokens.len()),
+                generated_tokens: 0,
+                stop_reason: None,
+            });
+        }
+
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let prompt_start = Instant::now();
+        ctx.decode(&mut batch)
+            .map_err(|e| Error::Inference(format!("Decode prompt: {e}")))?;
+        let prompt_eval = prompt_start.elapsed();
+
+        // Generate one token per unfinished sequence per decode step
+        let generation_start = Instant::now();
+        for _ in 0..max_new_tokens {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            batch.clear();
+            for sequence in sequences.iter_mut().filter(|s| s.stop_reason.is_none()) {
+                if sequence.generated_tokens == sequence.max_new_tokens {
+                    // Stopped early only when the sequence ran out of context
+                    sequence.stop_reason =
+                        Some(if sequence.max_new_tokens < max_new_tokens as usize {
+                            StopReason::ContextFull
+                        } else {
+                            StopReason::MaxTokens
+                        });
+                    continue;
+                }
+                let next_token = sequence.sampler.sample(&ctx, sequence.logits_idx);
+                if self.model.is_eog_token(next_token) {
+                    sequence.stop_reason = Some(StopReason::EndOfText);
+                    continue;
+                }
+                sequence.generated_tokens += 1;
+
+                let piece = self
+                    .model
+                    .token_to_piece(next_token, &mut sequence.decoder, true, None)
+                    .map_err(|e| Error::Inference(format!("Detokenization: {e}")))?;
+                sequence.output.push_str(&piece);
+
+                batch
+                    .add(next_token, sequence.next_pos, &[sequence.seq_id], true)
+                    .map_err(|e| Error::Inference(format!("Batch add: {e}")))?;
+                sequence.logits_idx = batch.n_tokens() - 1;
+                sequence.next_pos += 1;
+            }
+
+            if batch.n_tokens() == 0 {
+                break;
+            }
+            ctx.decode(&mut batch)
+                .map_err(|e| Error::Inference(format!("Decode: {e}")))?;
+        }
+
+        let generation = generation_start.elapsed();
+
+        Ok(sequences
+            .into_iter()
+            .map(|sequence| Generation {
+                text: sequence.output.trim().to_string(),
+                stats: GenerationStats {
+                    prompt_tokens: sequence.prompt_tokens,
+                    cached_prompt_tokens: 0,
+                    generated_tokens: sequence.generated_tokens,
+                    stop_reason: sequence.stop_reason.unwrap_or(StopReason::MaxTokens),
+                    prompt_eval,
+                    generation,
+                },
+            })
+            .collect())
+    }
+
+    /// Count the tokens `text` occupies in a prompt, without the BOS token
+    pub fn count_tokens(&self, text: &str) -> Result<usize> {
+        self.model
+            .str_to_token(text, llama_cpp_2::model::AddBos::Never)
+            .map(|tokens| tokens.len())
+            .map_err(|e| Error::Inference(format!("Tokenization: {e}")))
+    }
+
+    /// Create the llama context kept in the prompt cache
+    fn new_cached_context(&self) -> Result<LlamaContext<'static>> {
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(self.config.context_size))
+            .with_n_threads(self.config.threads as i32)
+            .with_n_threads_batch(self.config.threads as i32);
+
+        let ctx = self
+            .model
+            .new_context(&self.backend, ctx_params)
+            .map_err(|e| Error::Inference(format!("Context creation: {e}")))?;
+
+        // SAFETY: the context borrows `self.model`, which is boxed so its address
+        // stays stable for the lifetime of `self`. The context is only stored in
+        // `self.prompt_cache`, which is declared before `model` and therefore
+        // dropped first, so the borrow never outlives the model.
+        Ok(unsafe { std::mem::transmute::<LlamaContext<'_>, LlamaContext<'static>>(ctx) })
     }
 
     /// Get a reference to the underlying model
@@ -148,6 +536,48 @@ impl ModelManager {
     }
 }
 
+/// Build the sampler chain described by `sampling`
+///
+/// Greedy decoding is used when the temperature is zero; the repetition penalty still applies.
+fn build_sampler(sampling: &SamplingConfig) -> LlamaSampler {
+    let mut samplers = Vec::new();
+    if sampling.repeat_penalty != 1.0 {
+        samplers.push(LlamaSampler::penalties(
+            sampling.repeat_last_n as i32,
+            sampling.repeat_penalty,
+            0.0,
+            0.0,
+        ));
+    }
+
+    if sampling.is_greedy() {
+        samplers.push(LlamaSampler::greedy());
+        return LlamaSampler::chain_simple(samplers);
+    }
+
+    if sampling.top_k > 0 {
+        samplers.push(LlamaSampler::top_k(sampling.top_k as i32));
+    }
+    if sampling.top_p < 1.0 {
+        samplers.push(LlamaSampler::top_p(sampling.top_p, 1));
+    }
+    if sampling.min_p > 0.0 {
+        samplers.push(LlamaSampler::min_p(sampling.min_p, 1));
+    }
+    samplers.push(LlamaSampler::temp(sampling.temperature));
+    samplers.push(LlamaSampler::dist(
+        sampling.seed.unwrap_or_else(random_seed),
+    ));
+    LlamaSampler::chain_simple(samplers)
+}
+
+fn random_seed() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default()
+}
+
 fn configure_logging(config: &Config, backend: &mut LlamaBackend) -> Result<()> {
     if config.log_to_file {
         init_log_file(&config.log_path)?;
@@ -197,3 +627,81 @@ unsafe extern "C" fn log_callback(
     let message = unsafe { CStr::from_ptr(text) }.to_bytes();
     let _ = file.write_all(message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(ids: &[i32]) -> Vec<LlamaToken> {
+        ids.iter().copied().map(LlamaToken::new).collect()
+    }
+
+    #[test]
+    fn reusable_prefix_len_counts_shared_tokens() {
+        let cached = tokens(&[1, 2, 3, 4, 9, 9]);
+        let prompt = tokens(&[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(reusable_prefix_len(&cached, &prompt), 4);
+    }
+
+    #[test]
+    fn reusable_prefix_len_keeps_last_prompt_token_for_decoding() {
+        let cached = tokens(&[1, 2, 3, 4, 5]);
+        let prompt = tokens(&[1, 2, 3]);
+
+        assert_eq!(reusable_prefix_len(&cached, &prompt), 2);
+    }
+
+    #[test]
+    fn reusable_prefix_len_is_zero_without_cache() {
+        assert_eq!(reusable_prefix_len(&[], &tokens(&[1, 2, 3])), 0);
+        assert_eq!(reusable_prefix_len(&tokens(&[7, 8]), &tokens(&[1, 2])), 0);
+    }
+
+    #[test]
+    #[ignore = "needs a GGUF model in PETIT_TRAD_TEST_MODEL"]
+    fn infer_batch_decodes_several_prompts_with_a_model() {
+        let model_path = std::env::var_os("PETIT_TRAD_TEST_MODEL")
+            .expect("PETIT_TRAD_TEST_MODEL should point at a GGUF model");
+        let manager = ModelManager::new(Config {
+            model_path: model_path.into(),
+            context_size: 2048,
+            threads: 4,
+            ..Config::default()
+        })
+        .expect("model should load");
+        let prompts = [
+            "<start_of_turn>user\nTranslate into French: Hello<end_of_turn>\n<start_of_turn>model\n",
+            "<start_of_turn>user\nTranslate into French: Good night<end_of_turn>\n<start_of_turn>model\n",
+            "<start_of_turn>user\nTranslate into German: Thank you very much<end_of_turn>\n<start_of_turn>model\n",
+        ];
+
+        let generations = manager
+            .infer_batch(&prompts, 16, &CancellationToken::new())
+            .expect("batched prompts should decode");
+
+        assert_eq!(generations.len(), prompts.len());
+        for generation in &generations {
+            assert!(generation.stats.generated_tokens > 0);
+            assert!(!generation.text.is_empty());
+        }
+
+        // A context too small for all three splits them into several batches
+        let small = ModelManager::new(Config {
+            context_size: 96,
+            ..manager.config().clone()
+        })
+        .expect("model should load");
+        let generations = small
+            .infer_batch(&prompts, 16, &CancellationToken::new())
+            .expect("prompts should decode in smaller batches");
+        assert_eq!(generations.len(), prompts.len());
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            manager.infer_batch(&prompts, 16, &cancel),
+            Err(Error::Cancelled)
+        ));
+    }
+}
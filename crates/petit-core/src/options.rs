@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Per-request translation options
+
+use crate::{Error, GlossaryCandidate, Result};
+use serde::{Deserialize, Serialize};
+
+/// Register the translation should use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Formality {
+    Formal,
+    Informal,
+}
+
+impl std::str::FromStr for Formality {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "formal" => Ok(Formality::Formal),
+            "informal" => Ok(Formality::Informal),
+            other => Err(Error::Config(format!(
+                "formality must be `formal` or `informal`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// How placeholders and markup in the source are protected from the model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderMode {
+    /// Send the text as it is
+    #[default]
+    Off,
+    /// Protect placeholders and report missing or duplicated ones as warnings
+    Warn,
+    /// Protect placeholders and fail when one is missing or duplicated
+    Strict,
+}
+
+impl std::str::FromStr for PlaceholderMode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" => Ok(PlaceholderMode::Off),
+            "warn" => Ok(PlaceholderMode::Warn),
+            "strict" => Ok(PlaceholderMode::Strict),
+            other => Err(Error::Config(format!(
+                "placeholders must be `off`, `warn` or `strict`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// Options that steer a single translation
+///
+/// Unset fields fall back to the translator defaults, so `TranslationOptions::default()`
+/// produces the same prompt and output length as a plain `translate` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationOptions {
+    /// Maximum tokens to generate (translator default when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_new_tokens: Option<u32>,
+
+    /// Formal or informal register
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formality: Option<Formality>,
+
+    /// Subject area of the text, e.g. `legal`, `medical` or `UI strings`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+
+    /// Glossary override: `Some(false)` skips the glossary lookup for this request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glossary: Option<bool>,
+
+    /// Terms that must be used for this request, ahead of any glossary lookup
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub glossary_terms: Vec<GlossaryCandidate>,
+
+    /// Free-form instructions added to the prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// Placeholder protection (`off` for plain text and `warn` for documents when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholders: Option<PlaceholderMode>,
+}
+
+impl TranslationOptions {
+    /// Create options that use the translator defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of generated tokens
+    pub fn with_max_new_tokens(mut self, max_new_tokens: u32) -> Self {
+        self.max_new_tokens = Some(max_new_tokens);
+        self
+    }
+
+    /// Request a formal or informal register
+    pub fn with_formality(mut self, formality: Formality) -> Self {
+        self.formality = Some(formality);
+        self
+    }
+
+    /// Set the subject area of the text
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Turn glossary lookup on or off for this request
+    pub fn with_glossary(mut self, enabled: bool) -> Self {
+        self.glossary = Some(enabled);
+        self
+    }
+
+    /// Require these terms in addition to the translator glossary
+    pub fn with_glossary_terms(mut self, terms: Vec<GlossaryCandidate>) -> Self {
+        self.glossary_terms = terms;
+        self
+    }
+
+    /// Add free-form instructions to the prompt
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Set how placeholders and markup are protected
+    pub fn with_placeholders(mut self, mode: PlaceholderMode) -> Self {
+        self.placeholders = Some(mode);
+        self
+    }
+
+    /// Whether the glossary should be consulted for this request
+    pub fn use_glossary(&self) -> bool {
+        self.glossary.unwrap_or(true)
+    }
+
+    /// Placeholder protection for this request
+    pub fn placeholder_mode(&self) -> PlaceholderMode {
+        self.placeholders.unwrap_or_default()
+    }
+
+    /// Domain hint with surrounding whitespace removed, if it is not blank
+    pub fn domain_hint(&self) -> Option<&str> {
+        non_blank(self.domain.as_deref())
+    }
+
+    /// Extra instructions with surrounding whitespace removed, if they are not blank
+    pub fn extra_instructions(&self) -> Option<&str> {
+        non_blank(self.instructions.as_deref())
+    }
+
+    /// Check that every value is within its valid range
+    pub fn validate(&self) -> Result<()> {
+        if self.max_new_tokens == Some(0) {
+            return Err(Error::Config(
+                "max_new_tokens must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn non_blank(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_options_use_translator_defaults() {
+        let options = TranslationOptions::default();
+
+        assert!(options.use_glossary());
+        assert_eq!(options.domain_hint(), None);
+        assert_eq!(options.extra_instructions(), None);
+        assert_eq!(options.placeholder_mode(), PlaceholderMode::Off);
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    fn blank_hints_are_ignored() {
+        let options = TranslationOptions::new()
+            .with_domain("  ")
+            .with_instructions("\n Keep it short. \n");
+
+        assert_eq!(options.domain_hint(), None);
+        assert_eq!(options.extra_instructions(), Some("Keep it short."));
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_new_tokens() {
+        let err = TranslationOptions::new()
+            .with_max_new_tokens(0)
+            .validate()
+            .expect_err("zero tokens should be rejected");
+
+        assert!(err.to_string().contains("max_new_tokens"));
+    }
+
+    #[test]
+    fn formality_parses_case_insensitively() {
+        assert_eq!("Formal".parse::<Formality>().unwrap(), Formality::Formal);
+        assert_eq!(
+            " informal".parse::<Formality>().unwrap(),
+            Formality::Informal
+        );
+        assert!("casual".parse::<Formality>().is_err());
+    }
+
+    #[test]
+    fn options_deserialize_from_partial_toml() {
+        let options: TranslationOptions =
+            toml::from_str("formality = \"informal\"\ndomain = \"UI strings\"\nglossary = false\nplaceholders = \"strict\"")
+                .expect("options should parse");
+
+        assert_eq!(options.formality, Some(Formality::Informal));
+        assert_eq!(options.domain_hint(), Some("UI strings"));
+        assert!(!options.use_glossary());
+        assert_eq!(options.max_new_tokens, None);
+        assert_eq!(options.placeholder_mode(), PlaceholderMode::Strict);
+    }
+}
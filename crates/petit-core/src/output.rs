@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Structured translation results
+
+use crate::GlossaryCandidate;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+/// Why generation stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The model emitted an end-of-generation token
+    #[default]
+    EndOfText,
+    /// Generation reached `max_new_tokens`, so the text may be truncated
+    MaxTokens,
+    /// Generation filled the context window, so the text may be truncated
+    ContextFull,
+}
+
+impl StopReason {
+    /// Whether the output may be cut off
+    pub fn is_truncated(&self) -> bool {
+        !matches!(self, StopReason::EndOfText)
+    }
+}
+
+/// Token counts and timings of a generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationStats {
+    /// Tokens in the prompt
+    pub prompt_tokens: usize,
+    /// Prompt tokens served from the KV cache instead of being decoded
+    pub cached_prompt_tokens: usize,
+    /// Tokens generated by the model
+    pub generated_tokens: usize,
+    /// Why generation stopped
+    pub stop_reason: StopReason,
+    /// Time spent evaluating the prompt
+    #[serde(
+        rename = "prompt_eval_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub prompt_eval: Duration,
+    /// Time spent generating tokens
+    #[serde(
+        rename = "generation_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub generation: Duration,
+}
+
+impl GenerationStats {
+    /// Combine stats of generations that make up one translation (e.g. chunks)
+    ///
+    /// Counts and timings add up; the stop reason reports truncation if any part was truncated.
+    pub fn merge(&mut self, other: &GenerationStats) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.cached_prompt_tokens += other.cached_prompt_tokens;
+        self.generated_tokens += other.generated_tokens;
+        self.prompt_eval += other.prompt_eval;
+        self.generation += other.generation;
+        if !self.stop_reason.is_truncated() {
+            self.stop_reason = other.stop_reason;
+        }
+    }
+
+    /// Generated tokens per second, if any time was spent generating
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let secs = self.generation.as_secs_f64();
+        (secs > 0.0).then(|| self.generated_tokens as f64 / secs)
+    }
+}
+
+/// Result of a translation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationOutput {
+    /// Translated text
+    pub text: String,
+    /// Normalized source language code, detected when the request used `auto`
+    pub source_lang: String,
+    /// Normalized target language code
+    pub target_lang: String,
+    /// Glossary candidates injected into the prompt
+    #[serde(default)]
+    pub glossary_candidates: Vec<GlossaryCandidate>,
+    /// Token counts, stop reason and timings
+    #[serde(flatten)]
+    pub stats: GenerationStats,
+    /// Problems found in the translation, e.g. missing placeholders
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl TranslationOutput {
+    /// Create an output carrying only text, for backends without generation details
+    pub fn from_text(
+        text: impl Into<String>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+    ) -> Self {
+        Self {
+            text: text.into(),
+            source_lang: source_lang.into(),
+            target_lang: target_lang.into(),
+            glossary_candidates: Vec::new(),
+            stats: GenerationStats::default(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+fn serialize_millis<S>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+fn deserialize_millis<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(millis / 1000.0).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_adds_counts_and_keeps_truncation() {
+        let mut total = GenerationStats {
+            prompt_tokens: 40,
+            cached_prompt_tokens: 30,
+            generated_tokens: 12,
+            stop_reason: StopReason::MaxTokens,
+            prompt_eval: Duration::from_millis(10),
+            generation: Duration::from_millis(100),
+        };
+
+        total.merge(&GenerationStats {
+            prompt_tokens: 20,
+            generated_tokens: 8,
+            stop_reason: StopReason::EndOfText,
+            prompt_eval: Duration::from_millis(5),
+            generation: Duration::from_millis(60),
+            ..GenerationStats::default()
+        });
+
+        assert_eq!(total.prompt_tokens, 60);
+        assert_eq!(total.cached_prompt_tokens, 30);
+        assert_eq!(total.generated_tokens, 20);
+        assert_eq!(total.stop_reason, StopReason::MaxTokens);
+        assert_eq!(total.prompt_eval, Duration::from_millis(15));
+        assert_eq!(total.generation, Duration::from_millis(160));
+    }
+
+    #[test]
+    fn tokens_per_second_needs_generation_time() {
+        let stats = GenerationStats {
+            generated_tokens: 50,
+            generation: Duration::from_millis(500),
+            ..GenerationStats::default()
+        };
+
+        assert_eq!(stats.tokens_per_second(), Some(100.0));
+        assert_eq!(GenerationStats::default().tokens_per_second(), None);
+    }
+
+    #[test]
+    fn output_round_trips_through_json() {
+        let mut output = TranslationOutput::from_text("Bonjour", "en", "fr");
+        output.stats = GenerationStats {
+            generated_tokens: 3,
+            stop_reason: StopReason::MaxTokens,
+            generation: Duration::from_millis(250),
+            ..GenerationStats::default()
+        };
+
+        let json = serde_json::to_string(&output).expect("output should serialize");
+        let parsed: TranslationOutput = serde_json::from_str(&json).expect("output should parse");
+
+        assert_eq!(parsed, output);
+    }
+
+    #[test]
+    fn stop_reason_reports_truncation() {
+        assert!(!StopReason::EndOfText.is_truncated());
+        assert!(StopReason::MaxTokens.is_truncated());
+        assert!(StopReason::ContextFull.is_truncated());
+    }
+}
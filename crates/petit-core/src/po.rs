@@ -0,0 +1,567 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Gettext PO and POT catalog translation
+//!
+//! Entries with an empty `msgstr` are translated and marked `#, fuzzy` so a translator reviews
+//! them. Every other line is written back unchanged: translated and obsolete entries, comments,
+//! `msgctxt`, flags and the header, apart from an empty `Language` or a template `Plural-Forms`.
+//!
+//! Plural entries get one `msgstr[n]` per plural form of the target language, taken from the
+//! header when it declares them: form 0 comes from `msgid`, the others from `msgid_plural`.
+
+use crate::language::plural_forms;
+use crate::protect::add_instructions;
+use crate::{Error, Result, TranslationOptions, TranslationRequest, Translator};
+
+/// One catalog entry and the lines it spans
+#[derive(Debug, Default)]
+struct Entry {
+    /// Index of the first line
+    start: usize,
+    /// Index one past the last line
+    end: usize,
+    /// Line holding the `#,` flags
+    flags: Option<usize>,
+    /// Where a new `#,` line goes: after translator, extracted and reference comments
+    flags_at: usize,
+    /// Extracted `#.` comments, passed to the model as notes
+    notes: Vec<String>,
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgid_plural: Option<String>,
+    msgstr: Vec<String>,
+    /// Lines of the `msgstr` keywords and their continuations
+    msgstr_lines: Option<(usize, usize)>,
+    obsolete: bool,
+}
+
+impl Entry {
+    fn is_header(&self) -> bool {
+        self.msgid.as_deref() == Some("") && self.msgctxt.is_none()
+    }
+
+    fn needs_translation(&self) -> bool {
+        !self.obsolete
+            && !self.is_header()
+            && self.msgstr.iter().all(String::is_empty)
+            && self
+                .msgid
+                .as_deref()
+                .is_some_and(|msgid| msgid.chars().any(char::is_alphabetic))
+    }
+}
+
+/// Which string of an entry a keyword continues
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    Plural,
+    Str(usize),
+}
+
+/// Translate the untranslated entries of a PO or POT catalog
+pub fn translate_po(
+    translator: &dyn Translator,
+    source: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let newline = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    let entries = parse_entries(&lines)?;
+    let header = entries.iter().find(|entry| entry.is_header());
+    let plural_count = header
+        .and_then(|header| header.msgstr.first())
+        .and_then(|msgstr| declared_plural_count(msgstr))
+        .unwrap_or_else(|| plural_forms(target_lang).0);
+
+    let pending: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| entry.needs_translation())
+        .collect();
+    // Source strings in request order, with the newlines gettext wants kept around them
+    let mut sources: Vec<&str> = Vec::new();
+    let mut requests = Vec::new();
+    for entry in &pending {
+        let options = entry_options(entry, options);
+        let msgid = entry.msgid.as_deref().unwrap_or_default();
+        let texts = match entry.msgid_plural.as_deref() {
+            Some(plural) if plural_count == 1 => vec![plural],
+            Some(plural) => vec![msgid, plural],
+            None => vec![msgid],
+        };
+        for text in texts {
+            sources.push(text);
+            requests.push(
+                TranslationRequest::new(text.trim_matches('\n'), source_lang, target_lang)
+                    .with_options(options.clone()),
+            );
+        }
+    }
+    let mut outputs = translator
+        .translate_batch(&requests)
+        .into_iter()
+        .zip(sources)
+        .map(|(output, source)| output.map(|output| keep_newlines(source, &output.text)));
+
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut cursor = 0;
+    let mut has_plurals = false;
+    for entry in &pending {
+        out.extend(
+            lines[cursor..entry.start]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        cursor = entry.end;
+        let mut next = || outputs.next().expect("one output per request");
+        let msgstr = match entry.msgid_plural {
+            Some(_) if plural_count == 1 => vec![next()?],
+            Some(_) => {
+                let singular = next()?;
+                let plural = next()?;
+                let mut forms = vec![singular];
+                forms.resize(plural_count, plural);
+                forms
+            }
+            None => vec![next()?],
+        };
+        has_plurals |= entry.msgid_plural.is_some();
+        write_entry(&mut out, &lines, entry, &msgstr);
+    }
+    out.extend(lines[cursor..].iter().map(|line| line.to_string()));
+
+    // The header is rewritten in place, which needs its line numbers unchanged
+    if let Some(header) = header.filter(|header| {
+        pending
+            .first()
+            .is_none_or(|first| first.start > header.start)
+    }) {
+        update_header(&mut out, header, target_lang, has_plurals);
+    }
+    let mut document = out.join(newline);
+    if source.ends_with('\n') {
+        document.push_str(newline);
+    }
+    Ok(document)
+}
+
+/// Options carrying the entry's context and developer notes
+fn entry_options(entry: &Entry, options: &TranslationOptions) -> TranslationOptions {
+    let mut instructions = String::from("The text is a user interface string.");
+    if let Some(context) = &entry.msgctxt {
+        instructions.push_str(&format!("\nIts context is \"{context}\"."));
+    }
+    for note in &entry.notes {
+        instructions.push_str(&format!("\nNote from the developers: {note}"));
+    }
+    add_instructions(options, &instructions)
+}
+
+/// Give a translation the leading and trailing newlines of its `msgid`, as gettext requires
+fn keep_newlines(original: &str, translation: &str) -> String {
+    let leading = &original[..original.len() - original.trim_start_matches('\n').len()];
+    let trailing = &original[original.trim_end_matches('\n').len()..];
+    format!("{leading}{}{trailing}", translation.trim_matches('\n'))
+}
+
+/// Copy an entry with `fuzzy` added and its `msgstr` replaced
+fn write_entry(out: &mut Vec<String>, lines: &[&str], entry: &Entry, msgstr: &[String]) {
+    let (msgstr_start, msgstr_end) = entry.msgstr_lines.unwrap_or((entry.end, entry.end));
+    for (index, line) in lines
+        .iter()
+        .enumerate()
+        .take(msgstr_start)
+        .skip(entry.start)
+    {
+        if index == entry.flags_at && entry.flags.is_none() {
+            out.push("#, fuzzy".to_string());
+        }
+        if Some(index) == entry.flags {
+            out.push(add_fuzzy(line));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    if entry.msgid_plural.is_some() {
+        for (index, form) in msgstr.iter().enumerate() {
+            push_string(out, &format!("msgstr[{index}]"), form);
+        }
+    } else {
+        push_string(out, "msgstr", &msgstr[0]);
+    }
+    out.extend(
+        lines[msgstr_end..entry.end]
+            .iter()
+            .map(|line| line.to_string()),
+    );
+}
+
+fn add_fuzzy(flags: &str) -> String {
+    let existing = flags.trim_start_matches("#,").trim();
+    if existing.split(',').any(|flag| flag.trim() == "fuzzy") {
+        flags.to_string()
+    } else if existing.is_empty() {
+        "#, fuzzy".to_string()
+    } else {
+        format!("#, fuzzy, {existing}")
+    }
+}
+
+/// Write `keyword "value"`, breaking after each embedded newline like msgmerge does
+fn push_string(out: &mut Vec<String>, keyword: &str, value: &str) {
+    if value.trim_end_matches('\n').contains('\n') {
+        out.push(format!("{keyword} \"\""));
+        for piece in value.split_inclusive('\n') {
+            out.push(format!("\"{}\"", escape(piece)));
+        }
+    } else {
+        out.push(format!("{keyword} \"{}\"", escape(value)));
+    }
+}
+
+/// Fill in the target language and plural forms of a template header
+fn update_header(out: &mut [String], header: &Entry, target_lang: &str, has_plurals: bool) {
+    let Some((start, end)) = header.msgstr_lines else {
+        return;
+    };
+    let (count, expression) = plural_forms(target_lang);
+    for line in &mut out[start..end] {
+        if line.trim() == "\"Language: \\n\"" {
+            *line = format!("\"Language: {}\\n\"", gettext_locale(target_lang));
+        } else if line.trim_start().starts_with("\"Plural-Forms:")
+            && has_plurals
+            && declared_plural_count(&unquote(line.trim()).unwrap_or_default()).is_none()
+        {
+            *line = format!("\"Plural-Forms: nplurals={count}; plural={expression};\\n\"");
+        }
+    }
+}
+
+/// `nplurals` of a header, unless it is missing or still the template placeholder
+fn declared_plural_count(header: &str) -> Option<usize> {
+    header
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Plural-Forms:"))
+        .and_then(|forms| {
+            forms
+                .split(';')
+                .find_map(|part| part.trim().strip_prefix("nplurals="))
+        })
+        .and_then(|count| count.trim().parse().ok())
+        .filter(|count| *count > 0)
+}
+
+/// `pt-br` -> `pt_BR`
+fn gettext_locale(code: &str) -> String {
+    match code.split_once(['-', '_']) {
+        Some((language, region)) => {
+            format!("{}_{}", language.to_lowercase(), region.to_uppercase())
+        }
+        None => code.to_lowercase(),
+    }
+}
+
+fn parse_entries(lines: &[&str]) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut start = 0;
+    for index in 0..=lines.len() {
+        if index < lines.len() && !lines[index].trim().is_empty() {
+            continue;
+        }
+        if index > start {
+            entries.push(parse_entry(lines, start, index)?);
+        }
+        start = index + 1;
+    }
+    Ok(entries)
+}
+
+fn parse_entry(lines: &[&str], start: usize, end: usize) -> Result<Entry> {
+    let mut entry = Entry {
+        start,
+        end,
+        flags_at: start,
+        ..Entry::default()
+    };
+    let mut field = None;
+    for (index, line) in lines.iter().enumerate().take(end).skip(start) {
+        let line = line.trim();
+        let error = |message: &str| Error::Document(format!("line {}: {message}", index + 1));
+        if line.starts_with("#~") {
+            entry.obsolete = true;
+            continue;
+        }
+        if line.starts_with("#,") {
+            entry.flags = Some(index);
+            continue;
+        }
+        if let Some(note) = line.strip_prefix("#.") {
+            entry.notes.push(note.trim().to_string());
+        }
+        if line.starts_with('#') {
+            if !line.starts_with("#|") && field.is_none() && entry.flags.is_none() {
+                entry.flags_at = index + 1;
+            }
+            continue;
+        }
+
+        let (current, value) = if line.starts_with('"') {
+            (field.ok_or_else(|| error("string outside an entry"))?, line)
+        } else {
+            let (keyword, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error("expected a keyword and a string"))?;
+            let current = match keyword {
+                "msgctxt" => Field::Context,
+                "msgid" => Field::Id,
+                "msgid_plural" => Field::Plural,
+                "msgstr" => Field::Str(0),
+                _ => keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|number| number.parse().ok())
+                    .map(Field::Str)
+                    .ok_or_else(|| error(&format!("unknown keyword `{keyword}`")))?,
+            };
+            if let Field::Str(form) = current {
+                if entry.msgstr.len() <= form {
+                    entry.msgstr.resize(form + 1, String::new());
+                }
+                entry.msgstr_lines.get_or_insert((index, index + 1));
+            }
+            (current, value.trim())
+        };
+        let text = unquote(value).ok_or_else(|| error("expected a quoted string"))?;
+        match current {
+            Field::Context => entry
+                .msgctxt
+                .get_or_insert_with(String::new)
+                .push_str(&text),
+            Field::Id => entry.msgid.get_or_insert_with(String::new).push_str(&text),
+            Field::Plural => entry
+                .msgid_plural
+                .get_or_insert_with(String::new)
+                .push_str(&text),
+            Field::Str(form) => {
+                entry.msgstr[form].push_str(&text);
+                if let Some((_, msgstr_end)) = &mut entry.msgstr_lines {
+                    *msgstr_end = index + 1;
+                }
+            }
+        }
+        field = Some(current);
+    }
+
+    if entry.msgid.is_none() && !entry.obsolete && field.is_some() {
+        return Err(Error::Document(format!(
+            "line {}: entry without msgid",
+            start + 1
+        )));
+    }
+    Ok(entry)
+}
+
+/// Decode a C-style quoted string
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next()? {
+            'n' => text.push('\n'),
+            't' => text.push('\t'),
+            'r' => text.push('\r'),
+            other => text.push(other),
+        }
+    }
+    Some(text)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Recorder;
+
+    fn translate(source: &str, target_lang: &str) -> (String, Vec<(String, Option<String>)>) {
+        let recorder = Recorder::default();
+        let output = translate_po(
+            &recorder,
+            source,
+            "en",
+            target_lang,
+            &TranslationOptions::default(),
+        )
+        .expect("catalog should translate");
+        (output, recorder.requests())
+    }
+
+    const TEMPLATE: &str = r#"# Translations for the demo app.
+msgid ""
+msgstr ""
+"Project-Id-Version: demo 1.0\n"
+"Language: \n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n"
+
+#. Shown on the toolbar
+#: src/main.c:10
+#, c-format
+msgctxt "toolbar"
+msgid "Open %s"
+msgstr ""
+
+#: src/main.c:20
+msgid "Saved"
+msgstr "Enregistré"
+
+#: src/main.c:30
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] ""
+msgstr[1] ""
+
+msgid ""
+"First line\n"
+"Second line\n"
+msgstr ""
+
+#~ msgid "Old"
+#~ msgstr ""
+"#;
+
+    #[test]
+    fn translates_empty_entries_and_marks_them_fuzzy() {
+        let (output, requests) = translate(TEMPLATE, "fr");
+
+        assert_eq!(
+            output,
+            r#"# Translations for the demo app.
+msgid ""
+msgstr ""
+"Project-Id-Version: demo 1.0\n"
+"Language: fr\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+#. Shown on the toolbar
+#: src/main.c:10
+#, fuzzy, c-format
+msgctxt "toolbar"
+msgid "Open %s"
+msgstr "OPEN %S"
+
+#: src/main.c:20
+msgid "Saved"
+msgstr "Enregistré"
+
+#: src/main.c:30
+#, fuzzy
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "ONE FILE"
+msgstr[1] "%D FILES"
+
+#, fuzzy
+msgid ""
+"First line\n"
+"Second line\n"
+msgstr ""
+"FIRST LINE\n"
+"SECOND LINE\n"
+
+#~ msgid "Old"
+#~ msgstr ""
+"#
+        );
+        let texts: Vec<&str> = requests.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Open %s", "One file", "%d files", "First line\nSecond line"]
+        );
+        let instructions = requests[0].1.as_deref().unwrap();
+        assert!(instructions.contains("\"toolbar\""));
+        assert!(instructions.contains("Shown on the toolbar"));
+    }
+
+    #[test]
+    fn plural_forms_follow_the_target_language() {
+        let (output, requests) = translate(TEMPLATE, "ru");
+        assert!(
+            output.contains(
+                "msgstr[0] \"ONE FILE\"\nmsgstr[1] \"%D FILES\"\nmsgstr[2] \"%D FILES\"\n"
+            )
+        );
+        assert!(output.contains("\"Plural-Forms: nplurals=3; plural=(n%10==1"));
+        assert_eq!(requests.len(), 4);
+
+        let (output, _) = translate(TEMPLATE, "ja");
+        assert!(output.contains("msgid_plural \"%d files\"\nmsgstr[0] \"%D FILES\"\n\n"));
+    }
+
+    #[test]
+    fn declared_plural_forms_win_over_the_language_table() {
+        let source = "msgid \"\"\nmsgstr \"Plural-Forms: nplurals=3; plural=(n==1 ? 0 : n==2 ? 1 : 2);\\n\"\n\nmsgid \"day\"\nmsgid_plural \"days\"\nmsgstr[0] \"\"\n";
+
+        let (output, _) = translate(source, "de");
+
+        assert!(output.ends_with("msgstr[0] \"DAY\"\nmsgstr[1] \"DAYS\"\nmsgstr[2] \"DAYS\"\n"));
+        assert!(output.contains("nplurals=3; plural=(n==1 ? 0 : n==2 ? 1 : 2)"));
+    }
+
+    #[test]
+    fn rejects_malformed_catalogs() {
+        let recorder = Recorder::default();
+        let options = TranslationOptions::default();
+
+        let err = translate_po(
+            &recorder,
+            "msgid \"a\"\nmsgfoo \"b\"\n",
+            "en",
+            "fr",
+            &options,
+        )
+        .expect_err("unknown keyword should fail");
+        assert_eq!(
+            err.to_string(),
+            "Document error: line 2: unknown keyword `msgfoo`"
+        );
+
+        let err = translate_po(&recorder, "msgid \"a\nmsgstr \"\"\n", "en", "fr", &options)
+            .expect_err("unterminated string should fail");
+        assert!(matches!(err, Error::Document(_)));
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let value = "Say \"hi\"\tnow\\";
+        assert_eq!(unquote(&format!("\"{}\"", escape(value))).unwrap(), value);
+        assert_eq!(add_fuzzy("#, python-format"), "#, fuzzy, python-format");
+        assert_eq!(add_fuzzy("#, fuzzy"), "#, fuzzy");
+    }
+}
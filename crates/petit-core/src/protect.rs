@@ -0,0 +1,541 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Placeholder and markup protection
+//!
+//! Spans the model must not touch (printf and brace placeholders, tags, URLs and inline
+//! code) are replaced with numbered markers like `⟦0⟧` before prompting and put back
+//! afterwards. Markers that come back missing or duplicated are reported.
+
+use crate::{Error, PlaceholderMode, Result, TranslationOptions};
+
+/// Instruction added to prompts whose text contains markers
+const MARKER_INSTRUCTIONS: &str = "Copy every marker like ⟦0⟧ to the translation unchanged; \
+     it stands for a placeholder, code, a link or a tag.";
+
+/// printf conversion characters, including `%@` from Objective-C
+const PRINTF_CONVERSIONS: &str = "diouxXeEfFgGaAcspn@";
+
+/// Characters allowed in `{name}`, `{0}` and `{0:.2f}` style placeholders
+fn is_brace_placeholder_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | ':' | '!' | '$')
+}
+
+/// Text with protected spans replaced by markers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtectedText {
+    /// Text sent to the model, with `⟦n⟧` in place of protected span `n`
+    pub text: String,
+    /// Original spans, indexed by marker number
+    pub spans: Vec<String>,
+}
+
+impl ProtectedText {
+    /// Text without any protected spans
+    pub fn unprotected(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Whether any span was replaced by a marker
+    pub fn has_spans(&self) -> bool {
+        !self.spans.is_empty()
+    }
+
+    /// Put the original spans back into `output` and check every marker came back once
+    ///
+    /// With [`PlaceholderMode::Strict`] any missing or duplicated marker is an
+    /// [`Error::Placeholder`]. Otherwise the problems are returned as warnings and the text is
+    /// kept as the model wrote it: duplicated markers are all restored and missing spans are
+    /// left out rather than pasted somewhere they may not belong.
+    pub fn restore(&self, output: &str, mode: PlaceholderMode) -> Result<(String, Vec<String>)> {
+        let (text, counts) = replace_markers(output, &self.spans);
+        let mut issues = Vec::new();
+        for (span, &count) in self.spans.iter().zip(&counts) {
+            match count {
+                0 => issues.push(format!(
+                    "placeholder `{span}` is missing from the translation"
+                )),
+                1 => {}
+                count => issues.push(format!(
+                    "placeholder `{span}` appears {count} times in the translation"
+                )),
+            }
+        }
+        if mode == PlaceholderMode::Strict && !issues.is_empty() {
+            return Err(Error::Placeholder(issues.join("; ")));
+        }
+        Ok((text, issues))
+    }
+}
+
+/// Replace protected spans of `text` with markers
+///
+/// Markers already in the text, e.g. from a document format, are protected as well so
+/// their numbering cannot clash.
+pub fn protect(text: &str) -> ProtectedText {
+    let mut protected = ProtectedText::default();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = protected_span_len(rest, protected.text.chars().next_back());
+        if len > 0 {
+            protected.text.push_str(&marker(protected.spans.len()));
+            protected.spans.push(rest[..len].to_string());
+            rest = &rest[len..];
+        } else {
+            protected.text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    protected
+}
+
+/// Protect `text` unless `mode` turns protection off
+pub(crate) fn protect_with_mode(text: &str, mode: PlaceholderMode) -> ProtectedText {
+    match mode {
+        PlaceholderMode::Off => ProtectedText::unprotected(text),
+        PlaceholderMode::Warn | PlaceholderMode::Strict => protect(text),
+    }
+}
+
+/// Length in bytes of the protected span at the start of `text`, or 0
+fn protected_span_len(text: &str, previous: Option<char>) -> usize {
+    let after_word = previous.is_some_and(char::is_alphanumeric);
+    [
+        marker_len(text),
+        if after_word { 0 } else { url_len(text) },
+        inline_code_len(text),
+        tag_len(text),
+        brace_len(text),
+        printf_len(text, previous),
+    ]
+    .into_iter()
+    .find(|len| *len > 0)
+    .unwrap_or(0)
+}
+
+/// `⟦n⟧` markers
+fn marker_len(text: &str) -> usize {
+    let Some(rest) = text.strip_prefix('⟦') else {
+        return 0;
+    };
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && rest[digits..].starts_with('⟧') {
+        '⟦'.len_utf8() + digits + '⟧'.len_utf8()
+    } else {
+        0
+    }
+}
+
+/// `http://` and `https://` URLs, without trailing punctuation
+fn url_len(text: &str) -> usize {
+    let starts_with = |scheme: &str| {
+        text.as_bytes()
+            .get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme.as_bytes()))
+    };
+    if !starts_with("http://") && !starts_with("https://") {
+        return 0;
+    }
+    let mut url = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '`'))
+        .next()
+        .unwrap_or_default();
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+        // Keep a closing bracket that matches one inside the URL
+        let trimmed = match trimmed.chars().next_back() {
+            Some(close @ (')' | ']')) => {
+                let open = if close == ')' { '(' } else { '[' };
+                if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    if url.trim_end_matches('/').ends_with(':') {
+        return 0;
+    }
+    url.len()
+}
+
+/// Inline code between matching backtick runs
+fn inline_code_len(text: &str) -> usize {
+    let ticks = text.chars().take_while(|c| *c == '`').count();
+    if ticks == 0 {
+        return 0;
+    }
+    let fence = &text[..ticks];
+    let mut offset = ticks;
+    while let Some(found) = text[offset..].find(fence) {
+        let start = offset + found;
+        let run = text[start..].chars().take_while(|c| *c == '`').count();
+        if run == ticks {
+            return start + ticks;
+        }
+        offset = start + run;
+    }
+    0
+}
+
+/// HTML and XML tags and comments, each tag protected on its own
+fn tag_len(text: &str) -> usize {
+    if text.starts_with("<!--") {
+        return text.find("-->").map_or(0, |end| end + 3);
+    }
+    let Some(rest) = text.strip_prefix('<') else {
+        return 0;
+    };
+    let name = rest.strip_prefix('/').unwrap_or(rest);
+    if !name.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) {
+        return 0;
+    }
+    match rest.find(['<', '>']) {
+        Some(end) if rest[end..].starts_with('>') => end + 2,
+        _ => 0,
+    }
+}
+
+/// `{0}`, `{name}`, `{{name}}`, `${name}` and `%{name}` placeholders
+fn brace_len(text: &str) -> usize {
+    if let Some(rest) = text.strip_prefix("{{") {
+        return match rest.find("}}") {
+            Some(end) if !rest[..end].contains(['\n', '{']) => end + 4,
+            _ => 0,
+        };
+    }
+    let prefix = if text.starts_with("${") || text.starts_with("%{") {
+        1
+    } else {
+        0
+    };
+    let Some(rest) = text[prefix..].strip_prefix('{') else {
+        return 0;
+    };
+    let name_len = rest
+        .chars()
+        .take_while(|c| is_brace_placeholder_char(*c))
+        .map(char::len_utf8)
+        .sum::<usize>();
+    let starts_well = rest
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_');
+    if starts_well && rest[name_len..].starts_with('}') {
+        prefix + name_len + 2
+    } else {
+        0
+    }
+}
+
+/// printf placeholders such as `%s`, `%1$d`, `%-5.2f`, `%(name)s` and `%%`
+///
+/// A `%` right after a number is a percent sign, not a placeholder: `20%ige`, `50%of`. What
+/// follows the conversion is not looked at, so `%dpx` and `%d个文件` stay protected.
+fn printf_len(text: &str, previous: Option<char>) -> usize {
+    let Some(rest) = text.strip_prefix('%') else {
+        return 0;
+    };
+    if rest.starts_with('%') {
+        return 2;
+    }
+    if previous.is_some_and(char::is_numeric) {
+        return 0;
+    }
+    let bytes = rest.as_bytes();
+    let mut index = 0;
+    if bytes.first() == Some(&b'(') {
+        match rest.find(')') {
+            Some(end)
+                if end > 1
+                    && rest[1..end]
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                index = end + 1;
+            }
+            _ => return 0,
+        }
+    } else {
+        // Positional argument like `%1$s`
+        let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits > 0 && bytes.get(digits) == Some(&b'$') {
+            index = digits + 1;
+        }
+    }
+    index += bytes[index..]
+        .iter()
+        .take_while(|b| matches!(b, b'-' | b'+' | b'0' | b'#'))
+        .count();
+    index += bytes[index..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if bytes.get(index) == Some(&b'.') {
+        index += 1;
+        index += bytes[index..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+    }
+    index += bytes[index..]
+        .iter()
+        .take_while(|b| matches!(b, b'h' | b'l' | b'L' | b'q' | b'j' | b'z' | b't'))
+        .count();
+    let is_conversion = bytes
+        .get(index)
+        .is_some_and(|conversion| PRINTF_CONVERSIONS.as_bytes().contains(conversion));
+    if is_conversion { index + 2 } else { 0 }
+}
+
+/// Marker that stands for protected span `index`
+pub(crate) fn marker(index: usize) -> String {
+    format!("⟦{index}⟧")
+}
+
+/// `options` with `extra` added after any instructions it already has
+pub(crate) fn add_instructions(options: &TranslationOptions, extra: &str) -> TranslationOptions {
+    let mut options = options.clone();
+    options.instructions = Some(match options.extra_instructions() {
+        Some(existing) => format!("{existing}\n{extra}"),
+        None => extra.to_string(),
+    });
+    options
+}
+
+/// Options for a text, asking the model to keep markers when the text has any
+pub(crate) fn marker_options(
+    options: &TranslationOptions,
+    has_markers: bool,
+) -> TranslationOptions {
+    let asked = options
+        .extra_instructions()
+        .is_some_and(|instructions| instructions.contains(MARKER_INSTRUCTIONS));
+    if has_markers && !asked {
+        add_instructions(options, MARKER_INSTRUCTIONS)
+    } else {
+        options.clone()
+    }
+}
+
+/// Replace markers with the spans they stand for
+pub(crate) fn restore_markers(text: String, protected: &[String]) -> String {
+    replace_markers(&text, protected).0
+}
+
+/// Replace known markers in a single pass, counting how often each one occurs
+///
+/// Unknown markers are kept as they are.
+pub(crate) fn replace_markers(text: &str, spans: &[String]) -> (String, Vec<usize>) {
+    let mut counts = vec![0; spans.len()];
+    let mut restored = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('⟦') {
+        restored.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let len = marker_len(rest);
+        let index = (len > 0)
+            .then(|| {
+                rest['⟦'.len_utf8()..len - '⟧'.len_utf8()]
+                    .parse::<usize>()
+                    .ok()
+            })
+            .flatten()
+            .filter(|index| *index < spans.len());
+        match index {
+            Some(index) => {
+                counts[index] += 1;
+                restored.push_str(&spans[index]);
+                rest = &rest[len..];
+            }
+            None => {
+                restored.push('⟦');
+                rest = &rest['⟦'.len_utf8()..];
+            }
+        }
+    }
+    restored.push_str(rest);
+    (restored, counts)
+}
+
+/// Restores markers in streamed output, holding back a marker split across pieces
+pub(crate) struct StreamRestorer<'a> {
+    spans: &'a [String],
+    pending: String,
+}
+
+impl<'a> StreamRestorer<'a> {
+    pub(crate) fn new(spans: &'a [String]) -> Self {
+        Self {
+            spans,
+            pending: String::new(),
+        }
+    }
+
+    /// Text of `piece` that is ready to show, with complete markers restored
+    pub(crate) fn push(&mut self, piece: &str) -> String {
+        self.pending.push_str(piece);
+        let ready = match self.pending.rfind('⟦') {
+            Some(start) if !self.pending[start..].contains('⟧') => start,
+            _ => self.pending.len(),
+        };
+        let ready: String = self.pending.drain(..ready).collect();
+        replace_markers(&ready, self.spans).0
+    }
+
+    /// Whatever is still held back at the end of generation
+    pub(crate) fn finish(self) -> String {
+        replace_markers(&self.pending, self.spans).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_placeholders_tags_urls_and_code() {
+        let protected = protect(
+            "Hello %s, you have {0} new <b>messages</b> at https://example.com/inbox. Run `petit --help` or %1$d {{count}} %{name} ${user} %(total).2f 100%%",
+        );
+
+        assert_eq!(
+            protected.text,
+            "Hello ⟦0⟧, you have ⟦1⟧ new ⟦2⟧messages⟦3⟧ at ⟦4⟧. Run ⟦5⟧ or ⟦6⟧ ⟦7⟧ ⟦8⟧ ⟦9⟧ ⟦10⟧ 100⟦11⟧"
+        );
+        assert_eq!(
+            protected.spans,
+            [
+                "%s",
+                "{0}",
+                "<b>",
+                "</b>",
+                "https://example.com/inbox",
+                "`petit --help`",
+                "%1$d",
+                "{{count}}",
+                "%{name}",
+                "${user}",
+                "%(total).2f",
+                "%%",
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_ordinary_text_alone() {
+        for text in [
+            "50% off, 3 < 4 and {not a placeholder}",
+            "Ratio 5%, see http: later",
+            "a { b } c",
+            "eine 20%ige Lösung",
+            "50%of the votes",
+            "Rate 5%i.",
+        ] {
+            let protected = protect(text);
+            assert_eq!(protected.text, text);
+            assert!(!protected.has_spans(), "{text}");
+        }
+    }
+
+    #[test]
+    fn masks_placeholders_glued_to_text() {
+        for (text, masked, spans) in [
+            ("%dx%d", "⟦0⟧x⟦1⟧", &["%d", "%d"][..]),
+            ("%.1fMB free", "⟦0⟧MB free", &["%.1f"][..]),
+            ("Width: %dpx", "Width: ⟦0⟧px", &["%d"][..]),
+            ("已删除%d个文件", "已删除⟦0⟧个文件", &["%d"][..]),
+            ("%sのファイル", "⟦0⟧のファイル", &["%s"][..]),
+        ] {
+            let protected = protect(text);
+            assert_eq!(protected.text, masked, "{text}");
+            assert_eq!(protected.spans, spans, "{text}");
+        }
+    }
+
+    #[test]
+    fn url_keeps_balanced_brackets_and_drops_punctuation() {
+        let protected = protect("See (https://en.wikipedia.org/wiki/Rust_(language)).");
+
+        assert_eq!(protected.text, "See (⟦0⟧).");
+        assert_eq!(
+            protected.spans,
+            ["https://en.wikipedia.org/wiki/Rust_(language)"]
+        );
+    }
+
+    #[test]
+    fn existing_markers_are_renumbered_and_restored() {
+        let protected = protect("⟦3⟧ and %s");
+        assert_eq!(protected.text, "⟦0⟧ and ⟦1⟧");
+
+        let (text, warnings) = protected
+            .restore("⟦1⟧ et ⟦0⟧", PlaceholderMode::Warn)
+            .unwrap();
+        assert_eq!(text, "%s et ⟦3⟧");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn restore_reports_missing_and_duplicated_markers() {
+        let protected = protect("Delete %s from <b>trash</b>?");
+
+        let (text, warnings) = protected
+            .restore(
+                "Supprimer ⟦0⟧ ⟦0⟧ de la corbeille⟦2⟧ ?",
+                PlaceholderMode::Warn,
+            )
+            .unwrap();
+        assert_eq!(text, "Supprimer %s %s de la corbeille</b> ?");
+        assert_eq!(
+            warnings,
+            [
+                "placeholder `%s` appears 2 times in the translation",
+                "placeholder `<b>` is missing from the translation",
+            ]
+        );
+
+        let err = protected
+            .restore("Supprimer ⟦0⟧ de la corbeille ?", PlaceholderMode::Strict)
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::Placeholder(message) if message.contains("`</b>` is missing"))
+        );
+    }
+
+    #[test]
+    fn stream_restorer_waits_for_split_markers() {
+        let spans = vec!["%s".to_string()];
+        let mut restorer = StreamRestorer::new(&spans);
+
+        assert_eq!(restorer.push("Bonjour ⟦"), "Bonjour ");
+        assert_eq!(restorer.push("0⟧ !"), "%s !");
+        assert_eq!(restorer.push(" ⟦"), " ");
+        assert_eq!(restorer.finish(), "⟦");
+    }
+
+    #[test]
+    fn stream_restorer_matches_restore_with_missing_spans() {
+        let protected = protect("Delete %s from <b>trash</b>?");
+        let output = "Supprimer ⟦0⟧ de la corbeille⟦2⟧ ?";
+        let mut restorer = StreamRestorer::new(&protected.spans);
+
+        let mut streamed = String::new();
+        for piece in ["Supprimer ⟦", "0⟧ de la corbeille", "⟦2⟧ ?"] {
+            streamed.push_str(&restorer.push(piece));
+        }
+        streamed.push_str(&restorer.finish());
+
+        let (text, _) = protected.restore(output, PlaceholderMode::Warn).unwrap();
+        assert_eq!(streamed, text);
+        assert_eq!(streamed, "Supprimer %s de la corbeille</b> ?");
+    }
+}
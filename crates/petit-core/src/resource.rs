@@ -0,0 +1,509 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! JSON and YAML i18n resource files
+//!
+//! The tree is walked in file order and only string leaves are translated; keys, numbers and
+//! booleans are copied. Interpolation tokens (`{{name}}`, `%{count}`, `{name}`) and the syntax of
+//! ICU `plural` and `select` arguments are swapped for `⟦n⟧` markers, so only the messages inside
+//! them reach the model.
+//!
+//! With an earlier translation of the file, strings it already has are reused and only missing
+//! keys are translated. A Rails-style root key naming the source locale becomes the target locale.
+//! YAML is written back in a normalized style, without comments.
+
+use serde::Serialize;
+use serde_yaml_ng::{Mapping, Value};
+
+use crate::language::{base_lang, is_auto_source, is_supported};
+use crate::protect::{add_instructions, marker, marker_options, restore_markers};
+use crate::{Error, Result, TranslationOptions, TranslationRequest, Translator};
+
+/// Resource file syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceFormat {
+    /// Nested JSON objects, as used by i18next and FormatJS
+    Json,
+    /// Nested YAML mappings, as used by Rails
+    Yaml,
+}
+
+impl ResourceFormat {
+    fn parse(self, text: &str) -> Result<Value> {
+        match self {
+            ResourceFormat::Json => serde_json::from_str(text)
+                .map_err(|err| Error::Document(format!("invalid JSON: {err}"))),
+            ResourceFormat::Yaml => serde_yaml_ng::from_str(text)
+                .map_err(|err| Error::Document(format!("invalid YAML: {err}"))),
+        }
+    }
+}
+
+/// A string leaf with markers in place of tokens
+struct Message {
+    template: String,
+    protected: Vec<String>,
+}
+
+impl Message {
+    fn has_prose(&self) -> bool {
+        self.template.chars().any(char::is_alphabetic)
+    }
+}
+
+/// Translate the string leaves of a resource file
+///
+/// `existing` is an earlier translation of the same file; its strings are kept and only the
+/// keys it lacks are translated. Keys found only in `existing` are kept at the end of their
+/// mapping.
+pub fn translate_resource(
+    translator: &dyn Translator,
+    format: ResourceFormat,
+    source: &str,
+    existing: Option<&str>,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let mut tree = format.parse(source)?;
+    rename_locale_root(&mut tree, source_lang, target_lang);
+    let existing = existing
+        .map(|existing| format.parse(existing))
+        .transpose()?;
+
+    let mut requests = Vec::new();
+    walk(
+        &tree,
+        existing.as_ref(),
+        &mut String::new(),
+        &mut |path, text| {
+            let message = protect_tokens(text);
+            if message.has_prose() {
+                let options = add_instructions(
+                    options,
+                    &format!("The text is the value of `{path}` in an application's locale file."),
+                );
+                requests.push(
+                    TranslationRequest::new(message.template, source_lang, target_lang)
+                        .with_options(marker_options(&options, !message.protected.is_empty())),
+                );
+            }
+            text.to_string()
+        },
+    );
+    let mut outputs = translator
+        .translate_batch(&requests)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?
+        .into_iter();
+
+    let translated = walk(
+        &tree,
+        existing.as_ref(),
+        &mut String::new(),
+        &mut |_, text| {
+            let message = protect_tokens(text);
+            if !message.has_prose() {
+                return text.to_string();
+            }
+            let output = outputs.next().expect("one output per message");
+            restore_markers(output.text.trim().to_string(), &message.protected)
+        },
+    );
+    render(format, &translated, source)
+}
+
+/// Copy `source`, taking strings from `existing` and the rest from `leaf`
+fn walk(
+    source: &Value,
+    existing: Option<&Value>,
+    path: &mut String,
+    leaf: &mut dyn FnMut(&str, &str) -> String,
+) -> Value {
+    match source {
+        Value::String(text) => match existing {
+            Some(Value::String(done)) if !done.trim().is_empty() => Value::String(done.clone()),
+            _ => Value::String(leaf(path, text)),
+        },
+        Value::Mapping(mapping) => {
+            let existing = existing.and_then(Value::as_mapping);
+            let mut copy = Mapping::with_capacity(mapping.len());
+            for (key, value) in mapping {
+                let length = path.len();
+                push_path(path, &key_name(key));
+                copy.insert(
+                    key.clone(),
+                    walk(value, existing.and_then(|map| map.get(key)), path, leaf),
+                );
+                path.truncate(length);
+            }
+            for (key, value) in existing.into_iter().flatten() {
+                if !copy.contains_key(key) {
+                    copy.insert(key.clone(), value.clone());
+                }
+            }
+            Value::Mapping(copy)
+        }
+        Value::Sequence(items) => {
+            let existing = existing.and_then(Value::as_sequence);
+            Value::Sequence(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        let length = path.len();
+                        path.push_str(&format!("[{index}]"));
+                        let item = walk(
+                            item,
+                            existing.and_then(|items| items.get(index)),
+                            path,
+                            leaf,
+                        );
+                        path.truncate(length);
+                        item
+                    })
+                    .collect(),
+            )
+        }
+        Value::Tagged(tagged) => {
+            let mut tagged = tagged.clone();
+            tagged.value = walk(&tagged.value, existing, path, leaf);
+            Value::Tagged(tagged)
+        }
+        other => other.clone(),
+    }
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        other => serde_yaml_ng::to_string(other)
+            .map(|key| key.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+fn push_path(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+}
+
+/// Rename a single root key naming the source locale, as in Rails `en:` files
+fn rename_locale_root(tree: &mut Value, source_lang: &str, target_lang: &str) {
+    let Some(mapping) = tree.as_mapping_mut() else {
+        return;
+    };
+    if mapping.len() != 1 {
+        return;
+    }
+    let Some((key, _)) = mapping.iter().next() else {
+        return;
+    };
+    let Some(locale) = key.as_str().map(str::to_string) else {
+        return;
+    };
+    let normalized = locale.to_lowercase().replace('_', "-");
+    let matches_source = if is_auto_source(source_lang) {
+        is_supported(&normalized)
+    } else {
+        base_lang(&normalized) == base_lang(&source_lang.to_lowercase())
+    };
+    if matches_source && let Some(value) = mapping.remove(locale) {
+        mapping.insert(Value::String(target_lang.to_string()), value);
+    }
+}
+
+fn render(format: ResourceFormat, tree: &Value, source: &str) -> Result<String> {
+    let mut text = match format {
+        ResourceFormat::Json => {
+            let indent = json_indent(source);
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+            let mut bytes = Vec::new();
+            let mut serializer = serde_json::Serializer::with_formatter(&mut bytes, formatter);
+            tree.serialize(&mut serializer)
+                .map_err(|err| Error::Document(format!("cannot write JSON: {err}")))?;
+            String::from_utf8(bytes).map_err(|err| Error::Document(err.to_string()))?
+        }
+        ResourceFormat::Yaml => serde_yaml_ng::to_string(tree)
+            .map_err(|err| Error::Document(format!("cannot write YAML: {err}")))?,
+    };
+    if source.ends_with('\n') && !text.ends_with('\n') {
+        text.push('\n');
+    } else if !source.ends_with('\n') && text.ends_with('\n') {
+        text.pop();
+    }
+    Ok(text)
+}
+
+/// Indentation of the first indented line, so the output diffs cleanly against the source
+fn json_indent(source: &str) -> String {
+    source
+        .lines()
+        .skip(1)
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+/// Swap interpolation tokens and ICU syntax for markers
+fn protect_tokens(text: &str) -> Message {
+    let mut message = Message {
+        template: String::new(),
+        protected: Vec::new(),
+    };
+    let mut builder = Builder {
+        message: &mut message,
+        after_marker: false,
+    };
+    builder.message_text(text, false);
+    message
+}
+
+struct Builder<'a> {
+    message: &'a mut Message,
+    /// The template ends with a marker, so the next protected text can join it
+    after_marker: bool,
+}
+
+impl Builder<'_> {
+    fn text(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.message.template.push_str(text);
+            self.after_marker = false;
+        }
+    }
+
+    fn protect(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.message.protected.last_mut() {
+            Some(last) if self.after_marker => last.push_str(text),
+            _ => {
+                self.message
+                    .template
+                    .push_str(&marker(self.message.protected.len()));
+                self.message.protected.push(text.to_string());
+            }
+        }
+        self.after_marker = true;
+    }
+
+    /// A message, where `#` stands for the number when it is inside a plural branch
+    fn message_text(&mut self, text: &str, in_plural: bool) {
+        let mut rest = text;
+        while let Some(start) = rest.find(['{', '%', '#']) {
+            self.text(&rest[..start]);
+            rest = &rest[start..];
+            let token_end = if rest.starts_with("{{") {
+                rest.find("}}").map(|end| end + 2)
+            } else if rest.starts_with("%{") {
+                rest.find('}').map(|end| end + 1)
+            } else if rest.starts_with('{') {
+                matching_brace(rest).map(|end| end + 1)
+            } else if rest.starts_with('#') && in_plural {
+                Some(1)
+            } else {
+                None
+            };
+            match token_end {
+                Some(end) if rest.starts_with('{') && !rest.starts_with("{{") => {
+                    self.argument(&rest[..end]);
+                    rest = &rest[end..];
+                }
+                Some(end) => {
+                    self.protect(&rest[..end]);
+                    rest = &rest[end..];
+                }
+                None => {
+                    self.text(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        self.text(rest);
+    }
+
+    /// An ICU argument in braces: `{name}`, `{n, number}` or `{n, plural, one {…} other {…}}`
+    fn argument(&mut self, argument: &str) {
+        let inner = &argument[1..argument.len() - 1];
+        let mut parts = inner.splitn(3, ',');
+        let (Some(_), Some(kind), Some(branches)) = (parts.next(), parts.next(), parts.next())
+        else {
+            self.protect(argument);
+            return;
+        };
+        let kind = kind.trim();
+        if !matches!(kind, "plural" | "select" | "selectordinal") {
+            self.protect(argument);
+            return;
+        }
+
+        let header_end = argument.len() - 1 - branches.len();
+        self.protect(&argument[..header_end]);
+        let mut rest = branches;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = matching_brace(&rest[open..]).map(|close| open + close) else {
+                break;
+            };
+            self.protect(&rest[..=open]);
+            self.message_text(&rest[open + 1..close], kind != "select");
+            self.protect("}");
+            rest = &rest[close + 1..];
+        }
+        self.protect(rest);
+        self.protect("}");
+    }
+}
+
+/// Byte index of the brace closing the one `text` starts with
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Recorder;
+
+    fn translate(
+        format: ResourceFormat,
+        source: &str,
+        existing: Option<&str>,
+    ) -> (String, Vec<String>) {
+        let recorder = Recorder::default();
+        let output = translate_resource(
+            &recorder,
+            format,
+            source,
+            existing,
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("resource should translate");
+        (output, recorder.texts())
+    }
+
+    #[test]
+    fn json_keeps_keys_order_and_tokens() {
+        let source = r#"{
+    "title": "Welcome, {{name}}",
+    "zebra": 3,
+    "nav": {
+        "home": "Home",
+        "items": "{count, plural, =0 {No items} one {# item} other {# items}}"
+    },
+    "list": ["First", "2"]
+}
+"#;
+
+        let (output, texts) = translate(ResourceFormat::Json, source, None);
+
+        assert_eq!(
+            output,
+            r#"{
+    "title": "WELCOME, {{name}}",
+    "zebra": 3,
+    "nav": {
+        "home": "HOME",
+        "items": "{count, plural, =0 {NO ITEMS} one {# ITEM} other {# ITEMS}}"
+    },
+    "list": [
+        "FIRST",
+        "2"
+    ]
+}
+"#
+        );
+        assert_eq!(
+            texts,
+            vec![
+                "Welcome, ⟦0⟧",
+                "Home",
+                "⟦0⟧No items⟦1⟧ item⟦2⟧ items⟦3⟧",
+                "First"
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_renames_the_locale_root_and_keeps_rails_tokens() {
+        let source = "en:\n  greeting: \"Hello %{user}\"\n  count: 2\n  errors:\n    blank: can't be blank\n";
+
+        let (output, texts) = translate(ResourceFormat::Yaml, source, None);
+
+        assert_eq!(
+            output,
+            "fr:\n  greeting: HELLO %{user}\n  count: 2\n  errors:\n    blank: CAN'T BE BLANK\n"
+        );
+        assert_eq!(texts, vec!["Hello ⟦0⟧", "can't be blank"]);
+    }
+
+    #[test]
+    fn existing_translations_are_kept() {
+        let source = r#"{"save": "Save", "cancel": "Cancel", "nested": {"ok": "OK now"}}"#;
+        let existing = r#"{"save": "Enregistrer", "nested": {"ok": ""}, "legacy": "Ancien"}"#;
+
+        let (output, texts) = translate(ResourceFormat::Json, source, Some(existing));
+
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["save"], "Enregistrer");
+        assert_eq!(value["cancel"], "CANCEL");
+        assert_eq!(value["nested"]["ok"], "OK NOW");
+        assert_eq!(value["legacy"], "Ancien");
+        assert_eq!(texts, vec!["Cancel", "OK now"]);
+        assert!(output.find("\"save\"").unwrap() < output.find("\"legacy\"").unwrap());
+    }
+
+    #[test]
+    fn protects_icu_select_and_simple_arguments() {
+        let message =
+            protect_tokens("{gender, select, male {He} other {They}} paid {amount, number} #1");
+
+        assert_eq!(message.template, "⟦0⟧He⟦1⟧They⟦2⟧ paid ⟦3⟧ #1");
+        assert_eq!(
+            message.protected,
+            vec![
+                "{gender, select, male {",
+                "} other {",
+                "}}",
+                "{amount, number}"
+            ]
+        );
+        assert_eq!(protect_tokens("50% off {").template, "50% off {");
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let recorder = Recorder::default();
+        let options = TranslationOptions::default();
+
+        let err = translate_resource(
+            &recorder,
+            ResourceFormat::Json,
+            "{\"a\": ",
+            None,
+            "en",
+            "fr",
+            &options,
+        )
+        .expect_err("broken JSON should fail");
+        assert!(err.to_string().starts_with("Document error: invalid JSON"));
+    }
+}
@@ -0,0 +1,392 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sentence and paragraph segmentation
+//!
+//! Segments borrow from the source text and keep their byte offsets and the
+//! whitespace that follows them, so the source can be rebuilt exactly:
+//! `&text[..segments[0].start]` followed by every `text` + `trailing` pair.
+
+use std::ops::Range;
+
+/// Abbreviations that do not end a sentence, compared case-insensitively
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "approx", "cf", "fig", "vol", "inc",
+    "ltd", "co", "corp", "dept", "est", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep",
+    "sept", "oct", "nov", "dec", "mme", "mlle", "mm", "bzw", "usw", "ca", "sra", "srta", "dña",
+];
+
+/// Abbreviations that only stay inside a sentence when a number follows (e.g. "No. 5")
+const NUMERIC_ABBREVIATIONS: &[&str] = &["no", "nr", "nos", "p", "pp", "art", "ch", "sec"];
+
+/// A sentence or paragraph within a source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'a> {
+    /// Segment text without surrounding whitespace
+    pub text: &'a str,
+    /// Whitespace between this segment and the next one, or the end of the source
+    pub trailing: &'a str,
+    /// Byte offset of `text` in the source
+    pub start: usize,
+}
+
+impl Segment<'_> {
+    /// Byte offset just past `text` in the source
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Byte range of `text` in the source
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end()
+    }
+}
+
+/// Split text into paragraphs separated by blank lines
+pub fn paragraphs(text: &str) -> Vec<Segment<'_>> {
+    segments_from_ranges(text, paragraph_ranges(text))
+}
+
+/// Split text into sentences
+///
+/// Sentences never span paragraphs; the last sentence of a paragraph carries the
+/// blank lines that follow it as trailing whitespace.
+pub fn sentences(text: &str) -> Vec<Segment<'_>> {
+    let ranges = paragraph_ranges(text)
+        .into_iter()
+        .flat_map(|paragraph| {
+            sentence_ranges(&text[paragraph.clone()])
+                .into_iter()
+                .map(move |sentence| {
+                    paragraph.start + sentence.start..paragraph.start + sentence.end
+                })
+        })
+        .collect();
+    segments_from_ranges(text, ranges)
+}
+
+fn segments_from_ranges(text: &str, ranges: Vec<Range<usize>>) -> Vec<Segment<'_>> {
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let next_start = ranges
+                .get(index + 1)
+                .map(|next| next.start)
+                .unwrap_or(text.len());
+            Segment {
+                text: &text[range.clone()],
+                trailing: &text[range.end..next_start],
+                start: range.start,
+            }
+        })
+        .collect()
+}
+
+/// Byte ranges of paragraphs, trimmed of surrounding whitespace
+fn paragraph_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim();
+        if content.is_empty() {
+            if let Some(range) = current.take() {
+                ranges.push(range);
+            }
+        } else {
+            let content_start = offset + (line.len() - line.trim_start().len());
+            let content_end = offset + line.trim_end().len();
+            match current.as_mut() {
+                Some(range) => range.end = content_end,
+                None => current = Some(content_start..content_end),
+            }
+        }
+        offset += line.len();
+    }
+    if let Some(range) = current {
+        ranges.push(range);
+    }
+    ranges
+}
+
+/// Byte ranges of sentences within a trimmed paragraph
+fn sentence_ranges(paragraph: &str) -> Vec<Range<usize>> {
+    let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+    let byte_at = |index: usize| chars.get(index).map_or(paragraph.len(), |&(byte, _)| byte);
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i].1;
+
+        if is_terminal(ch) {
+            // Runs such as "?!" or "..." end together, followed by closing quotes or brackets
+            let mut end = i + 1;
+            while end < chars.len() && is_terminal(chars[end].1) {
+                end += 1;
+            }
+            while end < chars.len() && is_closing(chars[end].1) {
+                end += 1;
+            }
+            let mut next = end;
+            while next < chars.len() && chars[next].1.is_whitespace() {
+                next += 1;
+            }
+
+            if next < chars.len() && ends_sentence(paragraph, &chars, byte_at(start), i, end, next)
+            {
+                ranges.push(byte_at(start)..byte_at(end));
+                start = next;
+            }
+            i = next;
+            continue;
+        }
+
+        // Thai has no sentence punctuation; a space between Thai words separates sentences
+        if ch.is_whitespace() && i > 0 && is_thai(chars[i - 1].1) {
+            let mut next = i;
+            while next < chars.len() && chars[next].1.is_whitespace() {
+                next += 1;
+            }
+            if next < chars.len() && is_thai(chars[next].1) {
+                ranges.push(byte_at(start)..byte_at(i));
+                start = next;
+            }
+            i = next;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if byte_at(start) < paragraph.len() {
+        ranges.push(byte_at(start)..paragraph.len());
+    }
+    ranges
+}
+
+/// Decide whether the terminal run `chars[terminal..end]` ends a sentence
+///
+/// `next` is the index of the first character after the whitespace following the run.
+fn ends_sentence(
+    paragraph: &str,
+    chars: &[(usize, char)],
+    sentence_start: usize,
+    terminal: usize,
+    end: usize,
+    next: usize,
+) -> bool {
+    let run = &chars[terminal..end];
+    if run.iter().any(|&(_, ch)| is_wide_terminal(ch)) {
+        return true;
+    }
+    if next == end {
+        // No whitespace after the run: decimals ("3.14"), versions, URLs
+        return false;
+    }
+
+    let next_char = chars[next].1;
+    if next_char.is_lowercase() {
+        return false;
+    }
+    if run.len() > 1 || run[0].1 != '.' {
+        return true;
+    }
+
+    // A single period: rule out abbreviations, initials and list numbers
+    let period_byte = chars[terminal].0;
+    let word_start = paragraph[..period_byte]
+        .rfind(char::is_whitespace)
+        .map_or(0, |index| {
+            index + paragraph[index..].chars().next().map_or(1, char::len_utf8)
+        });
+    let word = paragraph[word_start.max(sentence_start)..period_byte]
+        .trim_start_matches(|ch: char| !ch.is_alphanumeric());
+    let is_list_marker = word_start <= sentence_start && is_list_number(word);
+    !is_abbreviation(word, next_char) && !is_list_marker
+}
+
+fn is_abbreviation(word: &str, next_char: char) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+    let lower = word.to_lowercase();
+    if ABBREVIATIONS.contains(&lower.as_str()) {
+        return true;
+    }
+    if next_char.is_ascii_digit() && NUMERIC_ABBREVIATIONS.contains(&lower.as_str()) {
+        return true;
+    }
+
+    // Initials ("J. Smith") and dotted abbreviations ("e.g.", "U.S.")
+    let mut parts = word.split('.');
+    parts.all(|part| {
+        let mut letters = part.chars();
+        matches!((letters.next(), letters.next()), (Some(letter), None) if letter.is_alphabetic())
+    }) && (word.contains('.') || word.chars().all(char::is_uppercase))
+}
+
+fn is_list_number(word: &str) -> bool {
+    !word.is_empty() && word.len() <= 3 && word.chars().all(|ch| ch.is_ascii_digit())
+}
+
+fn is_terminal(ch: char) -> bool {
+    matches!(
+        ch,
+        '.' | '!' | '?' | '…' | '‼' | '⁇' | '⁈' | '⁉' | '।' | '॥' | '؟' | '۔'
+    ) || is_wide_terminal(ch)
+}
+
+/// Full-width stops that end a sentence even without following whitespace
+fn is_wide_terminal(ch: char) -> bool {
+    matches!(ch, '。' | '！' | '？' | '｡')
+}
+
+fn is_closing(ch: char) -> bool {
+    matches!(
+        ch,
+        '"' | '\'' | '’' | '”' | ')' | ']' | '}' | '»' | '›' | '」' | '』' | '）' | '】' | '》'
+    )
+}
+
+fn is_thai(ch: char) -> bool {
+    ('\u{0E00}'..='\u{0E7F}').contains(&ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(segments: &[Segment<'a>]) -> Vec<&'a str> {
+        segments.iter().map(|segment| segment.text).collect()
+    }
+
+    fn rebuild(source: &str, segments: &[Segment<'_>]) -> String {
+        let mut rebuilt = source[..segments.first().map_or(source.len(), |s| s.start)].to_string();
+        for segment in segments {
+            rebuilt.push_str(segment.text);
+            rebuilt.push_str(segment.trailing);
+        }
+        rebuilt
+    }
+
+    #[test]
+    fn splits_simple_sentences_and_keeps_offsets() {
+        let text = "Hello world. How are you?  Fine!";
+
+        let segments = sentences(text);
+
+        assert_eq!(texts(&segments), ["Hello world.", "How are you?", "Fine!"]);
+        assert_eq!(segments[1].start, 13);
+        assert_eq!(segments[1].trailing, "  ");
+        assert_eq!(&text[segments[2].range()], "Fine!");
+        assert_eq!(rebuild(text, &segments), text);
+    }
+
+    #[test]
+    fn does_not_split_after_abbreviations_or_initials() {
+        let text = "Dr. Smith met J. R. Tolkien, e.g. at Oxford. Mr. Brown came too.";
+
+        let segments = sentences(text);
+
+        assert_eq!(
+            texts(&segments),
+            [
+                "Dr. Smith met J. R. Tolkien, e.g. at Oxford.",
+                "Mr. Brown came too."
+            ]
+        );
+    }
+
+    #[test]
+    fn numeric_abbreviations_need_a_following_number() {
+        let segments = sentences("See No. 5 for details. I said no. Then I left.");
+
+        assert_eq!(
+            texts(&segments),
+            ["See No. 5 for details.", "I said no.", "Then I left."]
+        );
+    }
+
+    #[test]
+    fn does_not_split_decimals_or_lowercase_continuations() {
+        let segments = sentences("Pi is 3.14 and e is 2.72. The end... of it. Done.");
+
+        assert_eq!(
+            texts(&segments),
+            ["Pi is 3.14 and e is 2.72.", "The end... of it.", "Done."]
+        );
+    }
+
+    #[test]
+    fn keeps_closing_quotes_with_the_sentence() {
+        let segments = sentences("He said \"Stop.\" Then he left.");
+
+        assert_eq!(texts(&segments), ["He said \"Stop.\"", "Then he left."]);
+    }
+
+    #[test]
+    fn list_numbers_do_not_end_sentences() {
+        let segments = sentences("1. Install the model. 2. Run it.");
+
+        assert_eq!(texts(&segments), ["1. Install the model.", "2. Run it."]);
+    }
+
+    #[test]
+    fn splits_cjk_full_stops_without_whitespace() {
+        let text = "今日は晴れです。明日は雨ですか？はい！";
+
+        let segments = sentences(text);
+
+        assert_eq!(
+            texts(&segments),
+            ["今日は晴れです。", "明日は雨ですか？", "はい！"]
+        );
+        assert_eq!(rebuild(text, &segments), text);
+    }
+
+    #[test]
+    fn splits_thai_at_spaces_between_thai_text() {
+        let text = "วันนี้อากาศดี พรุ่งนี้ฝนตก";
+
+        let segments = sentences(text);
+
+        assert_eq!(texts(&segments), ["วันนี้อากาศดี", "พรุ่งนี้ฝนตก"]);
+        assert_eq!(segments[0].trailing, " ");
+    }
+
+    #[test]
+    fn paragraphs_split_on_blank_lines_and_rebuild_exactly() {
+        let text = "\n  First line\nstill first.\n\n \nSecond paragraph.\n";
+
+        let segments = paragraphs(text);
+
+        assert_eq!(
+            texts(&segments),
+            ["First line\nstill first.", "Second paragraph."]
+        );
+        assert_eq!(segments[0].start, 3);
+        assert_eq!(segments[0].trailing, "\n\n \n");
+        assert_eq!(segments[1].trailing, "\n");
+        assert_eq!(rebuild(text, &segments), text);
+    }
+
+    #[test]
+    fn sentences_do_not_cross_paragraphs() {
+        let text = "One. Two\n\nThree. Four.";
+
+        let segments = sentences(text);
+
+        assert_eq!(texts(&segments), ["One.", "Two", "Three.", "Four."]);
+        assert_eq!(segments[1].trailing, "\n\n");
+        assert_eq!(rebuild(text, &segments), text);
+    }
+
+    #[test]
+    fn empty_and_whitespace_only_text_has_no_segments() {
+        assert!(sentences("").is_empty());
+        assert!(paragraphs(" \n\t\n").is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! SRT and WebVTT subtitle translation
+//!
+//! Cues are found by their timing line and only the text lines below it are translated, so cue
+//! numbers, identifiers, timings, cue settings, the WebVTT header and NOTE, STYLE and REGION
+//! blocks keep their original bytes. Styling tags such as `<i>`, `<font color="…">`,
+//! `<c.yellow>`, `<v Name>` and `{\an8}` are swapped for `⟦n⟧` markers.
+//!
+//! Each cue is sent with the text of the cues around it, so a sentence split over several cues
+//! reads as one, and the translation is rewrapped to the cue's original line count.
+
+use std::ops::Range;
+
+use crate::protect::{add_instructions, marker, marker_options, restore_markers};
+use crate::{Error, Result, TranslationOptions, TranslationRequest, Translator};
+
+/// Subtitle file flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    /// SubRip: numbered cues with `00:00:01,000 --> 00:00:02,000` timings
+    Srt,
+    /// WebVTT: a `WEBVTT` header, optional cue identifiers and settings
+    WebVtt,
+}
+
+/// Text of one cue, with tags replaced by markers
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cue {
+    /// Source bytes of the cue text, from its first text line to the end of its last
+    range: Range<usize>,
+    /// Cue lines with markers in place of tags
+    lines: Vec<String>,
+    /// Source text of each marker, by marker number
+    protected: Vec<String>,
+    /// Line ending used inside the cue
+    newline: &'static str,
+}
+
+impl Cue {
+    fn template(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Cue text without markers, as context for the neighbouring cues
+    fn plain_text(&self) -> String {
+        let mut text = self.lines.join(" ");
+        for index in 0..self.protected.len() {
+            text = text.replace(&marker(index), "");
+        }
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn has_prose(&self) -> bool {
+        self.plain_text().chars().any(char::is_alphabetic)
+    }
+}
+
+/// Translate the cue text of an SRT or WebVTT file, keeping everything else
+pub fn translate_subtitles(
+    translator: &dyn Translator,
+    format: SubtitleFormat,
+    source: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    if format == SubtitleFormat::WebVtt
+        && !source.trim_start_matches('\u{feff}').starts_with("WEBVTT")
+    {
+        return Err(Error::Document(
+            "WebVTT files must start with `WEBVTT`".to_string(),
+        ));
+    }
+    let cues = parse_cues(source);
+    if cues.is_empty() {
+        return Err(Error::Document("no subtitle cues found".to_string()));
+    }
+
+    let translated: Vec<&Cue> = cues.iter().filter(|cue| cue.has_prose()).collect();
+    let requests: Vec<TranslationRequest> = translated
+        .iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            let previous = index.checked_sub(1).map(|index| translated[index]);
+            let next = translated.get(index + 1).copied();
+            let options = add_instructions(options, &context_instructions(previous, next));
+            TranslationRequest::new(cue.template(), source_lang, target_lang)
+                .with_options(marker_options(&options, !cue.protected.is_empty()))
+        })
+        .collect();
+    let outputs = translator
+        .translate_batch(&requests)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut document = source.to_string();
+    for (cue, output) in translated.iter().zip(outputs).rev() {
+        let lines = rewrap(&output.text, cue.lines.len());
+        let text = restore_markers(lines.join(cue.newline), &cue.protected);
+        document.replace_range(cue.range.clone(), &text);
+    }
+    Ok(document)
+}
+
+/// Prompt lines describing the cues around the one being translated
+fn context_instructions(previous: Option<&Cue>, next: Option<&Cue>) -> String {
+    let mut instructions = String::from(
+        "The text is one subtitle cue. Translate only the cue and keep its line breaks.",
+    );
+    if let Some(previous) = previous {
+        instructions.push_str(&format!(
+            "\nThe previous cue, for context only: \"{}\"",
+            previous.plain_text()
+        ));
+    }
+    if let Some(next) = next {
+        instructions.push_str(&format!(
+            "\nThe next cue, for context only: \"{}\"",
+            next.plain_text()
+        ));
+    }
+    instructions
+}
+
+/// Find the cues of a subtitle file
+///
+/// Blocks are separated by blank lines. A block whose lines include a `-->` timing line is a
+/// cue and the lines after the timing are its text; other blocks (the WebVTT header, NOTE,
+/// STYLE and REGION) are left alone.
+fn parse_cues(source: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut block: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if content.trim().is_empty() {
+            cues.extend(cue_from_block(&block));
+            block.clear();
+        } else {
+            block.push((offset, line));
+        }
+        offset += line.len();
+    }
+    cues.extend(cue_from_block(&block));
+    cues
+}
+
+fn cue_from_block(block: &[(usize, &str)]) -> Option<Cue> {
+    let timing = block.iter().position(|(_, line)| line.contains("-->"))?;
+    let text_lines = &block[timing + 1..];
+    let (start, _) = text_lines.first()?;
+    let (last_start, last_line) = text_lines.last()?;
+    let newline = if block[timing].1.ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut protected = Vec::new();
+    let lines = text_lines
+        .iter()
+        .map(|(_, line)| protect_tags(line.trim_end_matches(['\r', '\n']), &mut protected))
+        .collect();
+    Some(Cue {
+        range: *start..last_start + last_line.trim_end_matches(['\r', '\n']).len(),
+        lines,
+        protected,
+        newline,
+    })
+}
+
+/// Replace `<…>` tags and `{\…}` override blocks with markers
+fn protect_tags(line: &str, protected: &mut Vec<String>) -> String {
+    let mut template = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(['<', '{']) {
+        let close = if rest[start..].starts_with('<') {
+            '>'
+        } else if rest[start..].starts_with("{\\") {
+            '}'
+        } else {
+            template.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+        let Some(length) = rest[start..].find(close) else {
+            break;
+        };
+        let end = start + length + 1;
+        template.push_str(&rest[..start]);
+        template.push_str(&marker(protected.len()));
+        protected.push(rest[start..end].to_string());
+        rest = &rest[end..];
+    }
+    template.push_str(rest);
+    template
+}
+
+/// Fit a translation into `count` lines
+///
+/// A translation with the right number of lines keeps its breaks; otherwise the words are
+/// spread over `count` lines of similar length.
+fn rewrap(translation: &str, count: usize) -> Vec<String> {
+    let lines: Vec<&str> = translation
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.len() == count {
+        return lines.into_iter().map(str::to_string).collect();
+    }
+
+    let words: Vec<&str> = translation.split_whitespace().collect();
+    let mut remaining = words.iter().map(|word| word.chars().count()).sum::<usize>();
+    let mut wrapped = Vec::with_capacity(count);
+    let mut words = words.into_iter().peekable();
+    for slots in (1..=count.max(1)).rev() {
+        let target = remaining / slots;
+        let mut line = Vec::new();
+        let mut length = 0;
+        while let Some(word) = words.peek() {
+            if slots > 1 && !line.is_empty() && length >= target {
+                break;
+            }
+            length += word.chars().count();
+            line.push(*word);
+            words.next();
+        }
+        remaining -= length;
+        wrapped.push(line.join(" "));
+    }
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Recorder;
+
+    fn translate(format: SubtitleFormat, source: &str) -> (String, Vec<(String, Option<String>)>) {
+        let recorder = Recorder::default();
+        let output = translate_subtitles(
+            &recorder,
+            format,
+            source,
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("subtitles should translate");
+        (output, recorder.requests())
+    }
+
+    #[test]
+    fn srt_keeps_numbers_timings_and_tags() {
+        let source = "1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Where are</i>\r\nyou going?\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\n{\\an8}Home.\r\n\r\n3\r\n00:00:05,000 --> 00:00:06,000\r\n♪ ♪\r\n";
+
+        let (output, requests) = translate(SubtitleFormat::Srt, source);
+
+        assert_eq!(
+            output,
+            "1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>WHERE ARE</i>\r\nYOU GOING?\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\n{\\an8}HOME.\r\n\r\n3\r\n00:00:05,000 --> 00:00:06,000\r\n♪ ♪\r\n"
+        );
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "⟦0⟧Where are⟦1⟧\nyou going?");
+        assert_eq!(requests[1].0, "⟦0⟧Home.");
+    }
+
+    #[test]
+    fn cues_carry_their_neighbours_as_context() {
+        let source = "1\n00:00:01,000 --> 00:00:02,000\nI never said\n\n2\n00:00:02,000 --> 00:00:03,000\n<b>she</b> stole it.\n\n3\n00:00:03,000 --> 00:00:04,000\nReally.\n";
+
+        let (_, requests) = translate(SubtitleFormat::Srt, source);
+
+        let middle = requests[1].1.as_deref().unwrap();
+        assert!(middle.contains("previous cue, for context only: \"I never said\""));
+        assert!(middle.contains("next cue, for context only: \"Really.\""));
+        assert!(middle.contains("⟦0⟧"));
+        let first = requests[0].1.as_deref().unwrap();
+        assert!(!first.contains("previous cue"));
+        assert!(first.contains("next cue, for context only: \"she stole it.\""));
+    }
+
+    #[test]
+    fn webvtt_keeps_header_notes_styles_and_cue_settings() {
+        let source = "WEBVTT - Episode 1\n\nSTYLE\n::cue { color: yellow }\n\nNOTE translated by hand\n\nintro\n00:01.000 --> 00:02.000 align:start line:10%\n<v Roger>Hello <c.loud>there</c>\n\n00:02.500 --> 00:03.000\nBye\n";
+
+        let (output, requests) = translate(SubtitleFormat::WebVtt, source);
+
+        assert_eq!(
+            output,
+            "WEBVTT - Episode 1\n\nSTYLE\n::cue { color: yellow }\n\nNOTE translated by hand\n\nintro\n00:01.000 --> 00:02.000 align:start line:10%\n<v Roger>HELLO <c.loud>THERE</c>\n\n00:02.500 --> 00:03.000\nBYE\n"
+        );
+        assert_eq!(requests[0].0, "⟦0⟧Hello ⟦1⟧there⟦2⟧");
+    }
+
+    #[test]
+    fn rejects_files_without_cues_or_header() {
+        let recorder = Recorder::default();
+        let options = TranslationOptions::default();
+
+        let err = translate_subtitles(
+            &recorder,
+            SubtitleFormat::Srt,
+            "hello\n",
+            "en",
+            "fr",
+            &options,
+        )
+        .expect_err("text without timings should fail");
+        assert!(matches!(err, Error::Document(_)));
+
+        let err = translate_subtitles(
+            &recorder,
+            SubtitleFormat::WebVtt,
+            "1\n00:00:01,000 --> 00:00:02,000\nHi\n",
+            "en",
+            "fr",
+            &options,
+        )
+        .expect_err("WebVTT without header should fail");
+        assert!(err.to_string().contains("WEBVTT"));
+    }
+
+    #[test]
+    fn rewrap_keeps_line_count() {
+        assert_eq!(
+            rewrap("Où vas-tu\nce soir ?", 2),
+            vec!["Où vas-tu", "ce soir ?"]
+        );
+        assert_eq!(
+            rewrap("Où est-ce que tu vas ce soir ?", 2),
+            vec!["Où est-ce que", "tu vas ce soir ?"]
+        );
+        assert_eq!(rewrap("Salut\ntoi", 1), vec!["Salut toi"]);
+    }
+}
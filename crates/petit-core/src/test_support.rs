@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Test doubles shared by the document format tests
+
+use crate::{Result, TranslationOptions, TranslationOutput, Translator};
+use std::sync::Mutex;
+
+/// Upper-cases its input and records every request
+#[derive(Default)]
+pub(crate) struct Recorder {
+    requests: Mutex<Vec<(String, TranslationOptions)>>,
+}
+
+impl Recorder {
+    /// Recorded prompt texts with their options
+    pub(crate) fn options(self) -> Vec<(String, TranslationOptions)> {
+        self.requests.into_inner().unwrap()
+    }
+
+    /// Recorded prompt texts with their extra instructions
+    pub(crate) fn requests(self) -> Vec<(String, Option<String>)> {
+        self.options()
+            .into_iter()
+            .map(|(text, options)| (text, options.instructions))
+            .collect()
+    }
+
+    /// Recorded prompt texts
+    pub(crate) fn texts(self) -> Vec<String> {
+        self.requests().into_iter().map(|(text, _)| text).collect()
+    }
+}
+
+impl Translator for Recorder {
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> Result<TranslationOutput> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((text.to_string(), options.clone()));
+        Ok(TranslationOutput::from_text(
+            text.to_uppercase(),
+            source_lang,
+            target_lang,
+        ))
+    }
+
+    fn supported_languages(&self) -> &[&str] {
+        &[]
+    }
+}
@@ -0,0 +1,508 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! XLIFF 1.2 and 2.0 translation
+//!
+//! Each `trans-unit` (1.2) or `segment` (2.0) without a translation has its `source` translated
+//! into `target`. Inline codes are swapped for `⟦n⟧` markers: `<x/>`, `<ph>`, `<bpt>` and the
+//! other standalone codes as a whole, and the start and end tags of `<g>`, `<pc>` and `<mrk>`
+//! around text that is translated.
+//!
+//! The file is edited in place, so every byte outside the new targets stays as it was. A 1.2
+//! target is marked `state="needs-review-translation"`; 2.0 has no target state, so the segment
+//! gets `state="translated"` with `subState="petit:needs-review-translation"`.
+//!
+//! A translation that drops or repeats an inline code would leave the tags unbalanced, so its
+//! unit keeps no target and the next run retries it; with [`PlaceholderMode::Strict`] it is an
+//! [`Error::Placeholder`] instead.
+
+use roxmltree::{Document, Node};
+use std::ops::Range;
+
+use crate::protect::{marker, marker_options, replace_markers};
+use crate::{Error, PlaceholderMode, Result, TranslationOptions, TranslationRequest, Translator};
+
+/// State given to machine-translated targets
+const REVIEW_STATE: &str = "needs-review-translation";
+
+/// Inline elements whose content is translatable text
+const PAIRED_CODES: &[&str] = &["g", "pc", "mrk"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    V1,
+    V2,
+}
+
+/// A source to translate and where its translation goes
+#[derive(Debug)]
+struct Segment {
+    /// `id` of the unit or segment, for error messages
+    id: String,
+    template: String,
+    protected: Vec<String>,
+    /// Replaced by the target content; an empty range inserts it
+    content: Range<usize>,
+    /// Text around the content, for a target that has to be created or expanded
+    before: String,
+    after: String,
+    /// Attribute changes that mark the translation for review
+    edits: Vec<(Range<usize>, String)>,
+}
+
+/// Translate the untranslated segments of an XLIFF 1.2 or 2.0 document
+pub fn translate_xliff(
+    translator: &dyn Translator,
+    source: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<String> {
+    let document = Document::parse(source).map_err(|err| Error::Document(err.to_string()))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "xliff" {
+        return Err(Error::Document("not an XLIFF document".to_string()));
+    }
+    let version = match root.attribute("version") {
+        Some(version) if version.starts_with("1.") => Version::V1,
+        Some(version) if version.starts_with("2.") => Version::V2,
+        other => {
+            return Err(Error::Document(format!(
+                "unsupported XLIFF version {}",
+                other.unwrap_or("(none)")
+            )));
+        }
+    };
+
+    let segments: Vec<Segment> = root
+        .descendants()
+        .filter(|node| match version {
+            Version::V1 => node.has_tag_name("trans-unit") && translatable(*node),
+            Version::V2 => {
+                node.has_tag_name("segment")
+                    && node
+                        .ancestors()
+                        .find(|ancestor| ancestor.has_tag_name("unit"))
+                        .is_none_or(translatable)
+            }
+        })
+        .filter_map(|node| segment(source, version, node))
+        .collect();
+    let requests: Vec<TranslationRequest> = segments
+        .iter()
+        .map(|segment| {
+            TranslationRequest::new(segment.template.clone(), source_lang, target_lang)
+                .with_options(marker_options(options, !segment.protected.is_empty()))
+        })
+        .collect();
+    let outputs = translator
+        .translate_batch(&requests)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut edits = Vec::new();
+    for (segment, output) in segments.into_iter().zip(outputs) {
+        let (content, counts) = replace_markers(&escape(output.text.trim()), &segment.protected);
+        if counts.iter().any(|count| *count != 1) {
+            if options.placeholder_mode() == PlaceholderMode::Strict {
+                return Err(Error::Placeholder(format!(
+                    "unit `{}`: inline codes are missing or repeated in the translation",
+                    segment.id
+                )));
+            }
+            continue;
+        }
+        edits.push((
+            segment.content,
+            format!("{}{content}{}", segment.before, segment.after),
+        ));
+        edits.extend(segment.edits);
+    }
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut document = source.to_string();
+    for (range, text) in edits.into_iter().rev() {
+        document.replace_range(range, &text);
+    }
+    Document::parse(&document)
+        .map_err(|err| Error::Document(format!("translated XLIFF is not well-formed: {err}")))?;
+    Ok(document)
+}
+
+fn translatable(node: Node<'_, '_>) -> bool {
+    node.attribute("translate") != Some("no")
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+/// Build the segment for a unit whose target is missing or empty
+fn segment(source: &str, version: Version, node: Node<'_, '_>) -> Option<Segment> {
+    let source_node = child(node, "source")?;
+    let target = child(node, "target");
+    if target.is_some_and(|target| !is_blank(target)) {
+        return None;
+    }
+
+    let mut template = String::new();
+    let mut protected = Vec::new();
+    inline_template(source, source_node, &mut template, &mut protected);
+    if !template.chars().any(char::is_alphabetic) {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let (content, before, after) = match target {
+        Some(target) => {
+            if version == Version::V1 {
+                set_attribute(source, target, "state", REVIEW_STATE, &mut edits);
+            }
+            let range = target.range();
+            if source[range.clone()].ends_with("/>") {
+                // `<target/>` becomes `<target>…</target>`
+                let name = qualified_name(source, target);
+                (
+                    range.end - 2..range.end,
+                    ">".to_string(),
+                    format!("</{name}>"),
+                )
+            } else {
+                (content_range(source, target), String::new(), String::new())
+            }
+        }
+        None => {
+            let name = qualified_name(source, source_node);
+            let name = format!("{}target", name.strip_suffix("source").unwrap_or_default());
+            let state = match version {
+                Version::V1 => format!(" state=\"{REVIEW_STATE}\""),
+                Version::V2 => String::new(),
+            };
+            let end = source_node.range().end;
+            (
+                end..end,
+                format!("{}<{name}{state}>", indentation(source, source_node)),
+                format!("</{name}>"),
+            )
+        }
+    };
+    if version == Version::V2 {
+        set_attribute(source, node, "state", "translated", &mut edits);
+        set_attribute(
+            source,
+            node,
+            "subState",
+            &format!("petit:{REVIEW_STATE}"),
+            &mut edits,
+        );
+    }
+    Some(Segment {
+        id: node.attribute("id").unwrap_or_default().to_string(),
+        template,
+        protected,
+        content,
+        before,
+        after,
+        edits,
+    })
+}
+
+/// Whether an element holds nothing but whitespace
+fn is_blank(node: Node<'_, '_>) -> bool {
+    node.children()
+        .all(|child| child.is_text() && child.text().is_some_and(|text| text.trim().is_empty()))
+}
+
+/// Text of an inline container with markers in place of codes
+fn inline_template(
+    source: &str,
+    node: Node<'_, '_>,
+    template: &mut String,
+    protected: &mut Vec<String>,
+) {
+    for child in node.children() {
+        if child.is_text() {
+            template.push_str(child.text().unwrap_or_default());
+        } else if child.is_element()
+            && PAIRED_CODES.contains(&child.tag_name().name())
+            && child.has_children()
+        {
+            let range = child.range();
+            let content = content_range(source, child);
+            protect(template, protected, &source[range.start..content.start]);
+            inline_template(source, child, template, protected);
+            protect(template, protected, &source[content.end..range.end]);
+        } else {
+            protect(template, protected, &source[child.range()]);
+        }
+    }
+}
+
+fn protect(template: &mut String, protected: &mut Vec<String>, text: &str) {
+    template.push_str(&marker(protected.len()));
+    protected.push(text.to_string());
+}
+
+/// Bytes between the start and end tag of an element that is not self-closing
+fn content_range(source: &str, node: Node<'_, '_>) -> Range<usize> {
+    let range = node.range();
+    let close = range.start + source[range.clone()].rfind("</").unwrap_or(range.len());
+    let open_end = node
+        .first_child()
+        .map_or(close, |first| first.range().start);
+    open_end..close
+}
+
+/// Tag name as written, including any namespace prefix
+fn qualified_name<'a>(source: &'a str, node: Node<'_, '_>) -> &'a str {
+    let tag = &source[node.range().start + 1..];
+    let end = tag
+        .find(|ch: char| ch.is_whitespace() || ch == '/' || ch == '>')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+/// Whitespace before an element, so a new sibling lines up with it
+fn indentation<'a>(source: &'a str, node: Node<'_, '_>) -> &'a str {
+    node.prev_sibling()
+        .filter(|sibling| sibling.is_text())
+        .map(|sibling| &source[sibling.range()])
+        .filter(|text| text.trim().is_empty())
+        .unwrap_or_default()
+}
+
+/// Set an unprefixed attribute, replacing its value or adding it after the tag name
+fn set_attribute(
+    source: &str,
+    node: Node<'_, '_>,
+    name: &str,
+    value: &str,
+    edits: &mut Vec<(Range<usize>, String)>,
+) {
+    match node
+        .attributes()
+        .find(|attribute| attribute.name() == name && attribute.namespace().is_none())
+    {
+        Some(attribute) => edits.push((attribute.range_value(), value.to_string())),
+        None => {
+            let at = node.range().start + 1 + qualified_name(source, node).len();
+            edits.push((at..at, format!(" {name}=\"{value}\"")));
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TranslationOutput;
+    use crate::test_support::Recorder;
+
+    fn translate(source: &str) -> (String, Vec<String>) {
+        let recorder = Recorder::default();
+        let output = translate_xliff(
+            &recorder,
+            source,
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("XLIFF should translate");
+        (output, recorder.texts())
+    }
+
+    #[test]
+    fn xliff_1_2_fills_targets_and_keeps_inline_codes() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="fr" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="1">
+        <source>Click <g id="1">Save &amp; close</g> now<x id="2"/></source>
+        <note>Button label</note>
+      </trans-unit>
+      <trans-unit id="2">
+        <source>Cancel</source>
+        <target state="new"/>
+      </trans-unit>
+      <trans-unit id="3">
+        <source>Done</source>
+        <target>Terminé</target>
+      </trans-unit>
+      <trans-unit id="4" translate="no">
+        <source>petit</source>
+      </trans-unit>
+      <trans-unit id="5">
+        <source>Hello <ph id="1">%s</ph></source>
+        <target xml:lang="fr">  </target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#;
+
+        let (output, texts) = translate(source);
+
+        assert_eq!(
+            output,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="fr" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="1">
+        <source>Click <g id="1">Save &amp; close</g> now<x id="2"/></source>
+        <target state="needs-review-translation">CLICK <g id="1">SAVE &amp; CLOSE</g> NOW<x id="2"/></target>
+        <note>Button label</note>
+      </trans-unit>
+      <trans-unit id="2">
+        <source>Cancel</source>
+        <target state="needs-review-translation">CANCEL</target>
+      </trans-unit>
+      <trans-unit id="3">
+        <source>Done</source>
+        <target>Terminé</target>
+      </trans-unit>
+      <trans-unit id="4" translate="no">
+        <source>petit</source>
+      </trans-unit>
+      <trans-unit id="5">
+        <source>Hello <ph id="1">%s</ph></source>
+        <target state="needs-review-translation" xml:lang="fr">HELLO <ph id="1">%s</ph></target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#
+        );
+        assert_eq!(
+            texts,
+            vec!["Click ⟦0⟧Save & close⟦1⟧ now⟦2⟧", "Cancel", "Hello ⟦0⟧"]
+        );
+    }
+
+    #[test]
+    fn xliff_2_0_marks_segments_for_review() {
+        let source = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="fr">
+ <file id="f1">
+  <unit id="u1">
+   <segment id="s1">
+    <source>Open <pc id="1">the file</pc></source>
+   </segment>
+   <segment id="s2" state="initial">
+    <source>Close</source>
+    <target></target>
+   </segment>
+  </unit>
+  <unit id="u2" translate="no">
+   <segment><source>Keep</source></segment>
+  </unit>
+ </file>
+</xliff>"#;
+
+        let (output, texts) = translate(source);
+
+        assert_eq!(
+            output,
+            r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="fr">
+ <file id="f1">
+  <unit id="u1">
+   <segment state="translated" subState="petit:needs-review-translation" id="s1">
+    <source>Open <pc id="1">the file</pc></source>
+    <target>OPEN <pc id="1">THE FILE</pc></target>
+   </segment>
+   <segment subState="petit:needs-review-translation" id="s2" state="translated">
+    <source>Close</source>
+    <target>CLOSE</target>
+   </segment>
+  </unit>
+  <unit id="u2" translate="no">
+   <segment><source>Keep</source></segment>
+  </unit>
+ </file>
+</xliff>"#
+        );
+        assert_eq!(texts, vec!["Open ⟦0⟧the file⟦1⟧", "Close"]);
+    }
+
+    /// Drops the first inline code of "Click" units and repeats it in "Open" units
+    struct Mangler;
+
+    impl Translator for Mangler {
+        fn translate_with_options(
+            &self,
+            text: &str,
+            source_lang: &str,
+            target_lang: &str,
+            _options: &TranslationOptions,
+        ) -> Result<TranslationOutput> {
+            let text = if text.starts_with("Click") {
+                text.replacen("⟦0⟧", "", 1)
+            } else if text.starts_with("Open") {
+                text.replacen("⟦0⟧", "⟦0⟧⟦0⟧", 1)
+            } else {
+                text.to_string()
+            };
+            Ok(TranslationOutput::from_text(
+                text.to_uppercase(),
+                source_lang,
+                target_lang,
+            ))
+        }
+
+        fn supported_languages(&self) -> &[&str] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn units_with_dropped_or_repeated_inline_codes_stay_untranslated() {
+        let source = r#"<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="fr" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="dropped">
+        <source>Click <g id="1">Save</g> now</source>
+      </trans-unit>
+      <trans-unit id="repeated">
+        <source>Open <g id="2">the file</g></source>
+      </trans-unit>
+      <trans-unit id="plain">
+        <source>Done</source>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+
+        let output = translate_xliff(&Mangler, source, "en", "fr", &TranslationOptions::default())
+            .expect("XLIFF should translate");
+        assert_eq!(
+            output,
+            source.replace(
+                "<source>Done</source>",
+                "<source>Done</source>\n        <target state=\"needs-review-translation\">DONE</target>"
+            )
+        );
+
+        let strict = TranslationOptions::new().with_placeholders(PlaceholderMode::Strict);
+        let err = translate_xliff(&Mangler, source, "en", "fr", &strict)
+            .expect_err("strict mode should reject the broken unit");
+        assert!(
+            matches!(&err, Error::Placeholder(message) if message.contains("unit `dropped`")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        let recorder = Recorder::default();
+        let options = TranslationOptions::default();
+
+        for source in ["<html/>", "<xliff version=\"3.0\"/>", "<xliff"] {
+            let err = translate_xliff(&recorder, source, "en", "fr", &options)
+                .expect_err("non-XLIFF input should fail");
+            assert!(matches!(err, Error::Document(_)), "{source}: {err}");
+        }
+    }
+}
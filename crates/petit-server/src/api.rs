@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! JSON REST endpoints
+//!
+//! - `POST /translate`: translate one text
+//! - `POST /translate/batch`: translate several texts in one decode batch
+//! - `GET /languages`: supported language codes
+//! - `GET /health`: translator status
+//!
+//! With [`ServerApi::LibreTranslate`], `/translate` and `/languages` use LibreTranslate's shapes
+//! instead (see [`crate::libretranslate`]); the batch and health endpoints stay available.
+//! With [`ServerConfig::deepl`], the DeepL-compatible `/v2` endpoints (see [`crate::deepl`]) are
+//! served next to either API.
+
+use axum::Router;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use petit_core::language::{AUTO_SOURCE_LANG, supported_languages, validate_pair};
+use petit_core::{TranslationOptions, TranslationOutput, TranslationRequest};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::deepl::Glossaries;
+use crate::{ApiError, ServerApi, ServerConfig, Worker, WorkerStatus, deepl, libretranslate};
+
+/// Shared handler state
+#[derive(Clone)]
+pub(crate) struct AppState {
+    pub(crate) worker: Worker,
+    pub(crate) config: Arc<ServerConfig>,
+    pub(crate) glossaries: Glossaries,
+}
+
+/// Body of `POST /translate` and of each batch entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranslateBody {
+    /// Text to translate
+    pub text: String,
+    /// Source language, or `auto` (server default when unset)
+    #[serde(default)]
+    pub source_lang: Option<String>,
+    /// Target language (server default when unset)
+    #[serde(default)]
+    pub target_lang: Option<String>,
+    /// Per-request translation options
+    #[serde(default)]
+    pub options: TranslationOptions,
+}
+
+impl TranslateBody {
+    /// Resolve defaults and reject invalid input before it reaches the queue
+    fn into_request(self, config: &ServerConfig) -> Result<TranslationRequest, ApiError> {
+        if self.text.trim().is_empty() {
+            return Err(ApiError::BadRequest("text must not be empty".to_string()));
+        }
+        let source_lang = self
+            .source_lang
+            .unwrap_or_else(|| config.default_source.clone());
+        let target_lang = self
+            .target_lang
+            .unwrap_or_else(|| config.default_target.clone());
+        validate_pair(&source_lang, &target_lang)?;
+        self.options.validate()?;
+
+        Ok(TranslationRequest::new(self.text, source_lang, target_lang).with_options(self.options))
+    }
+}
+
+/// Body of `POST /translate/batch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchBody {
+    /// Texts to translate, answered in the same order
+    pub requests: Vec<TranslateBody>,
+}
+
+/// One entry of a batch response
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum BatchResult {
+    /// Successful translation
+    Ok(TranslationOutput),
+    /// Failed translation with its message
+    Err { error: String },
+}
+
+/// Build the router for the translation API
+pub fn router(worker: Worker, config: ServerConfig) -> Router {
+    let mut router = Router::new()
+        .route("/translate/batch", post(translate_batch))
+        .route("/health", get(health));
+    if config.deepl {
+        router = router.merge(deepl::routes());
+    }
+    let router = match config.api {
+        ServerApi::Native => router
+            .route("/translate", post(translate))
+            .route("/languages", get(languages)),
+        ServerApi::LibreTranslate => router.merge(libretranslate::routes()),
+    };
+    router.with_state(AppState {
+        worker,
+        config: Arc::new(config),
+        glossaries: Glossaries::default(),
+    })
+}
+
+/// Unwrap a JSON body, reporting malformed input as a JSON error
+pub(crate) fn json_body<T>(body: Result<Json<T>, JsonRejection>) -> Result<T, ApiError> {
+    body.map(|Json(value)| value)
+        .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))
+}
+
+async fn translate(
+    State(state): State<AppState>,
+    body: Result<Json<TranslateBody>, JsonRejection>,
+) -> Result<Json<TranslationOutput>, ApiError> {
+    let request = json_body(body)?.into_request(&state.config)?;
+    let output = state
+        .worker
+        .run(move |translator| {
+            translator.translate_with_options(
+                &request.text,
+                &request.source_lang,
+                &request.target_lang,
+                &request.options,
+            )
+        })
+        .await??;
+    Ok(Json(output))
+}
+
+async fn translate_batch(
+    State(state): State<AppState>,
+    body: Result<Json<BatchBody>, JsonRejection>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let body = json_body(body)?;
+    if body.requests.is_empty() {
+        return Err(ApiError::BadRequest(
+            "requests must not be empty".to_string(),
+        ));
+    }
+    if body.requests.len() > state.config.max_batch_size {
+        return Err(ApiError::BadRequest(format!(
+            "batch has {} requests, the limit is {}",
+            body.requests.len(),
+            state.config.max_batch_size
+        )));
+    }
+
+    // Invalid entries are answered directly; the rest are translated together
+    let mut results: Vec<Option<BatchResult>> = Vec::with_capacity(body.requests.len());
+    let mut requests = Vec::new();
+    for entry in body.requests {
+        match entry.into_request(&state.config) {
+            Ok(request) => {
+                results.push(None);
+                requests.push(request);
+            }
+            Err(err) => results.push(Some(BatchResult::Err {
+                error: err.to_string(),
+            })),
+        }
+    }
+
+    let mut outputs = if requests.is_empty() {
+        Vec::new()
+    } else {
+        state
+            .worker
+            .run(move |translator| translator.translate_batch(&requests))
+            .await?
+    }
+    .into_iter();
+
+    let results: Vec<BatchResult> = results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| match outputs.next() {
+                Some(Ok(output)) => BatchResult::Ok(output),
+                Some(Err(err)) => BatchResult::Err {
+                    error: err.to_string(),
+                },
+                None => BatchResult::Err {
+                    error: "translator returned too few results".to_string(),
+                },
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "results": results })))
+}
+
+async fn languages(State(state): State<AppState>) -> Json<serde_json::Value> {
+    Json(json!({
+        "languages": supported_languages(),
+        "auto_source": AUTO_SOURCE_LANG,
+        "default_source": state.config.default_source,
+        "default_target": state.config.default_target,
+    }))
+}
+
+async fn health(State(state): State<AppState>) -> Response {
+    let (status, mut body) = match state.worker.status() {
+        WorkerStatus::Ready => (StatusCode::OK, json!({ "status": "ok" })),
+        WorkerStatus::Loading => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            json!({ "status": "loading" }),
+        ),
+        WorkerStatus::Failed(message) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            json!({ "status": "error", "error": message }),
+        ),
+    };
+    body["queue_size"] = json!(state.config.queue_size);
+    (status, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use petit_core::MockTranslator;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn test_router() -> Router {
+        let worker = Worker::spawn(4, || {
+            Ok(Box::new(
+                MockTranslator::new().with_error("boom", "scripted failure"),
+            ))
+        });
+        router(worker, ServerConfig::default())
+    }
+
+    async fn call(
+        router: Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn translate_uses_server_default_languages() {
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate",
+            Some(json!({ "text": "hello world" })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["text"], "HELLO WORLD");
+        assert_eq!(body["source_lang"], "auto");
+        assert_eq!(body["target_lang"], "fr");
+        assert_eq!(body["generated_tokens"], 2);
+    }
+
+    #[tokio::test]
+    async fn translate_rejects_invalid_input() {
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate",
+            Some(json!({ "text": "hello", "target_lang": "xx" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("xx"));
+
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate",
+            Some(json!({ "txt": "hello" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("text"));
+    }
+
+    #[tokio::test]
+    async fn translate_reports_translator_errors() {
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate",
+            Some(json!({ "text": "boom" })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error"].as_str().unwrap().contains("scripted failure"));
+    }
+
+    #[tokio::test]
+    async fn batch_keeps_order_and_reports_entry_errors() {
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate/batch",
+            Some(json!({ "requests": [
+                { "text": "one", "target_lang": "de" },
+                { "text": "two", "target_lang": "xx" },
+                { "text": "boom" },
+                { "text": "three", "options": { "max_new_tokens": 1 } },
+            ] })),
+        )
+        .await;
+        let results = body["results"].as_array().unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(results[0]["text"], "ONE");
+        assert_eq!(results[0]["target_lang"], "de");
+        assert!(results[1]["error"].as_str().unwrap().contains("xx"));
+        assert!(
+            results[2]["error"]
+                .as_str()
+                .unwrap()
+                .contains("scripted failure")
+        );
+        assert_eq!(results[3]["text"], "THREE");
+    }
+
+    #[tokio::test]
+    async fn batch_enforces_size_limit() {
+        let worker = Worker::spawn(4, || Ok(Box::new(MockTranslator::new())));
+        let router = router(
+            worker,
+            ServerConfig {
+                max_batch_size: 1,
+                ..ServerConfig::default()
+            },
+        );
+
+        let (status, _) = call(
+            router,
+            "POST",
+            "/translate/batch",
+            Some(json!({ "requests": [{ "text": "a" }, { "text": "b" }] })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn languages_and_health_report_server_state() {
+        let router = test_router();
+        let (_, languages) = call(router.clone(), "GET", "/languages", None).await;
+        assert!(
+            languages["languages"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("fr"))
+        );
+        assert_eq!(languages["auto_source"], "auto");
+
+        // Wait for the mock translator to finish "loading"
+        let mut health = call(router.clone(), "GET", "/health", None).await;
+        while health.1["status"] == "loading" {
+            tokio::task::yield_now().await;
+            health = call(router.clone(), "GET", "/health", None).await;
+        }
+        assert_eq!(health.0, StatusCode::OK);
+        assert_eq!(health.1["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn health_reports_load_failure() {
+        let worker = Worker::spawn(1, || {
+            Err(petit_core::Error::ModelLoad("missing.gguf".to_string()))
+        });
+        let router = router(worker.clone(), ServerConfig::default());
+        let _ = worker.run(|_| ()).await;
+
+        let (status, body) = call(router, "GET", "/health", None).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "error");
+        assert!(body["error"].as_str().unwrap().contains("missing.gguf"));
+    }
+}
@@ -0,0 +1,514 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Resident translator on a Unix domain socket
+//!
+//! `petit daemon` keeps one translator loaded so one-shot commands such as `petit --stdin` skip
+//! the model and glossary load. Every message is a frame: a 4-byte big-endian length followed
+//! by that many bytes of JSON. A client writes a [`DaemonRequest`] frame and reads one
+//! [`DaemonResponse`] frame; a connection may carry any number of these exchanges.
+//!
+//! The daemon also reports the translator settings it was started with, so clients whose own
+//! settings differ can translate in-process instead of getting another setup's output.
+
+use petit_core::{TranslationOptions, TranslationOutput};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// Largest frame either side accepts
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Message sent by a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    /// Translate one text
+    Translate {
+        text: String,
+        source_lang: String,
+        target_lang: String,
+        #[serde(default)]
+        options: TranslationOptions,
+    },
+    /// Check that the daemon answers
+    Ping,
+    /// Ask for the translator settings the daemon was started with
+    Settings,
+}
+
+/// Message sent back by the daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    /// Successful translation
+    Translation { output: TranslationOutput },
+    /// The request failed
+    Error { message: String },
+    /// Answer to [`DaemonRequest::Ping`]
+    Pong,
+    /// Answer to [`DaemonRequest::Settings`]
+    Settings { settings: Value },
+}
+
+/// Socket used when none is configured
+///
+/// `$XDG_RUNTIME_DIR/petit.sock` when the runtime directory is set, otherwise `petit.sock` in a
+/// `petit-<uid>` directory of the temporary directory, which the daemon creates with mode 0700.
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("petit.sock"),
+        None => std::env::temp_dir()
+            .join(format!("petit-{}", user_id()))
+            .join("petit.sock"),
+    }
+}
+
+#[cfg(unix)]
+fn user_id() -> String {
+    unix::current_uid().to_string()
+}
+
+#[cfg(not(unix))]
+fn user_id() -> String {
+    std::env::var("USERNAME").unwrap_or_else(|_| "user".to_string())
+}
+
+/// Write `message` as one frame
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let payload = serde_json::to_vec(message)?;
+    writer.write_all(&frame_header(payload.len())?)?;
+    writer.write_all(&payload)?;
+    writer.flush()
+}
+
+/// Read one frame and decode it
+pub fn read_frame<R: Read, T: DeserializeOwned>(reader: &mut R) -> io::Result<T> {
+    let mut header = [0; 4];
+    reader.read_exact(&mut header)?;
+    let mut payload = vec![0; frame_len(header)?];
+    reader.read_exact(&mut payload)?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+fn frame_header(len: usize) -> io::Result<[u8; 4]> {
+    if len > MAX_FRAME_LEN {
+        return Err(frame_too_large(len));
+    }
+    Ok((len as u32).to_be_bytes())
+}
+
+fn frame_len(header: [u8; 4]) -> io::Result<usize> {
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(frame_too_large(len));
+    }
+    Ok(len)
+}
+
+fn frame_too_large(len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("frame of {len} bytes exceeds the {MAX_FRAME_LEN} byte limit"),
+    )
+}
+
+#[cfg(unix)]
+pub use unix::{DaemonClient, serve_daemon};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use petit_core::Translator;
+    use std::fs;
+    use std::future::Future;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    use crate::Worker;
+
+    /// Blocking connection to a running daemon
+    pub struct DaemonClient {
+        stream: UnixStream,
+    }
+
+    impl DaemonClient {
+        /// Connect to the daemon listening on `path`
+        ///
+        /// A socket owned by another user is refused with [`io::ErrorKind::PermissionDenied`],
+        /// since its daemon would see every text sent to it.
+        pub fn connect(path: &Path) -> io::Result<Self> {
+            if fs::metadata(path)?.uid() != current_uid() {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} belongs to another user", path.display()),
+                ));
+            }
+            Ok(Self {
+                stream: UnixStream::connect(path)?,
+            })
+        }
+
+        /// Send one request and wait for its response
+        pub fn request(&mut self, request: &DaemonRequest) -> io::Result<DaemonResponse> {
+            write_frame(&mut self.stream, request)?;
+            read_frame(&mut self.stream)
+        }
+
+        /// Translate `text` on the daemon
+        ///
+        /// Failures reported by the daemon come back as [`io::ErrorKind::Other`] errors.
+        pub fn translate(
+            &mut self,
+            text: &str,
+            source_lang: &str,
+            target_lang: &str,
+            options: &TranslationOptions,
+        ) -> io::Result<TranslationOutput> {
+            let request = DaemonRequest::Translate {
+                text: text.to_string(),
+                source_lang: source_lang.to_string(),
+                target_lang: target_lang.to_string(),
+                options: options.clone(),
+            };
+            match self.request(&request)? {
+                DaemonResponse::Translation { output } => Ok(output),
+                DaemonResponse::Error { message } => Err(io::Error::other(message)),
+                DaemonResponse::Pong | DaemonResponse::Settings { .. } => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "daemon answered a translation with something else",
+                )),
+            }
+        }
+
+        /// Translator settings the daemon was started with
+        pub fn settings(&mut self) -> io::Result<Value> {
+            match self.request(&DaemonRequest::Settings)? {
+                DaemonResponse::Settings { settings } => Ok(settings),
+                DaemonResponse::Error { message } => Err(io::Error::other(message)),
+                DaemonResponse::Pong | DaemonResponse::Translation { .. } => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "daemon answered a settings request with something else",
+                )),
+            }
+        }
+    }
+
+    /// Serve translations on the Unix socket at `socket` until Ctrl-C
+    ///
+    /// A stale socket file left by a crashed daemon is replaced; a live one is an error. The
+    /// socket is only accessible to the current user and is removed on shutdown. `settings`
+    /// describe the translator built by `factory` and are reported to clients as they are.
+    pub fn serve_daemon<F>(
+        socket: &Path,
+        queue_size: usize,
+        settings: Value,
+        factory: F,
+    ) -> io::Result<()>
+    where
+        F: FnOnce() -> petit_core::Result<Box<dyn Translator>> + Send + 'static,
+    {
+        if queue_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "daemon queue size must be at least 1",
+            ));
+        }
+        prepare_socket_path(socket)?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let result = runtime.block_on(async {
+            let listener = bind_private(socket)?;
+            eprintln!("petit daemon listening on {}", socket.display());
+
+            let worker = Worker::spawn(queue_size, factory);
+            run_daemon(listener, worker, Arc::new(settings), async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+        });
+        let _ = fs::remove_file(socket);
+        result
+    }
+
+    /// Refuse to replace a live daemon, remove a stale socket and create the parent directory
+    ///
+    /// A missing parent is created with mode 0700. An existing one must belong to the current
+    /// user or to root, so another user cannot swap the socket.
+    fn prepare_socket_path(socket: &Path) -> io::Result<()> {
+        if let Some(parent) = socket
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(parent)?;
+            let owner = fs::metadata(parent)?.uid();
+            if owner != current_uid() && owner != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} belongs to another user", parent.display()),
+                ));
+            }
+        }
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("a daemon is already listening on {}", socket.display()),
+                ));
+            }
+            fs::remove_file(socket)?;
+        }
+        Ok(())
+    }
+
+    /// Bind `socket` with mode 0600, so nobody else can connect before it is served
+    #[allow(unsafe_code)]
+    fn bind_private(socket: &Path) -> io::Result<UnixListener> {
+        // SAFETY: umask only swaps the process file mode creation mask
+        let previous = unsafe { libc::umask(0o177) };
+        let bound = UnixListener::bind(socket);
+        // SAFETY: as above, restoring the previous mask
+        unsafe { libc::umask(previous) };
+        bound
+    }
+
+    /// Real user id of this process
+    #[allow(unsafe_code)]
+    pub(super) fn current_uid() -> u32 {
+        // SAFETY: getuid has no preconditions and cannot fail
+        unsafe { libc::getuid() }
+    }
+
+    /// Accept connections until `shutdown` completes
+    async fn run_daemon(
+        listener: UnixListener,
+        worker: Worker,
+        settings: Arc<Value>,
+        shutdown: impl Future<Output = ()>,
+    ) -> io::Result<()> {
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, _) = accepted?;
+                    tokio::spawn(handle_connection(stream, worker.clone(), settings.clone()));
+                }
+                () = &mut shutdown => return Ok(()),
+            }
+        }
+    }
+
+    async fn handle_connection(
+        mut stream: tokio::net::UnixStream,
+        worker: Worker,
+        settings: Arc<Value>,
+    ) {
+        loop {
+            let mut header = [0; 4];
+            // A closed connection ends the exchange
+            if stream.read_exact(&mut header).await.is_err() {
+                return;
+            }
+            let response = match frame_len(header) {
+                Ok(len) => {
+                    let mut payload = vec![0; len];
+                    if stream.read_exact(&mut payload).await.is_err() {
+                        return;
+                    }
+                    match serde_json::from_slice(&payload) {
+                        Ok(request) => answer(request, &worker, &settings).await,
+                        Err(err) => DaemonResponse::Error {
+                            message: format!("invalid request: {err}"),
+                        },
+                    }
+                }
+                Err(err) => {
+                    // The stream position is lost, so answer and close
+                    let _ = write_response(
+                        &mut stream,
+                        &DaemonResponse::Error {
+                            message: err.to_string(),
+                        },
+                    )
+                    .await;
+                    return;
+                }
+            };
+            if write_response(&mut stream, &response).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn answer(request: DaemonRequest, worker: &Worker, settings: &Value) -> DaemonResponse {
+        match request {
+            DaemonRequest::Ping => DaemonResponse::Pong,
+            DaemonRequest::Settings => DaemonResponse::Settings {
+                settings: settings.clone(),
+            },
+            DaemonRequest::Translate {
+                text,
+                source_lang,
+                target_lang,
+                options,
+            } => {
+                let result = worker
+                    .run(move |translator| {
+                        translator.translate_with_options(
+                            &text,
+                            &source_lang,
+                            &target_lang,
+                            &options,
+                        )
+                    })
+                    .await;
+                match result {
+                    Ok(Ok(output)) => DaemonResponse::Translation { output },
+                    Ok(Err(err)) => DaemonResponse::Error {
+                        message: err.to_string(),
+                    },
+                    Err(err) => DaemonResponse::Error {
+                        message: err.to_string(),
+                    },
+                }
+            }
+        }
+    }
+
+    async fn write_response(
+        stream: &mut tokio::net::UnixStream,
+        response: &DaemonResponse,
+    ) -> io::Result<()> {
+        let payload = serde_json::to_vec(response)?;
+        stream.write_all(&frame_header(payload.len())?).await?;
+        stream.write_all(&payload).await?;
+        stream.flush().await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use petit_core::MockTranslator;
+        use std::time::{SystemTime, UNIX_EPOCH};
+        use tokio::sync::oneshot;
+
+        fn temp_socket(name: &str) -> PathBuf {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system clock should be after epoch")
+                .as_nanos();
+            std::env::temp_dir().join(format!("petit-{name}-{stamp}.sock"))
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn client_translates_through_the_daemon() {
+            let socket = temp_socket("daemon");
+            let listener = UnixListener::bind(&socket).unwrap();
+            let worker = Worker::spawn(4, || {
+                Ok(Box::new(
+                    MockTranslator::new().with_error("boom", "scripted failure"),
+                ))
+            });
+            let (stop_tx, stop_rx) = oneshot::channel::<()>();
+            let settings = Arc::new(serde_json::json!({ "backend": "mock" }));
+            let daemon = tokio::spawn(run_daemon(listener, worker, settings, async {
+                let _ = stop_rx.await;
+            }));
+
+            let client_socket = socket.clone();
+            let (pong, settings, output, failure) = tokio::task::spawn_blocking(move || {
+                let mut client = DaemonClient::connect(&client_socket).unwrap();
+                let pong = client.request(&DaemonRequest::Ping).unwrap();
+                let settings = client.settings().unwrap();
+                let output = client
+                    .translate("hello", "en", "fr", &TranslationOptions::default())
+                    .unwrap();
+                let failure = client
+                    .translate("boom", "en", "fr", &TranslationOptions::default())
+                    .unwrap_err();
+                (pong, settings, output, failure)
+            })
+            .await
+            .unwrap();
+            stop_tx.send(()).unwrap();
+            daemon.await.unwrap().unwrap();
+            let _ = fs::remove_file(&socket);
+
+            assert_eq!(pong, DaemonResponse::Pong);
+            assert_eq!(settings["backend"], "mock");
+            assert_eq!(output.text, "HELLO");
+            assert_eq!(output.target_lang, "fr");
+            assert!(failure.to_string().contains("scripted failure"));
+        }
+
+        #[test]
+        fn stale_socket_is_replaced_and_live_socket_is_refused() {
+            let socket = temp_socket("stale");
+            drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+            assert!(socket.exists());
+            prepare_socket_path(&socket).unwrap();
+            assert!(!socket.exists());
+
+            let _live = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+            let err = prepare_socket_path(&socket).unwrap_err();
+            let _ = fs::remove_file(&socket);
+
+            assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        }
+
+        #[test]
+        fn socket_directory_is_created_private() {
+            let dir = temp_socket("private").with_extension("");
+            let socket = dir.join("petit.sock");
+            prepare_socket_path(&socket).unwrap();
+            let mode = fs::metadata(&dir).unwrap().mode();
+            let _ = fs::remove_dir(&dir);
+
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let request = DaemonRequest::Translate {
+            text: "Hello".to_string(),
+            source_lang: "en".to_string(),
+            target_lang: "fr".to_string(),
+            options: TranslationOptions::new().with_max_new_tokens(8),
+        };
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &request).unwrap();
+
+        assert_eq!(
+            u32::from_be_bytes(buffer[..4].try_into().unwrap()) as usize,
+            buffer.len() - 4
+        );
+        let parsed: DaemonRequest = read_frame(&mut buffer.as_slice()).unwrap();
+        assert_eq!(parsed, request);
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let mut buffer = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes().to_vec();
+        buffer.extend_from_slice(b"{}");
+
+        let err = read_frame::<_, DaemonRequest>(&mut buffer.as_slice()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
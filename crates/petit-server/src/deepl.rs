@@ -0,0 +1,795 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! DeepL API v2-compatible endpoints
+//!
+//! - `POST /v2/translate`: `{text, source_lang, target_lang, formality, glossary_id}` to
+//!   `{translations: [{detected_source_language, text}]}`
+//! - `GET|POST /v2/languages?type=source|target`: `[{language, name, supports_formality}]`
+//! - `GET /v2/glossary-language-pairs`: language pairs a glossary may use
+//! - `POST|GET /v2/glossaries`, `GET|DELETE /v2/glossaries/{id}` and
+//!   `GET /v2/glossaries/{id}/entries`: in-memory glossaries
+//!
+//! Language codes follow DeepL's upper case spelling (`EN-GB`, `PT-BR`) and go through
+//! [`normalize_lang`]. Each glossary becomes an exact-match [`GlossaryStore`]; its matches are
+//! sent as request glossary terms, so they apply on top of the server's own glossary.
+//! Errors use DeepL's `{"message": "..."}` body and the `Authorization` header is ignored.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use petit_core::language::{
+    AUTO_SOURCE_LANG, base_lang, is_auto_source, language_name, normalize_lang,
+    supported_languages, validate_pair,
+};
+use petit_core::{
+    Formality, GlossaryCandidate, GlossaryStore, TranslationOptions, TranslationRequest,
+    detect_language,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ApiError;
+use crate::api::AppState;
+use crate::libretranslate::JsonOrForm;
+
+/// Glossary matches sent with one text
+const GLOSSARY_MAX_MATCHES: usize = 16;
+
+/// Instruction added to the prompt for `tag_handling: "html"` or `"xml"`
+const MARKUP_INSTRUCTIONS: &str =
+    "The text contains markup. Keep every tag and attribute unchanged and translate only the text.";
+
+/// Error rendered in DeepL's shape
+#[derive(Debug)]
+pub struct DeepLError(pub ApiError);
+
+impl From<ApiError> for DeepLError {
+    fn from(err: ApiError) -> Self {
+        Self(err)
+    }
+}
+
+impl From<petit_core::Error> for DeepLError {
+    fn from(err: petit_core::Error) -> Self {
+        Self(ApiError::from(err))
+    }
+}
+
+impl IntoResponse for DeepLError {
+    fn into_response(self) -> Response {
+        (
+            self.0.status(),
+            Json(json!({ "message": self.0.to_string() })),
+        )
+            .into_response()
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> DeepLError {
+    DeepLError(ApiError::BadRequest(message.into()))
+}
+
+/// `text` as a list, also accepting a single string like one form field
+fn text_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Text {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Text::deserialize(deserializer)? {
+        Text::One(text) => vec![text],
+        Text::Many(texts) => texts,
+    })
+}
+
+/// Body of `POST /v2/translate`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranslateBody {
+    #[serde(deserialize_with = "text_list")]
+    pub text: Vec<String>,
+    #[serde(default)]
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    #[serde(default)]
+    pub formality: Option<String>,
+    #[serde(default)]
+    pub glossary_id: Option<String>,
+    #[serde(default)]
+    pub context: Option<String>,
+    #[serde(default)]
+    pub tag_handling: Option<String>,
+}
+
+/// Query of `/v2/languages`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LanguagesQuery {
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+}
+
+/// Body of `POST /v2/glossaries`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateGlossaryBody {
+    pub name: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub entries: String,
+    #[serde(default)]
+    pub entries_format: Option<String>,
+}
+
+/// Glossary metadata in DeepL's shape
+#[derive(Debug, Clone, Serialize)]
+pub struct GlossaryInfo {
+    pub glossary_id: String,
+    pub name: String,
+    pub ready: bool,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub creation_time: String,
+    pub entry_count: usize,
+}
+
+/// Glossary created through the API
+struct Glossary {
+    info: GlossaryInfo,
+    entries: Vec<(String, String)>,
+    store: GlossaryStore,
+}
+
+/// In-memory glossaries shared by every handler
+#[derive(Clone, Default)]
+pub(crate) struct Glossaries {
+    by_id: Arc<RwLock<HashMap<String, Arc<Glossary>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Glossaries {
+    fn get(&self, id: &str) -> Result<Arc<Glossary>, DeepLError> {
+        self.by_id
+            .read()
+            .ok()
+            .and_then(|glossaries| glossaries.get(id).cloned())
+            .ok_or_else(|| DeepLError(ApiError::NotFound(format!("glossary `{id}` not found"))))
+    }
+
+    fn insert(&self, glossary: Glossary) -> GlossaryInfo {
+        let info = glossary.info.clone();
+        if let Ok(mut glossaries) = self.by_id.write() {
+            glossaries.insert(info.glossary_id.clone(), Arc::new(glossary));
+        }
+        info
+    }
+
+    fn remove(&self, id: &str) -> bool {
+        self.by_id
+            .write()
+            .is_ok_and(|mut glossaries| glossaries.remove(id).is_some())
+    }
+
+    fn list(&self) -> Vec<GlossaryInfo> {
+        let mut glossaries: Vec<GlossaryInfo> = self
+            .by_id
+            .read()
+            .map(|glossaries| {
+                glossaries
+                    .values()
+                    .map(|glossary| glossary.info.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        glossaries.sort_by(|left, right| {
+            (&left.creation_time, &left.glossary_id)
+                .cmp(&(&right.creation_time, &right.glossary_id))
+        });
+        glossaries
+    }
+
+    fn new_id(&self) -> String {
+        let sequence = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        format!("{:016x}-{sequence:04x}", stamp as u64)
+    }
+}
+
+/// DeepL routes, served next to the native or LibreTranslate API
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v2/translate", post(translate))
+        .route("/v2/languages", get(languages).post(languages))
+        .route("/v2/glossary-language-pairs", get(glossary_language_pairs))
+        .route("/v2/glossaries", get(list_glossaries).post(create_glossary))
+        .route(
+            "/v2/glossaries/{id}",
+            get(get_glossary).delete(delete_glossary),
+        )
+        .route("/v2/glossaries/{id}/entries", get(glossary_entries))
+}
+
+/// Map DeepL's formality values onto [`Formality`]
+fn parse_formality(value: &str) -> Result<Option<Formality>, DeepLError> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "default" => Ok(None),
+        "more" | "prefer_more" => Ok(Some(Formality::Formal)),
+        "less" | "prefer_less" => Ok(Some(Formality::Informal)),
+        other => Err(bad_request(format!(
+            "formality must be `default`, `more`, `less`, `prefer_more` or `prefer_less`, got `{other}`"
+        ))),
+    }
+}
+
+async fn translate(
+    State(state): State<AppState>,
+    body: Result<JsonOrForm<TranslateBody>, ApiError>,
+) -> Result<Json<Value>, DeepLError> {
+    let JsonOrForm(body) = body?;
+    if body.text.is_empty() || body.text.iter().all(|text| text.trim().is_empty()) {
+        return Err(bad_request("Parameter 'text' not specified."));
+    }
+    if body.text.len() > state.config.max_batch_size {
+        return Err(bad_request(format!(
+            "text has {} entries, the limit is {}",
+            body.text.len(),
+            state.config.max_batch_size
+        )));
+    }
+
+    let source = body
+        .source_lang
+        .as_deref()
+        .map(normalize_lang)
+        .filter(|source| !source.trim().is_empty() && !is_auto_source(source))
+        .unwrap_or_else(|| AUTO_SOURCE_LANG.to_string());
+    let target = normalize_lang(&body.target_lang);
+    validate_pair(&source, &target)?;
+
+    let mut options = TranslationOptions::default();
+    if let Some(formality) = body.formality.as_deref() {
+        options.formality = parse_formality(formality)?;
+    }
+    let mut instructions = Vec::new();
+    match body.tag_handling.as_deref() {
+        None | Some("") => {}
+        Some("html" | "xml") => instructions.push(MARKUP_INSTRUCTIONS.to_string()),
+        Some(other) => {
+            return Err(bad_request(format!(
+                "tag_handling must be `html` or `xml`, got `{other}`"
+            )));
+        }
+    }
+    if let Some(context) = body.context.as_deref().map(str::trim)
+        && !context.is_empty()
+    {
+        instructions.push(format!(
+            "Context for the text, do not translate it:\n{context}"
+        ));
+    }
+    if !instructions.is_empty() {
+        options.instructions = Some(instructions.join("\n"));
+    }
+
+    let glossary = match body.glossary_id.as_deref() {
+        Some(id) => {
+            if source == AUTO_SOURCE_LANG {
+                return Err(bad_request(
+                    "source_lang is required when a glossary is used",
+                ));
+            }
+            let glossary = state.glossaries.get(id)?;
+            if base_lang(&glossary.info.source_lang) != base_lang(&source)
+                || base_lang(&glossary.info.target_lang) != base_lang(&target)
+            {
+                return Err(bad_request(format!(
+                    "glossary `{id}` is for {}->{}, not {source}->{target}",
+                    glossary.info.source_lang, glossary.info.target_lang
+                )));
+            }
+            Some(glossary)
+        }
+        None => None,
+    };
+
+    let mut detected = Vec::with_capacity(body.text.len());
+    let mut requests = Vec::with_capacity(body.text.len());
+    for text in body.text {
+        detected.push(if source == AUTO_SOURCE_LANG {
+            detect_language(&text)
+                .map_or_else(String::new, |detected| detected.language.to_uppercase())
+        } else {
+            base_lang(&source).to_uppercase()
+        });
+
+        let mut options = options.clone();
+        if let Some(glossary) = &glossary {
+            let terms: Vec<GlossaryCandidate> = glossary.store.select_candidates(
+                &glossary.info.source_lang,
+                &glossary.info.target_lang,
+                &text,
+            )?;
+            options = options.with_glossary_terms(terms);
+        }
+        requests.push(
+            TranslationRequest::new(text, source.clone(), target.clone()).with_options(options),
+        );
+    }
+
+    let translated = state
+        .worker
+        .run(move |translator| translator.translate_batch(&requests))
+        .await?
+        .into_iter()
+        .map(|result| result.map(|output| output.text))
+        .collect::<petit_core::Result<Vec<String>>>()?;
+
+    let translations: Vec<Value> = detected
+        .into_iter()
+        .zip(translated)
+        .map(|(detected, text)| json!({ "detected_source_language": detected, "text": text }))
+        .collect();
+    Ok(Json(json!({ "translations": translations })))
+}
+
+async fn languages(Query(query): Query<LanguagesQuery>) -> Result<Json<Value>, DeepLError> {
+    let target = match query.kind.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("source") => false,
+        Some("target") => true,
+        Some(other) => {
+            return Err(bad_request(format!(
+                "type must be `source` or `target`, got `{other}`"
+            )));
+        }
+    };
+
+    let languages: Vec<Value> = supported_languages()
+        .iter()
+        .map(|code| {
+            let mut language = json!({
+                "language": code.to_uppercase(),
+                "name": language_name(code).unwrap_or(code),
+            });
+            if target {
+                // Formality is a prompt instruction, so every target accepts it
+                language["supports_formality"] = json!(true);
+            }
+            language
+        })
+        .collect();
+    Ok(Json(Value::Array(languages)))
+}
+
+async fn glossary_language_pairs() -> Json<Value> {
+    let pairs: Vec<Value> = supported_languages()
+        .iter()
+        .flat_map(|source| {
+            supported_languages()
+                .iter()
+                .filter(move |target| *target != source)
+                .map(move |target| json!({ "source_lang": source, "target_lang": target }))
+        })
+        .collect();
+    Json(json!({ "supported_languages": pairs }))
+}
+
+async fn create_glossary(
+    State(state): State<AppState>,
+    body: Result<JsonOrForm<CreateGlossaryBody>, ApiError>,
+) -> Result<(StatusCode, Json<GlossaryInfo>), DeepLError> {
+    let JsonOrForm(body) = body?;
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(bad_request("Parameter 'name' not specified."));
+    }
+    let source = normalize_lang(&body.source_lang);
+    let target = normalize_lang(&body.target_lang);
+    if is_auto_source(&source) {
+        return Err(bad_request("glossaries need an explicit source_lang"));
+    }
+    validate_pair(&source, &target)?;
+
+    let entries = match body.entries_format.as_deref().unwrap_or("tsv") {
+        "tsv" => parse_tsv_entries(&body.entries)?,
+        "csv" => parse_csv_entries(&body.entries)?,
+        other => {
+            return Err(bad_request(format!(
+                "entries_format must be `tsv` or `csv`, got `{other}`"
+            )));
+        }
+    };
+    if entries.is_empty() {
+        return Err(bad_request("glossary has no entries"));
+    }
+    let store = GlossaryStore::from_terms(&source, &target, entries.clone(), GLOSSARY_MAX_MATCHES)?;
+
+    let info = state.glossaries.insert(Glossary {
+        info: GlossaryInfo {
+            glossary_id: state.glossaries.new_id(),
+            name: name.to_string(),
+            ready: true,
+            source_lang: source,
+            target_lang: target,
+            creation_time: rfc3339_now(),
+            entry_count: store.len(),
+        },
+        entries,
+        store,
+    });
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+async fn list_glossaries(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({ "glossaries": state.glossaries.list() }))
+}
+
+async fn get_glossary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<GlossaryInfo>, DeepLError> {
+    Ok(Json(state.glossaries.get(&id)?.info.clone()))
+}
+
+async fn delete_glossary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, DeepLError> {
+    if state.glossaries.remove(&id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(DeepLError(ApiError::NotFound(format!(
+            "glossary `{id}` not found"
+        ))))
+    }
+}
+
+async fn glossary_entries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, DeepLError> {
+    let glossary = state.glossaries.get(&id)?;
+    let tsv: String = glossary
+        .entries
+        .iter()
+        .map(|(source, target)| format!("{source}\t{target}\n"))
+        .collect();
+    Ok(([(CONTENT_TYPE, "text/tab-separated-values")], tsv).into_response())
+}
+
+/// Parse `source<TAB>target` lines, skipping blank ones
+fn parse_tsv_entries(entries: &str) -> Result<Vec<(String, String)>, DeepLError> {
+    entries
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(
+            |(index, line)| match line.split('\t').collect::<Vec<_>>()[..] {
+                [source, target] if !source.trim().is_empty() && !target.trim().is_empty() => {
+                    Ok((source.trim().to_string(), target.trim().to_string()))
+                }
+                _ => Err(bad_request(format!(
+                    "glossary entry on line {} must be `source<TAB>target`",
+                    index + 1
+                ))),
+            },
+        )
+        .collect()
+}
+
+/// Parse CSV rows, using the first two columns as source and target terms
+fn parse_csv_entries(entries: &str) -> Result<Vec<(String, String)>, DeepLError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(entries.as_bytes());
+    let mut parsed = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| bad_request(format!("CSV parse error: {err}")))?;
+        match (record.get(0).map(str::trim), record.get(1).map(str::trim)) {
+            (Some(source), Some(target)) if !source.is_empty() && !target.is_empty() => {
+                parsed.push((source.to_string(), target.to_string()));
+            }
+            _ => {
+                return Err(bad_request(format!(
+                    "glossary entry on row {} needs a source and a target term",
+                    index + 1
+                )));
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Current UTC time as `YYYY-MM-DDTHH:MM:SS.mmmZ`
+fn rfc3339_now() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = elapsed.as_secs();
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServerConfig, Worker, router};
+    use axum::body::{Body, to_bytes};
+    use axum::extract::Request;
+    use petit_core::MockTranslator;
+    use tower::ServiceExt;
+
+    fn test_router() -> Router {
+        let worker = Worker::spawn(4, || {
+            Ok(Box::new(
+                MockTranslator::new()
+                    .with_response("The weather is nice", "Das Wetter ist schön")
+                    .with_response("Check the account balance", "Prüfen Sie den Kontostand"),
+            ))
+        });
+        router(
+            worker,
+            ServerConfig {
+                deepl: true,
+                ..ServerConfig::default()
+            },
+        )
+    }
+
+    async fn call(
+        router: Router,
+        method: &str,
+        uri: &str,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+        (status, body)
+    }
+
+    async fn call_json(
+        router: Router,
+        method: &str,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        call(router, method, uri, "application/json", body.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn translate_accepts_json_and_form_bodies() {
+        let (status, body) = call_json(
+            test_router(),
+            "POST",
+            "/v2/translate",
+            json!({ "text": ["The weather is nice", "good night"], "target_lang": "DE" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["translations"][0]["text"], "Das Wetter ist schön");
+        assert_eq!(body["translations"][0]["detected_source_language"], "EN");
+        assert_eq!(body["translations"][1]["text"], "GOOD NIGHT");
+
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/v2/translate",
+            "application/x-www-form-urlencoded",
+            "text=hello&text=bye&source_lang=EN&target_lang=PT-BR&formality=prefer_less"
+                .to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["translations"][1]["text"], "BYE");
+        assert_eq!(body["translations"][1]["detected_source_language"], "EN");
+    }
+
+    #[tokio::test]
+    async fn translate_rejects_invalid_input_with_deepl_errors() {
+        let (status, body) = call_json(
+            test_router(),
+            "POST",
+            "/v2/translate",
+            json!({ "text": ["hi"], "target_lang": "XX" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["message"].as_str().unwrap().contains("xx"));
+
+        let (status, _) = call_json(
+            test_router(),
+            "POST",
+            "/v2/translate",
+            json!({ "text": ["hi"], "target_lang": "DE", "formality": "very" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = call_json(
+            test_router(),
+            "POST",
+            "/v2/translate",
+            json!({ "text": ["hi"], "source_lang": "EN", "target_lang": "DE", "glossary_id": "missing" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn routes_are_off_unless_enabled() {
+        let worker = Worker::spawn(4, || Ok(Box::new(MockTranslator::new())));
+        let (status, _) = call_json(
+            router(worker, ServerConfig::default()),
+            "POST",
+            "/v2/translate",
+            json!({ "text": ["hi"], "target_lang": "DE" }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn languages_use_deepl_codes() {
+        let (status, body) = call(
+            test_router(),
+            "GET",
+            "/v2/languages?type=target",
+            "",
+            String::new(),
+        )
+        .await;
+        let german = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|language| language["language"] == "DE")
+            .unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(german["name"], "German");
+        assert_eq!(german["supports_formality"], true);
+    }
+
+    #[tokio::test]
+    async fn glossary_lifecycle_applies_terms_to_translations() {
+        let router = test_router();
+        let (status, created) = call_json(
+            router.clone(),
+            "POST",
+            "/v2/glossaries",
+            json!({
+                "name": "Banking",
+                "source_lang": "EN",
+                "target_lang": "DE",
+                "entries": "account balance\tKontostand\ninvoice\tRechnung\n",
+                "entries_format": "tsv",
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["entry_count"], 2);
+        assert_eq!(created["source_lang"], "en");
+        let id = created["glossary_id"].as_str().unwrap().to_string();
+
+        let (_, listed) = call(router.clone(), "GET", "/v2/glossaries", "", String::new()).await;
+        assert_eq!(listed["glossaries"][0]["glossary_id"], id.as_str());
+
+        let (_, entries) = call(
+            router.clone(),
+            "GET",
+            &format!("/v2/glossaries/{id}/entries"),
+            "",
+            String::new(),
+        )
+        .await;
+        assert_eq!(entries, "account balance\tKontostand\ninvoice\tRechnung\n");
+
+        let (status, body) = call_json(
+            router.clone(),
+            "POST",
+            "/v2/translate",
+            json!({
+                "text": ["Check the account balance"],
+                "source_lang": "EN",
+                "target_lang": "DE",
+                "glossary_id": id,
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["translations"][0]["text"], "Prüfen Sie den Kontostand");
+
+        let (status, _) = call(
+            router.clone(),
+            "DELETE",
+            &format!("/v2/glossaries/{id}"),
+            "",
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, body) = call(
+            router,
+            "GET",
+            &format!("/v2/glossaries/{id}"),
+            "",
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["message"].as_str().unwrap().contains(&id));
+    }
+
+    #[tokio::test]
+    async fn glossary_entry_count_skips_duplicate_terms() {
+        let (status, created) = call(
+            test_router(),
+            "POST",
+            "/v2/glossaries",
+            "application/x-www-form-urlencoded",
+            "name=Dup&source_lang=EN&target_lang=DE&entries=invoice%09Rechnung%0Ainvoice%09Rechnung%0A"
+                .to_string(),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["entry_count"], 1);
+    }
+
+    #[test]
+    fn glossary_entries_parse_tsv_and_csv() {
+        assert_eq!(
+            parse_tsv_entries("a\tb\n\n c \t d \n").unwrap(),
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string())
+            ]
+        );
+        assert!(parse_tsv_entries("missing target\n").is_err());
+        assert_eq!(
+            parse_csv_entries("\"hello, world\",\"hallo, Welt\",en,de\n").unwrap(),
+            vec![("hello, world".to_string(), "hallo, Welt".to_string())]
+        );
+    }
+}
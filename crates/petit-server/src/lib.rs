@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! petit-server: Local HTTP translation server for petit_trad
+//!
+//! The server loads one translator and shares it between clients through a JSON REST API,
+//! so tools do not pay the model load time on every call. [`daemon`] offers the same over a
+//! Unix domain socket for local command-line clients.
+
+pub mod api;
+pub mod daemon;
+pub mod deepl;
+pub mod libretranslate;
+pub mod worker;
+
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use petit_core::Translator;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub use api::router;
+pub use worker::{Worker, WorkerStatus};
+
+/// Request and response shapes served on `/translate` and `/languages`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerApi {
+    /// petit's own JSON API
+    #[default]
+    Native,
+    /// LibreTranslate-compatible API, including `/detect`
+    LibreTranslate,
+}
+
+impl std::str::FromStr for ServerApi {
+    type Err = petit_core::Error;
+
+    fn from_str(value: &str) -> petit_core::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "native" => Ok(ServerApi::Native),
+            "libretranslate" => Ok(ServerApi::LibreTranslate),
+            other => Err(petit_core::Error::Config(format!(
+                "server api must be `native` or `libretranslate`, got `{other}`"
+            ))),
+        }
+    }
+}
+
+/// Server settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address to listen on, e.g. `127.0.0.1:8000`
+    pub bind: String,
+
+    /// API served on `/translate` and `/languages`
+    pub api: ServerApi,
+
+    /// Requests that may wait for the translator before new ones are rejected
+    pub queue_size: usize,
+
+    /// Maximum number of texts in one batch request
+    pub max_batch_size: usize,
+
+    /// Source language used when a request does not set one
+    pub default_source: String,
+
+    /// Target language used when a request does not set one
+    pub default_target: String,
+
+    /// Also serve the DeepL-compatible `/v2` endpoints
+    pub deepl: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8000".to_string(),
+            api: ServerApi::Native,
+            queue_size: 32,
+            max_batch_size: 64,
+            default_source: petit_core::language::AUTO_SOURCE_LANG.to_string(),
+            default_target: "fr".to_string(),
+            deepl: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Check that every value is within its valid range
+    pub fn validate(&self) -> petit_core::Result<()> {
+        if self.queue_size == 0 {
+            return Err(petit_core::Error::Config(
+                "server.queue_size must be at least 1".to_string(),
+            ));
+        }
+        if self.max_batch_size == 0 {
+            return Err(petit_core::Error::Config(
+                "server.max_batch_size must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by an endpoint, rendered as `{"error": "..."}`
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request is malformed or asks for something unsupported
+    BadRequest(String),
+    /// The requested resource does not exist
+    NotFound(String),
+    /// Every queue slot is taken
+    QueueFull,
+    /// The translator is not available
+    Unavailable(String),
+    /// The translation itself failed
+    Translation(petit_core::Error),
+}
+
+impl ApiError {
+    /// HTTP status for this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::QueueFull | ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Translation(err) => translation_status(err),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::NotFound(message)
+            | ApiError::Unavailable(message) => f.write_str(message),
+            ApiError::QueueFull => f.write_str("translation queue is full, retry later"),
+            ApiError::Translation(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<petit_core::Error> for ApiError {
+    fn from(err: petit_core::Error) -> Self {
+        ApiError::Translation(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+/// HTTP status for a failed translation
+pub fn translation_status(err: &petit_core::Error) -> StatusCode {
+    match err {
+        petit_core::Error::UnsupportedLanguage(_) | petit_core::Error::Config(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        petit_core::Error::Cancelled => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Serve the API on `config.bind` until Ctrl-C
+///
+/// `factory` creates the translator on the worker thread; the server accepts connections
+/// while it loads and queues translation requests until it is ready.
+pub fn serve<F>(config: ServerConfig, factory: F) -> std::io::Result<()>
+where
+    F: FnOnce() -> petit_core::Result<Box<dyn Translator>> + Send + 'static,
+{
+    config
+        .validate()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err.to_string()))?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::bind(&config.bind).await?;
+        eprintln!(
+            "petit server listening on http://{}",
+            listener.local_addr()?
+        );
+
+        let worker = Worker::spawn(config.queue_size, factory);
+        axum::serve(listener, router(worker, config))
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+    })
+}
@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! LibreTranslate-compatible endpoints
+//!
+//! - `POST /translate`: `{q, source, target, format}` to `{translatedText, detectedLanguage}`
+//! - `GET /languages`: `[{code, name, targets}]`
+//! - `POST /detect`: `{q}` to `[{language, confidence}]`
+//!
+//! Bodies may be JSON or form encoded, like LibreTranslate accepts. `source: "auto"` maps onto
+//! [`AUTO_SOURCE_LANG`], so the model infers the source; `detectedLanguage` comes from
+//! [`detect_language`]. API keys are accepted and ignored.
+
+use axum::extract::{FromRequest, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use petit_core::language::{
+    AUTO_SOURCE_LANG, is_auto_source, language_name, supported_languages, validate_pair,
+};
+use petit_core::{DetectedLanguage, TranslationOptions, TranslationRequest, detect_language};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::ApiError;
+use crate::api::AppState;
+
+/// Instruction added to the prompt for `format: "html"`
+const HTML_INSTRUCTIONS: &str =
+    "The text is HTML. Keep every tag and attribute unchanged and translate only the text.";
+
+/// Request body that may be JSON or form encoded
+///
+/// Repeated form fields become lists, as in `text=one&text=two`.
+pub(crate) struct JsonOrForm<T>(pub(crate) T);
+
+impl<S, T> FromRequest<S> for JsonOrForm<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+        if is_json {
+            let Json(value) = Json::<T>::from_request(request, state)
+                .await
+                .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+            Ok(Self(value))
+        } else {
+            let Form(fields) = Form::<Vec<(String, String)>>::from_request(request, state)
+                .await
+                .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+            serde_json::from_value(form_to_json(fields))
+                .map(Self)
+                .map_err(|err| ApiError::BadRequest(err.to_string()))
+        }
+    }
+}
+
+fn form_to_json(fields: Vec<(String, String)>) -> Value {
+    let mut object = Map::new();
+    for (key, value) in fields {
+        match object.get_mut(&key) {
+            Some(Value::Array(values)) => values.push(Value::String(value)),
+            Some(existing) => *existing = json!([existing.take(), value]),
+            None => {
+                object.insert(key, Value::String(value));
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+/// One text or a list of texts
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Query {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Body of `POST /translate`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranslateBody {
+    pub q: Query,
+    #[serde(default = "auto_source")]
+    pub source: String,
+    pub target: String,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Body of `POST /detect`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetectBody {
+    pub q: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Detection result in LibreTranslate's shape (confidence from 0 to 100)
+#[derive(Debug, Clone, Serialize)]
+pub struct Detection {
+    pub confidence: f32,
+    pub language: &'static str,
+}
+
+impl From<DetectedLanguage> for Detection {
+    fn from(detected: DetectedLanguage) -> Self {
+        Self {
+            confidence: (detected.confidence * 100.0).round(),
+            language: detected.language,
+        }
+    }
+}
+
+fn auto_source() -> String {
+    AUTO_SOURCE_LANG.to_string()
+}
+
+/// Routes that replace the native `/translate` and `/languages`
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/translate", post(translate))
+        .route("/languages", get(languages))
+        .route("/detect", post(detect))
+}
+
+async fn translate(
+    State(state): State<AppState>,
+    JsonOrForm(body): JsonOrForm<TranslateBody>,
+) -> Result<Json<Value>, ApiError> {
+    let source = if is_auto_source(&body.source) {
+        AUTO_SOURCE_LANG.to_string()
+    } else {
+        body.source
+    };
+    validate_pair(&source, &body.target)?;
+    let options = match body.format.as_deref().unwrap_or("text") {
+        "text" => TranslationOptions::default(),
+        "html" => TranslationOptions::new().with_instructions(HTML_INSTRUCTIONS),
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "format must be `text` or `html`, got `{other}`"
+            )));
+        }
+    };
+
+    let (texts, single) = match body.q {
+        Query::One(text) => (vec![text], true),
+        Query::Many(texts) => (texts, false),
+    };
+    if texts.is_empty() || texts.iter().all(|text| text.trim().is_empty()) {
+        return Err(ApiError::BadRequest(
+            "Invalid request: missing q parameter".to_string(),
+        ));
+    }
+    if texts.len() > state.config.max_batch_size {
+        return Err(ApiError::BadRequest(format!(
+            "q has {} texts, the limit is {}",
+            texts.len(),
+            state.config.max_batch_size
+        )));
+    }
+
+    let detected: Option<Vec<Value>> = (source == AUTO_SOURCE_LANG).then(|| {
+        texts
+            .iter()
+            .map(|text| {
+                detect_language(text)
+                    .map_or(Value::Null, |detected| json!(Detection::from(detected)))
+            })
+            .collect()
+    });
+    let requests: Vec<TranslationRequest> = texts
+        .into_iter()
+        .map(|text| {
+            TranslationRequest::new(text, source.clone(), body.target.clone())
+                .with_options(options.clone())
+        })
+        .collect();
+
+    let translated = state
+        .worker
+        .run(move |translator| translator.translate_batch(&requests))
+        .await?
+        .into_iter()
+        .map(|result| result.map(|output| output.text))
+        .collect::<petit_core::Result<Vec<String>>>()?;
+
+    let mut response = if single {
+        json!({ "translatedText": translated[0] })
+    } else {
+        json!({ "translatedText": translated })
+    };
+    if let Some(detected) = detected {
+        response["detectedLanguage"] = if single {
+            detected.into_iter().next().unwrap_or(Value::Null)
+        } else {
+            Value::Array(detected)
+        };
+    }
+    Ok(Json(response))
+}
+
+async fn languages() -> Json<Value> {
+    let languages: Vec<Value> = supported_languages()
+        .iter()
+        .map(|code| {
+            let targets: Vec<&str> = supported_languages()
+                .iter()
+                .copied()
+                .filter(|target| target != code)
+                .collect();
+            json!({
+                "code": code,
+                "name": language_name(code).unwrap_or(code),
+                "targets": targets,
+            })
+        })
+        .collect();
+    Json(Value::Array(languages))
+}
+
+async fn detect(
+    JsonOrForm(body): JsonOrForm<DetectBody>,
+) -> Result<Json<Vec<Detection>>, ApiError> {
+    if body.q.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "Invalid request: missing q parameter".to_string(),
+        ));
+    }
+    Ok(Json(
+        detect_language(&body.q)
+            .map(Detection::from)
+            .into_iter()
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServerApi, ServerConfig, Worker, router};
+    use axum::body::{Body, to_bytes};
+    use axum::http::StatusCode;
+    use petit_core::MockTranslator;
+    use tower::ServiceExt;
+
+    fn test_router() -> Router {
+        let worker = Worker::spawn(4, || {
+            Ok(Box::new(
+                MockTranslator::new().with_response("The weather is nice", "Il fait beau"),
+            ))
+        });
+        router(
+            worker,
+            ServerConfig {
+                api: ServerApi::LibreTranslate,
+                ..ServerConfig::default()
+            },
+        )
+    }
+
+    async fn call(
+        router: Router,
+        method: &str,
+        uri: &str,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn json_body(value: Value) -> (&'static str, String) {
+        ("application/json", value.to_string())
+    }
+
+    #[tokio::test]
+    async fn translate_with_auto_source_reports_detected_language() {
+        let (content_type, body) = json_body(json!({
+            "q": "The weather is nice",
+            "source": "auto",
+            "target": "fr",
+            "api_key": "ignored",
+        }));
+
+        let (status, body) = call(test_router(), "POST", "/translate", content_type, body).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["translatedText"], "Il fait beau");
+        assert_eq!(body["detectedLanguage"]["language"], "en");
+    }
+
+    #[tokio::test]
+    async fn translate_accepts_form_bodies_and_text_lists() {
+        let (status, body) = call(
+            test_router(),
+            "POST",
+            "/translate",
+            "application/x-www-form-urlencoded",
+            "q=The+weather+is+nice&source=en&target=fr".to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["translatedText"], "Il fait beau");
+        assert!(body.get("detectedLanguage").is_none());
+
+        let (content_type, request) = json_body(json!({
+            "q": ["The weather is nice", "good night"],
+            "source": "en",
+            "target": "fr",
+        }));
+        let (status, body) = call(test_router(), "POST", "/translate", content_type, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["translatedText"],
+            json!(["Il fait beau", "GOOD NIGHT"])
+        );
+    }
+
+    #[tokio::test]
+    async fn translate_rejects_unsupported_target_and_format() {
+        let (content_type, body) = json_body(json!({ "q": "Hi", "source": "en", "target": "xx" }));
+        let (status, body) = call(test_router(), "POST", "/translate", content_type, body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("xx"));
+
+        let (content_type, body) = json_body(json!({
+            "q": "Hi",
+            "source": "en",
+            "target": "fr",
+            "format": "pdf",
+        }));
+        let (status, _) = call(test_router(), "POST", "/translate", content_type, body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn languages_list_names_and_targets() {
+        let (status, body) = call(test_router(), "GET", "/languages", "", String::new()).await;
+        let languages = body.as_array().unwrap();
+        let french = languages
+            .iter()
+            .find(|language| language["code"] == "fr")
+            .unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(languages.len(), supported_languages().len());
+        assert_eq!(french["name"], "French");
+        assert!(french["targets"].as_array().unwrap().contains(&json!("en")));
+        assert!(!french["targets"].as_array().unwrap().contains(&json!("fr")));
+    }
+
+    #[tokio::test]
+    async fn detect_returns_ranked_languages() {
+        let (content_type, body) = json_body(json!({ "q": "Der Hund ist nicht im Garten." }));
+
+        let (status, body) = call(test_router(), "POST", "/detect", content_type, body).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["language"], "de");
+        assert!(body[0]["confidence"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn form_fields_repeat_into_lists() {
+        let value = form_to_json(vec![
+            ("q".to_string(), "one".to_string()),
+            ("target".to_string(), "de".to_string()),
+            ("q".to_string(), "two".to_string()),
+        ]);
+
+        assert_eq!(value, json!({ "q": ["one", "two"], "target": "de" }));
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Translator worker thread with a bounded job queue
+//!
+//! The translator is created and used on one dedicated thread, so a loaded model is shared by
+//! every request. Handlers submit jobs and await their result without blocking the runtime.
+
+use petit_core::Translator;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use tokio::sync::oneshot;
+
+use crate::ApiError;
+
+/// Work executed on the translator thread
+type Job = Box<dyn FnOnce(&dyn Translator) + Send>;
+
+/// Lifecycle of the translator owned by the worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerStatus {
+    /// The translator is being created (model and glossary loading)
+    Loading,
+    /// The translator accepts jobs
+    Ready,
+    /// The translator could not be created
+    Failed(String),
+}
+
+/// Handle to the translator thread
+#[derive(Clone)]
+pub struct Worker {
+    sender: SyncSender<Job>,
+    status: Arc<RwLock<WorkerStatus>>,
+}
+
+impl Worker {
+    /// Spawn the translator thread, queueing at most `queue_size` jobs
+    ///
+    /// `factory` runs on the new thread. Jobs submitted while it runs wait in the queue.
+    pub fn spawn<F>(queue_size: usize, factory: F) -> Self
+    where
+        F: FnOnce() -> petit_core::Result<Box<dyn Translator>> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(queue_size);
+        let status = Arc::new(RwLock::new(WorkerStatus::Loading));
+        let thread_status = Arc::clone(&status);
+
+        thread::spawn(move || run_worker(factory, receiver, thread_status));
+
+        Self { sender, status }
+    }
+
+    /// Current translator status
+    pub fn status(&self) -> WorkerStatus {
+        self.status
+            .read()
+            .map(|status| status.clone())
+            .unwrap_or_else(|_| WorkerStatus::Failed("worker status lock poisoned".to_string()))
+    }
+
+    /// Run `job` on the translator thread and return its result
+    ///
+    /// Fails immediately with [`ApiError::QueueFull`] when the queue has no free slot.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn Translator) -> T + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let job: Job = Box::new(move |translator| {
+            let _ = reply_tx.send(job(translator));
+        });
+
+        match self.sender.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => return Err(ApiError::QueueFull),
+            Err(TrySendError::Disconnected(_)) => return Err(self.unavailable()),
+        }
+        reply_rx.await.map_err(|_| self.unavailable())
+    }
+
+    fn unavailable(&self) -> ApiError {
+        match self.status() {
+            WorkerStatus::Failed(message) => {
+                ApiError::Unavailable(format!("translator failed to load: {message}"))
+            }
+            _ => ApiError::Unavailable("translator worker stopped".to_string()),
+        }
+    }
+}
+
+fn run_worker<F>(factory: F, receiver: Receiver<Job>, status: Arc<RwLock<WorkerStatus>>)
+where
+    F: FnOnce() -> petit_core::Result<Box<dyn Translator>>,
+{
+    let translator = match factory() {
+        Ok(translator) => {
+            set_status(&status, WorkerStatus::Ready);
+            Some(translator)
+        }
+        Err(err) => {
+            set_status(&status, WorkerStatus::Failed(err.to_string()));
+            None
+        }
+    };
+
+    for job in receiver {
+        // Without a translator the job is dropped, which reports the failure to its caller
+        if let Some(translator) = &translator {
+            job(translator.as_ref());
+        }
+    }
+}
+
+fn set_status(status: &RwLock<WorkerStatus>, value: WorkerStatus) {
+    if let Ok(mut status) = status.write() {
+        *status = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petit_core::{Error, MockTranslator};
+
+    #[tokio::test]
+    async fn jobs_run_on_the_translator_thread() {
+        let worker = Worker::spawn(4, || Ok(Box::new(MockTranslator::new())));
+
+        let text = worker
+            .run(|translator| translator.translate("hello", "en", "fr"))
+            .await
+            .unwrap()
+            .unwrap()
+            .text;
+
+        assert_eq!(text, "HELLO");
+        assert_eq!(worker.status(), WorkerStatus::Ready);
+    }
+
+    #[tokio::test]
+    async fn full_queue_rejects_jobs() {
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let worker = Worker::spawn(1, move || {
+            let _ = release_rx.recv();
+            Ok(Box::new(MockTranslator::new()))
+        });
+
+        // The worker is still inside the factory, so this job occupies the only slot
+        worker.sender.try_send(Box::new(|_| {})).unwrap();
+        let rejected = worker.run(|_| ()).await;
+        release_tx.send(()).unwrap();
+
+        assert!(matches!(rejected, Err(ApiError::QueueFull)));
+    }
+
+    #[tokio::test]
+    async fn failed_translator_reports_load_error() {
+        let worker = Worker::spawn(4, || Err(Error::ModelLoad("missing.gguf".to_string())));
+
+        let err = worker.run(|_| ()).await.unwrap_err();
+
+        assert!(matches!(err, ApiError::Unavailable(message) if message.contains("missing.gguf")));
+        assert!(matches!(worker.status(), WorkerStatus::Failed(_)));
+    }
+}
@@ -2,6 +2,8 @@
 
 //! Application state and logic
 
+use petit_core::{CancellationToken, StopReason, TranslationOptions, TranslationOutput};
+
 /// Application state
 pub struct App {
     /// Input text to translate
@@ -16,6 +18,8 @@ pub struct App {
     pub should_quit: bool,
     /// Whether translation is in progress
     pub is_loading: bool,
+    /// Whether the output pane holds partial output from the in-flight translation
+    pub is_streaming_output: bool,
     /// Whether the background translator is initializing
     pub is_worker_initializing: bool,
     /// Which pane is focused
@@ -30,8 +34,12 @@ pub struct App {
     pub status_line: Option<StatusLine>,
     /// Language edit state (if active)
     lang_edit: Option<LangEdit>,
+    /// Cancellation token for the in-flight translation
+    active_cancel: Option<CancellationToken>,
     /// Show compact language display in header
     pub compact_lang_display: bool,
+    /// Options sent with every translation
+    pub options: TranslationOptions,
 }
 
 /// Which pane is currently focused
@@ -64,6 +72,8 @@ pub struct TranslationRequest {
     pub text: String,
     pub source_lang: String,
     pub target_lang: String,
+    pub options: TranslationOptions,
+    pub cancel: CancellationToken,
 }
 
 struct LangEdit {
@@ -81,6 +91,7 @@ impl Default for App {
             target_lang: "fr".to_string(),
             should_quit: false,
             is_loading: false,
+            is_streaming_output: false,
             is_worker_initializing: false,
             focus: Focus::Input,
             input_cursor: 0,
@@ -88,7 +99,9 @@ impl Default for App {
             output_scroll: 0,
             status_line: None,
             lang_edit: None,
+            active_cancel: None,
             compact_lang_display: false,
+            options: TranslationOptions::default(),
         }
     }
 }
@@ -313,23 +326,84 @@ impl App {
             return None;
         }
 
+        let cancel = CancellationToken::new();
         self.is_loading = true;
+        self.is_streaming_output = false;
+        self.active_cancel = Some(cancel.clone());
         self.set_info_status("Translating...");
 
         Some(TranslationRequest {
             text: self.input.clone(),
             source_lang: self.source_lang.clone(),
             target_lang: self.target_lang.clone(),
+            options: self.options.clone(),
+            cancel,
         })
     }
 
-    pub fn apply_translation_result(&mut self, result: Result<String, String>) {
+    /// Request cancellation of the in-flight translation
+    ///
+    /// Returns false when there is nothing to cancel. The app stays loading until the
+    /// worker reports back, so a new request cannot overlap the aborted one.
+    pub fn cancel_translation(&mut self) -> bool {
+        if !self.is_loading {
+            return false;
+        }
+        if let Some(cancel) = &self.active_cancel {
+            cancel.cancel();
+        }
+        self.set_info_status("Cancelling translation...");
+        true
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.active_cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    pub fn apply_translation_cancelled(&mut self) {
+        self.is_loading = false;
+        self.is_streaming_output = false;
+        self.active_cancel = None;
+        self.set_info_status("Translation cancelled");
+    }
+
+    pub fn apply_translation_progress(&mut self, piece: &str) {
+        if !self.is_loading {
+            return;
+        }
+        if !self.is_streaming_output {
+            self.output.clear();
+            self.output_scroll = 0;
+            self.is_streaming_output = true;
+        }
+        if self.output.is_empty() {
+            self.output.push_str(piece.trim_start());
+        } else {
+            self.output.push_str(piece);
+        }
+    }
+
+    pub fn apply_translation_result(&mut self, result: Result<TranslationOutput, String>) {
         self.is_loading = false;
+        self.is_streaming_output = false;
+        self.active_cancel = None;
         match result {
-            Ok(text) => {
-                self.output = text;
+            Ok(output) => {
+                let details = translation_details(&output);
+                self.output = output.text;
                 self.output_scroll = 0;
-                self.set_success_status("Translation complete");
+                match output.stats.stop_reason {
+                    StopReason::EndOfText => {
+                        self.set_success_status(format!("Translation complete{details}"))
+                    }
+                    StopReason::MaxTokens => {
+                        self.set_info_status(format!("Translation stopped at max tokens{details}"))
+                    }
+                    StopReason::ContextFull => self
+                        .set_info_status(format!("Translation stopped at context limit{details}")),
+                }
             }
             Err(err) => {
                 self.set_error_status(err);
@@ -354,6 +428,7 @@ impl App {
 
     pub fn apply_worker_unavailable(&mut self) {
         self.is_loading = false;
+        self.active_cancel = None;
         self.set_error_status("Translation worker unavailable");
     }
 
@@ -392,14 +467,144 @@ impl App {
     }
 }
 
+/// Summarize token usage and timing of a translation for the status line
+fn translation_details(output: &TranslationOutput) -> String {
+    let stats = &output.stats;
+    if stats.generated_tokens == 0 {
+        return String::new();
+    }
+
+    let elapsed = stats.prompt_eval + stats.generation;
+    let mut details = format!(
+        " ({} tokens, {:.2}s",
+        stats.generated_tokens,
+        elapsed.as_secs_f64()
+    );
+    if let Some(rate) = stats.tokens_per_second() {
+        details.push_str(&format!(", {rate:.1} tok/s"));
+    }
+    match output.glossary_candidates.len() {
+        0 => {}
+        1 => details.push_str(", 1 glossary term"),
+        count => details.push_str(&format!(", {count} glossary terms")),
+    }
+    match output.warnings.len() {
+        0 => {}
+        1 => details.push_str(", 1 warning"),
+        count => details.push_str(&format!(", {count} warnings")),
+    }
+    details.push(')');
+    details
+}
+
+fn insert_into(buffer: &mut String, cursor: usize, ch: char) {
+    let mut temp = [0u8; 4];
+    let text = ch.encode_utf8(&mut temp);
+    insert_str_into(buffer, cursor, text);
+}
+
+fn insert_str_into(buffer: &mut String, cursor: usize, text: &str) {
+    let mut chars: Vec<char> = buffer.chars().collect();
+    let cursor = cursor.min(chars.len());
+    chars.splice(cursor..cursor, text.chars());
+    *buffer = chars.into_iter().collect();
+}
+
+fn remove_at(buffer: &mut String, index: usize) {
+    let mut chars: Vec<char> = buffer.chars().collect();
+    if index >= chars.len() {
+        return;
+    }
+    chars.remove(index);
+    *buffer = chars.into_iter().collect();
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (index, ch) in text.chars().enumerate() {
+        if ch == '\n' {
+            starts.push(index + 1);
+        }
+    }
+    starts
+}
+
+fn line_col(text: &str, cursor: usize) -> (usize, usize) {
+    let starts = line_starts(text);
+    let mut line = 0;
+    for idx in 0..starts.len() {
+        if idx + 1 == starts.len() || cursor < starts[idx + 1] {
+            line = idx;
+            break;
+        }
+    }
+    let col = cursor.saturating_sub(starts[line]);
+    (line, col)
+}
+
+fn line_start(text: &str, line: usize) -> Option<usize> {
+    let starts = line_starts(text);
+    starts.get(line).copied()
+}
+
+fn line_end(text: &str, line: usize) -> Option<usize> {
+    let starts = line_starts(text);
+    let total = text.chars().count();
+    if line >= starts.len() {
+        return None;
+    }
+    if line + 1 < starts.len() {
+        Some(starts[line + 1].saturating_sub(1))
+    } else {
+        Some(total)
+    }
+}
+
+fn move_vertical(text: &str, cursor: usize, delta: isize) -> Option<usize> {
+    let starts = line_starts(text);
+    let (line, col) = line_col(text, cursor);
+    let target_line = if delta.is_negative() {
+        let steps = (-delta) as usize;
+        line.checked_sub(steps)?
+    } else {
+        line + delta as usize
+    };
+    if target_line >= starts.len() {
+        return None;
+    }
+    let target_start = starts[target_line];
+    let target_end = line_end(text, target_line)?;
+    let target_len = target_end.saturating_sub(target_start);
+    let new_col = col.min(target_len);
+    Some(target_start + new_col)
+}
+
+fn max_scroll(text: &str) -> u16 {
+    let lines = line_starts(text).len();
+    lines.saturating_sub(1) as u16
+}
+
+fn scroll_value(current: u16, delta: i16, max: u16) -> u16 {
+    let next = if delta.is_negative() {
+        current.saturating_sub((-delta) as u16)
+    } else {
+        current.saturating_add(delta as u16)
+    };
+    next.min(max)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use petit_core::{GenerationStats, GlossaryCandidate};
+    use std::time::Duration;
 
     #[test]
     fn begin_translation_sets_loading_and_info_status() {
-        let mut app = App::default();
-        app.input = "Hello".to_string();
+        let mut app = App {
+            input: "Hello".to_string(),
+            ..App::default()
+        };
 
         let request = app.begin_translation();
 
@@ -431,12 +636,18 @@ mod tests {
         );
     }
 
+    fn output(text: &str) -> TranslationOutput {
+        TranslationOutput::from_text(text, "en", "fr")
+    }
+
     #[test]
     fn apply_translation_result_sets_success_status() {
-        let mut app = App::default();
-        app.is_loading = true;
+        let mut app = App {
+            is_loading: true,
+            ..App::default()
+        };
 
-        app.apply_translation_result(Ok("Bonjour".to_string()));
+        app.apply_translation_result(Ok(output("Bonjour")));
 
         assert!(!app.is_loading);
         assert_eq!(app.output, "Bonjour");
@@ -450,9 +661,135 @@ mod tests {
     }
 
     #[test]
-    fn apply_translation_result_sets_error_status() {
+    fn apply_translation_progress_replaces_previous_output_then_appends() {
+        let mut app = App {
+            output: "Old translation".to_string(),
+            input: "Hello world".to_string(),
+            ..Default::default()
+        };
+        app.begin_translation();
+
+        app.apply_translation_progress(" Bonjour");
+        app.apply_translation_progress(" le");
+        app.apply_translation_progress(" monde");
+
+        assert_eq!(app.output, "Bonjour le monde");
+        assert!(app.is_loading);
+    }
+
+    #[test]
+    fn apply_translation_progress_is_ignored_when_idle() {
+        let mut app = App {
+            output: "Bonjour".to_string(),
+            ..Default::default()
+        };
+
+        app.apply_translation_progress(" stray");
+
+        assert_eq!(app.output, "Bonjour");
+    }
+
+    #[test]
+    fn apply_translation_result_replaces_streamed_output() {
+        let mut app = App {
+            input: "Hello".to_string(),
+            ..Default::default()
+        };
+        app.begin_translation();
+        app.apply_translation_progress("Bonjour<end_of_turn>");
+
+        app.apply_translation_result(Ok(output("Bonjour")));
+
+        assert_eq!(app.output, "Bonjour");
+        assert!(!app.is_streaming_output);
+    }
+
+    #[test]
+    fn cancel_translation_triggers_request_token() {
+        let mut app = App {
+            input: "Hello".to_string(),
+            ..Default::default()
+        };
+        let request = app.begin_translation().expect("request should be created");
+
+        assert!(app.cancel_translation());
+
+        assert!(request.cancel.is_cancelled());
+        assert!(app.is_cancelling());
+        assert!(app.is_loading, "app waits for the worker to acknowledge");
+        assert!(app.begin_translation().is_none());
+    }
+
+    #[test]
+    fn cancel_translation_is_noop_when_idle() {
         let mut app = App::default();
-        app.is_loading = true;
+
+        assert!(!app.cancel_translation());
+        assert_eq!(app.status_line, None);
+    }
+
+    #[test]
+    fn apply_translation_cancelled_frees_app_for_next_request() {
+        let mut app = App {
+            input: "Hello".to_string(),
+            ..Default::default()
+        };
+        app.begin_translation();
+        app.apply_translation_progress("Bonj");
+        app.cancel_translation();
+
+        app.apply_translation_cancelled();
+
+        assert!(!app.is_loading);
+        assert!(!app.is_cancelling());
+        assert_eq!(app.output, "Bonj");
+        assert_eq!(
+            app.status_line,
+            Some(StatusLine {
+                kind: StatusKind::Info,
+                text: "Translation cancelled".to_string(),
+            })
+        );
+        assert!(app.begin_translation().is_some());
+    }
+
+    #[test]
+    fn apply_translation_result_reports_stats_and_truncation() {
+        let mut app = App {
+            is_loading: true,
+            ..Default::default()
+        };
+        let mut result = output("Bonjour");
+        result.stats = GenerationStats {
+            generated_tokens: 40,
+            stop_reason: StopReason::MaxTokens,
+            prompt_eval: Duration::from_millis(250),
+            generation: Duration::from_millis(2000),
+            ..GenerationStats::default()
+        };
+        result.glossary_candidates = vec![GlossaryCandidate {
+            source_term: "hello".to_string(),
+            target_term: "bonjour".to_string(),
+        }];
+
+        app.apply_translation_result(Ok(result));
+
+        assert_eq!(
+            app.status_line,
+            Some(StatusLine {
+                kind: StatusKind::Info,
+                text: "Translation stopped at max tokens (40 tokens, 2.25s, 20.0 tok/s, 1 glossary term)"
+                    .to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn apply_translation_result_sets_error_status() {
+        let mut app = App {
+            is_loading: true,
+            ..App::default()
+        };
 
         app.apply_translation_result(Err("Failed to load model".to_string()));
 
@@ -504,8 +841,10 @@ mod tests {
 
     #[test]
     fn worker_unavailable_sets_error_and_clears_loading() {
-        let mut app = App::default();
-        app.is_loading = true;
+        let mut app = App {
+            is_loading: true,
+            ..App::default()
+        };
 
         app.apply_worker_unavailable();
 
@@ -521,12 +860,14 @@ mod tests {
 
     #[test]
     fn invalid_language_edit_sets_error_status() {
-        let mut app = App::default();
-        app.lang_edit = Some(LangEdit {
-            target: LangTarget::Source,
-            buffer: "xx".to_string(),
-            cursor: 2,
-        });
+        let mut app = App {
+            lang_edit: Some(LangEdit {
+                target: LangTarget::Source,
+                buffer: "xx".to_string(),
+                cursor: 2,
+            }),
+            ..App::default()
+        };
 
         app.submit_language_edit();
 
@@ -582,99 +923,3 @@ mod tests {
         assert_eq!(status.text, "Cannot swap when source is auto");
     }
 }
-
-fn insert_into(buffer: &mut String, cursor: usize, ch: char) {
-    let mut temp = [0u8; 4];
-    let text = ch.encode_utf8(&mut temp);
-    insert_str_into(buffer, cursor, text);
-}
-
-fn insert_str_into(buffer: &mut String, cursor: usize, text: &str) {
-    let mut chars: Vec<char> = buffer.chars().collect();
-    let cursor = cursor.min(chars.len());
-    chars.splice(cursor..cursor, text.chars());
-    *buffer = chars.into_iter().collect();
-}
-
-fn remove_at(buffer: &mut String, index: usize) {
-    let mut chars: Vec<char> = buffer.chars().collect();
-    if index >= chars.len() {
-        return;
-    }
-    chars.remove(index);
-    *buffer = chars.into_iter().collect();
-}
-
-fn line_starts(text: &str) -> Vec<usize> {
-    let mut starts = vec![0];
-    for (index, ch) in text.chars().enumerate() {
-        if ch == '\n' {
-            starts.push(index + 1);
-        }
-    }
-    starts
-}
-
-fn line_col(text: &str, cursor: usize) -> (usize, usize) {
-    let starts = line_starts(text);
-    let mut line = 0;
-    for idx in 0..starts.len() {
-        if idx + 1 == starts.len() || cursor < starts[idx + 1] {
-            line = idx;
-            break;
-        }
-    }
-    let col = cursor.saturating_sub(starts[line]);
-    (line, col)
-}
-
-fn line_start(text: &str, line: usize) -> Option<usize> {
-    let starts = line_starts(text);
-    starts.get(line).copied()
-}
-
-fn line_end(text: &str, line: usize) -> Option<usize> {
-    let starts = line_starts(text);
-    let total = text.chars().count();
-    if line >= starts.len() {
-        return None;
-    }
-    if line + 1 < starts.len() {
-        Some(starts[line + 1].saturating_sub(1))
-    } else {
-        Some(total)
-    }
-}
-
-fn move_vertical(text: &str, cursor: usize, delta: isize) -> Option<usize> {
-    let starts = line_starts(text);
-    let (line, col) = line_col(text, cursor);
-    let target_line = if delta.is_negative() {
-        let steps = (-delta) as usize;
-        line.checked_sub(steps)?
-    } else {
-        line + delta as usize
-    };
-    if target_line >= starts.len() {
-        return None;
-    }
-    let target_start = starts[target_line];
-    let target_end = line_end(text, target_line)?;
-    let target_len = target_end.saturating_sub(target_start);
-    let new_col = col.min(target_len);
-    Some(target_start + new_col)
-}
-
-fn max_scroll(text: &str) -> u16 {
-    let lines = line_starts(text).len();
-    lines.saturating_sub(1) as u16
-}
-
-fn scroll_value(current: u16, delta: i16, max: u16) -> u16 {
-    let next = if delta.is_negative() {
-        current.saturating_sub((-delta) as u16)
-    } else {
-        current.saturating_add(delta as u16)
-    };
-    next.min(max)
-}
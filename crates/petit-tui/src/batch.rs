@@ -0,0 +1,868 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! `petit batch`: translate a file of segments
+//!
+//! Formats:
+//! - `tsv`: rows shaped like `eval/fixtures`: `case_id<TAB>source_lang<TAB>target_lang<TAB>
+//!   input_text` (extra columns ignored), `case_id<TAB>input_text`, or just `input_text`.
+//!   A header row naming `input_text` (or `text`) maps columns by name instead. `#` lines
+//!   and blank lines are skipped. Output rows are `case_id, source_lang, target_lang,
+//!   input_text, translation`. Tabs, newlines and backslashes in cells are backslash-escaped.
+//! - `jsonl`: one object per line with `text` and optional `id`, `source_lang` and
+//!   `target_lang`; the output repeats the object with `translation` added.
+//! - `text`: one segment per line; blank lines are copied through.
+//!
+//! Empty or missing languages fall back to the configured pair. Rows without an id are
+//! numbered from 1, and TSV and JSONL rows with blank text get an empty translation. When the output file already exists the run resumes: TSV and JSONL skip
+//! the ids already written and plain text skips the lines already written. Failed rows are
+//! left out of TSV and JSONL output, so a rerun retries them. Plain text output is matched by
+//! line position, so it stops at the first failed line and a rerun resumes from there.
+//!
+//! Records are translated in groups through `Translator::translate_batch`, and each group is
+//! written and flushed in input order before the next one starts.
+
+use anyhow::{Context, Result, anyhow, bail};
+use petit_core::{TranslationOptions, TranslationRequest, Translator};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+
+use crate::cli::CliArgs;
+use crate::config::AppConfig;
+use crate::create_translator;
+
+/// Records sent to `Translator::translate_batch` at a time
+const GROUP_SIZE: usize = 16;
+
+/// Input and output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    Tsv,
+    Jsonl,
+    Text,
+}
+
+impl std::str::FromStr for BatchFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "tsv" => Ok(BatchFormat::Tsv),
+            "jsonl" | "ndjson" => Ok(BatchFormat::Jsonl),
+            "text" | "txt" => Ok(BatchFormat::Text),
+            other => Err(anyhow!(
+                "Unknown batch format: {other} (expected `tsv`, `jsonl` or `text`)"
+            )),
+        }
+    }
+}
+
+impl BatchFormat {
+    /// Guess the format from a file extension, defaulting to plain text
+    fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .unwrap_or(BatchFormat::Text)
+    }
+}
+
+/// One segment to translate
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    id: String,
+    source_lang: String,
+    target_lang: String,
+    text: String,
+    /// The JSONL object the record came from
+    object: Option<Map<String, Value>>,
+}
+
+/// Failed record and its error
+struct Failure {
+    id: String,
+    error: String,
+}
+
+/// Translate `--input` (or stdin) into `--output` (or stdout)
+pub fn run_batch(config: AppConfig, cli: &CliArgs) -> Result<()> {
+    let format = cli
+        .batch_format
+        .or_else(|| cli.input.as_deref().map(BatchFormat::from_path))
+        .unwrap_or(BatchFormat::Text);
+    let input = match &cli.input {
+        Some(path) => {
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?
+        }
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+    let records = parse_records(format, &input, &config.source_lang, &config.target_lang)?;
+
+    let (done, mut output): (Done, Box<dyn Write>) = match &cli.output {
+        Some(path) => {
+            let done = prepare_output(format, path)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("cannot open {}", path.display()))?;
+            (done, Box::new(file))
+        }
+        None => (Done::default(), Box::new(io::stdout().lock())),
+    };
+
+    let options = config.translation_options();
+    let translator = create_translator(config.backend, config.http, config.core)?;
+    let mut progress = Progress::new(io::stderr().is_terminal());
+    let summary = translate_records(
+        translator.as_ref(),
+        &options,
+        format,
+        &records,
+        &done,
+        &mut output,
+        &mut progress,
+    )?;
+    progress.finish();
+
+    eprintln!(
+        "Translated {} of {} records ({} already done, {} failed)",
+        summary.translated,
+        records.len(),
+        summary.skipped,
+        summary.failures.len()
+    );
+    for failure in &summary.failures {
+        eprintln!("  {}: {}", failure.id, failure.error);
+    }
+    if !summary.failures.is_empty() {
+        bail!(
+            "{} records failed; rerun to retry them",
+            summary.failures.len()
+        );
+    }
+    Ok(())
+}
+
+fn parse_records(
+    format: BatchFormat,
+    input: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<Vec<Record>> {
+    let mut records = match format {
+        BatchFormat::Tsv => parse_tsv(input)?,
+        BatchFormat::Jsonl => parse_jsonl(input)?,
+        BatchFormat::Text => input
+            .lines()
+            .map(|line| Record {
+                id: String::new(),
+                source_lang: String::new(),
+                target_lang: String::new(),
+                text: line.to_string(),
+                object: None,
+            })
+            .collect(),
+    };
+
+    let mut ids = HashSet::new();
+    for (index, record) in records.iter_mut().enumerate() {
+        if record.id.is_empty() {
+            record.id = (index + 1).to_string();
+        }
+        if record.source_lang.is_empty() {
+            record.source_lang = source_lang.to_string();
+        }
+        if record.target_lang.is_empty() {
+            record.target_lang = target_lang.to_string();
+        }
+        // Resuming matches rows by id, so ids must be unique
+        if format != BatchFormat::Text && !ids.insert(record.id.clone()) {
+            bail!("duplicate record id: {}", record.id);
+        }
+    }
+    Ok(records)
+}
+
+fn parse_tsv(input: &str) -> Result<Vec<Record>> {
+    let mut rows = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            (
+                index + 1,
+                line.split('\t').map(unescape).collect::<Vec<_>>(),
+            )
+        })
+        .peekable();
+
+    let header = rows.peek().and_then(|(_, cells)| {
+        let names: Vec<String> = cells
+            .iter()
+            .map(|cell| cell.trim().to_lowercase())
+            .collect();
+        let column = |wanted: &[&str]| {
+            names
+                .iter()
+                .position(|name| wanted.contains(&name.as_str()))
+        };
+        column(&["input_text", "text"]).map(|text| TsvColumns {
+            id: column(&["case_id", "id"]),
+            source_lang: column(&["source_lang"]),
+            target_lang: column(&["target_lang"]),
+            text,
+        })
+    });
+    if header.is_some() {
+        rows.next();
+    }
+
+    rows.map(|(line_number, cells)| {
+        let columns = match header {
+            Some(columns) => columns,
+            None => TsvColumns::by_count(cells.len())
+                .ok_or_else(|| anyhow!("line {line_number}: expected 1, 2 or 4 columns, got 3"))?,
+        };
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| cells.get(index))
+                .map(|cell| cell.trim().to_string())
+                .unwrap_or_default()
+        };
+        Ok(Record {
+            id: cell(columns.id),
+            source_lang: cell(columns.source_lang),
+            target_lang: cell(columns.target_lang),
+            text: cells.get(columns.text).cloned().unwrap_or_default(),
+            object: None,
+        })
+    })
+    .collect()
+}
+
+/// Column positions in a TSV input
+#[derive(Clone, Copy)]
+struct TsvColumns {
+    id: Option<usize>,
+    source_lang: Option<usize>,
+    target_lang: Option<usize>,
+    text: usize,
+}
+
+impl TsvColumns {
+    /// Layout of a row without a header, following `eval/fixtures`
+    fn by_count(count: usize) -> Option<Self> {
+        match count {
+            1 => Some(Self {
+                id: None,
+                source_lang: None,
+                target_lang: None,
+                text: 0,
+            }),
+            2 => Some(Self {
+                id: Some(0),
+                source_lang: None,
+                target_lang: None,
+                text: 1,
+            }),
+            3 => None,
+            _ => Some(Self {
+                id: Some(0),
+                source_lang: Some(1),
+                target_lang: Some(2),
+                text: 3,
+            }),
+        }
+    }
+}
+
+fn parse_jsonl(input: &str) -> Result<Vec<Record>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let object = match serde_json::from_str(line) {
+                Ok(Value::Object(object)) => object,
+                Ok(_) => bail!("line {line_number}: expected a JSON object"),
+                Err(err) => bail!("line {line_number}: {err}"),
+            };
+            let string = |key: &str| object.get(key).and_then(Value::as_str).unwrap_or_default();
+            let text = match object.get("text").or_else(|| object.get("input_text")) {
+                Some(Value::String(text)) => text.clone(),
+                _ => bail!("line {line_number}: missing string field `text`"),
+            };
+            Ok(Record {
+                id: object
+                    .get("id")
+                    .or_else(|| object.get("case_id"))
+                    .map(id_string)
+                    .unwrap_or_default(),
+                source_lang: string("source_lang").trim().to_string(),
+                target_lang: string("target_lang").trim().to_string(),
+                text,
+                object: Some(object),
+            })
+        })
+        .collect()
+}
+
+/// Rows already present in the output of an interrupted run
+#[derive(Debug, Default)]
+struct Done {
+    ids: HashSet<String>,
+    lines: usize,
+}
+
+impl Done {
+    fn contains(&self, format: BatchFormat, index: usize, record: &Record) -> bool {
+        match format {
+            BatchFormat::Text => index < self.lines,
+            BatchFormat::Tsv | BatchFormat::Jsonl => self.ids.contains(&record.id),
+        }
+    }
+}
+
+/// Read what an earlier run wrote to `path` and drop a half-written last line
+fn prepare_output(format: BatchFormat, path: &Path) -> Result<Done> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Done::default()),
+        Err(err) => return Err(err).with_context(|| format!("cannot read {}", path.display())),
+    };
+    let complete = &existing[..existing.rfind('\n').map_or(0, |end| end + 1)];
+    if complete.len() != existing.len() {
+        let file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(complete.len() as u64)?;
+    }
+    Ok(completed_rows(format, complete))
+}
+
+fn completed_rows(format: BatchFormat, output: &str) -> Done {
+    let lines = output.lines();
+    match format {
+        BatchFormat::Text => Done {
+            ids: HashSet::new(),
+            lines: lines.count(),
+        },
+        BatchFormat::Tsv => Done {
+            ids: lines
+                .filter_map(|line| line.split('\t').next())
+                .map(unescape)
+                .collect(),
+            lines: 0,
+        },
+        BatchFormat::Jsonl => Done {
+            ids: lines
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .filter_map(|value| value.get("id").map(id_string))
+                .collect(),
+            lines: 0,
+        },
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    translated: usize,
+    skipped: usize,
+    failures: Vec<Failure>,
+}
+
+fn translate_records(
+    translator: &dyn Translator,
+    options: &TranslationOptions,
+    format: BatchFormat,
+    records: &[Record],
+    done: &Done,
+    output: &mut dyn Write,
+    progress: &mut Progress,
+) -> Result<Summary> {
+    let mut summary = Summary::default();
+    let mut pending = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        if done.contains(format, index, record) {
+            summary.skipped += 1;
+        } else {
+            pending.push((index, record));
+        }
+    }
+
+    for group in pending.chunks(GROUP_SIZE) {
+        let requests: Vec<TranslationRequest> = group
+            .iter()
+            .filter(|(_, record)| !record.text.trim().is_empty())
+            .map(|(_, record)| {
+                TranslationRequest::new(&record.text, &record.source_lang, &record.target_lang)
+                    .with_options(options.clone())
+            })
+            .collect();
+        if let Some((index, record)) = group
+            .iter()
+            .find(|(_, record)| !record.text.trim().is_empty())
+        {
+            progress.update(index + 1, records.len(), &record.id);
+        }
+        let mut results = translator.translate_batch(&requests).into_iter();
+
+        for (_, record) in group {
+            if record.text.trim().is_empty() {
+                if format == BatchFormat::Text {
+                    // Blank lines of a text file are layout, not segments
+                    writeln!(output, "{}", record.text)?;
+                } else {
+                    // Written as a row so resume finds its id
+                    writeln!(output, "{}", format_row(format, record, ""))?;
+                    summary.translated += 1;
+                }
+                continue;
+            }
+            let result = results
+                .next()
+                .ok_or_else(|| anyhow!("translator returned too few results"))?;
+            match result {
+                Ok(translated) => {
+                    writeln!(output, "{}", format_row(format, record, &translated.text))?;
+                    summary.translated += 1;
+                }
+                Err(err) => {
+                    progress.fail(&record.id, &err.to_string());
+                    summary.failures.push(Failure {
+                        id: record.id.clone(),
+                        error: err.to_string(),
+                    });
+                    // A line written for the failure would count as done on resume
+                    if format == BatchFormat::Text {
+                        output.flush()?;
+                        return Ok(summary);
+                    }
+                }
+            }
+        }
+        // Flushed per group so an interruption loses at most the group in flight
+        output.flush()?;
+    }
+    Ok(summary)
+}
+
+fn format_row(format: BatchFormat, record: &Record, translation: &str) -> String {
+    match format {
+        BatchFormat::Text => translation.replace('\n', " "),
+        BatchFormat::Tsv => [
+            record.id.as_str(),
+            record.source_lang.as_str(),
+            record.target_lang.as_str(),
+            record.text.as_str(),
+            translation,
+        ]
+        .map(escape)
+        .join("\t"),
+        BatchFormat::Jsonl => {
+            let mut object = record.object.clone().unwrap_or_default();
+            object
+                .entry("id")
+                .or_insert_with(|| Value::String(record.id.clone()));
+            object.insert(
+                "source_lang".to_string(),
+                Value::String(record.source_lang.clone()),
+            );
+            object.insert(
+                "target_lang".to_string(),
+                Value::String(record.target_lang.clone()),
+            );
+            object.insert(
+                "translation".to_string(),
+                Value::String(translation.to_string()),
+            );
+            Value::Object(object).to_string()
+        }
+    }
+}
+
+/// Progress line on stderr, redrawn in place on a terminal
+struct Progress {
+    redraw: bool,
+    drawn: bool,
+}
+
+impl Progress {
+    fn new(redraw: bool) -> Self {
+        Self {
+            redraw,
+            drawn: false,
+        }
+    }
+
+    fn update(&mut self, position: usize, total: usize, id: &str) {
+        if self.redraw {
+            eprint!("\r\x1b[K[{position}/{total}] {id}");
+            self.drawn = true;
+        } else {
+            eprintln!("[{position}/{total}] {id}");
+        }
+    }
+
+    fn fail(&mut self, id: &str, error: &str) {
+        self.finish();
+        eprintln!("{id} failed: {error}");
+    }
+
+    fn finish(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+}
+
+fn id_string(value: &Value) -> String {
+    match value {
+        Value::String(id) => id.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn escape(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(cell: &str) -> String {
+    let mut out = String::with_capacity(cell.len());
+    let mut chars = cell.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petit_core::TranslationOutput;
+
+    fn record(id: &str, source_lang: &str, target_lang: &str, text: &str) -> Record {
+        Record {
+            id: id.to_string(),
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+            text: text.to_string(),
+            object: None,
+        }
+    }
+
+    #[test]
+    fn tsv_follows_the_eval_fixture_schema() {
+        let input = "# case_id\tsource_lang\ttarget_lang\tinput_text\n\n\
+            hello-en-fr\ten\tfr\tHello.\texpected\n\
+            thanks\tThank you.\n\
+            Good night.\n\
+            blank-langs\t\t\tTwo\\tcells\n";
+
+        let records = parse_records(BatchFormat::Tsv, input, "auto", "de").unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                record("hello-en-fr", "en", "fr", "Hello."),
+                record("thanks", "auto", "de", "Thank you."),
+                record("3", "auto", "de", "Good night."),
+                record("blank-langs", "auto", "de", "Two\tcells"),
+            ]
+        );
+        assert!(parse_records(BatchFormat::Tsv, "a\tb\tc\n", "auto", "de").is_err());
+        assert!(parse_records(BatchFormat::Tsv, "a\tx\nb\ty\na\tz\n", "auto", "de").is_err());
+    }
+
+    #[test]
+    fn tsv_header_maps_columns_by_name() {
+        let input = "text\ttarget_lang\tid\nHello\tes\tgreeting\n";
+
+        let records = parse_records(BatchFormat::Tsv, input, "en", "fr").unwrap();
+
+        assert_eq!(records, vec![record("greeting", "en", "es", "Hello")]);
+    }
+
+    #[test]
+    fn jsonl_keeps_fields_and_adds_the_translation() {
+        let input = "{\"id\":7,\"text\":\"Hello\",\"note\":\"x\"}\n{\"text\":\"Bye\",\"target_lang\":\"it\"}\n";
+
+        let records = parse_records(BatchFormat::Jsonl, input, "en", "fr").unwrap();
+        let row: Value =
+            serde_json::from_str(&format_row(BatchFormat::Jsonl, &records[0], "Bonjour")).unwrap();
+
+        assert_eq!(records[0].id, "7");
+        assert_eq!(records[1].id, "2");
+        assert_eq!(records[1].target_lang, "it");
+        assert_eq!(row["id"], 7);
+        assert_eq!(row["note"], "x");
+        assert_eq!(row["target_lang"], "fr");
+        assert_eq!(row["translation"], "Bonjour");
+        assert!(parse_records(BatchFormat::Jsonl, "[1]\n", "en", "fr").is_err());
+    }
+
+    #[test]
+    fn completed_rows_are_found_by_id_or_line() {
+        let tsv = completed_rows(
+            BatchFormat::Tsv,
+            "a\ten\tfr\tHi\tSalut\nb\\tc\ten\tfr\tx\ty\n",
+        );
+        let jsonl = completed_rows(BatchFormat::Jsonl, "{\"id\":3,\"translation\":\"x\"}\n");
+        let text = completed_rows(BatchFormat::Text, "one\n\nthree\n");
+
+        assert!(tsv.ids.contains("a") && tsv.ids.contains("b\tc"));
+        assert!(jsonl.ids.contains("3"));
+        assert_eq!(text.lines, 3);
+    }
+
+    #[test]
+    fn prepare_output_drops_a_half_written_row() {
+        let path = std::env::temp_dir().join(format!("petit-batch-{}.tsv", std::process::id()));
+        fs::write(&path, "a\ten\tfr\tHi\tSalut\nb\ten\tfr\tBy").unwrap();
+
+        let done = prepare_output(BatchFormat::Tsv, &path).unwrap();
+        let remaining = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(done.ids, HashSet::from(["a".to_string()]));
+        assert_eq!(remaining, "a\ten\tfr\tHi\tSalut\n");
+    }
+
+    #[test]
+    fn translate_records_skips_done_rows_and_collects_failures() {
+        use petit_core::MockTranslator;
+
+        let translator = MockTranslator::new().with_response("Good morning", "Bonjour");
+        let records = parse_records(
+            BatchFormat::Tsv,
+            "a\ten\tfr\tGood morning\nb\ten\tfr\tdone before\nc\ten\txx\tbad pair\nd\ten\tde\thi\n",
+            "auto",
+            "fr",
+        )
+        .unwrap();
+        let done = Done {
+            ids: HashSet::from(["b".to_string()]),
+            lines: 0,
+        };
+        let mut output = Vec::new();
+
+        let summary = translate_records(
+            &translator,
+            &TranslationOptions::default(),
+            BatchFormat::Tsv,
+            &records,
+            &done,
+            &mut output,
+            &mut Progress::new(false),
+        )
+        .unwrap();
+
+        assert_eq!(summary.translated, 2);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].id, "c");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a\ten\tfr\tGood morning\tBonjour\nd\ten\tde\thi\tHI\n"
+        );
+    }
+
+    #[test]
+    fn text_output_stays_line_aligned() {
+        use petit_core::MockTranslator;
+
+        let translator = MockTranslator::new();
+        let records = parse_records(BatchFormat::Text, "one\n\ntwo\nthree\n", "en", "fr").unwrap();
+        let done = Done {
+            ids: HashSet::new(),
+            lines: 1,
+        };
+        let mut output = Vec::new();
+
+        translate_records(
+            &translator,
+            &TranslationOptions::default(),
+            BatchFormat::Text,
+            &records,
+            &done,
+            &mut output,
+            &mut Progress::new(false),
+        )
+        .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "\nTWO\nTHREE\n");
+    }
+
+    #[test]
+    fn text_resume_retries_the_failed_line() {
+        use petit_core::MockTranslator;
+
+        let records = parse_records(BatchFormat::Text, "one\ntwo\nthree\n", "en", "fr").unwrap();
+        let mut output = Vec::new();
+
+        let failing = MockTranslator::new().with_error("two", "scripted failure");
+        let summary = translate_records(
+            &failing,
+            &TranslationOptions::default(),
+            BatchFormat::Text,
+            &records,
+            &Done::default(),
+            &mut output,
+            &mut Progress::new(false),
+        )
+        .unwrap();
+        assert_eq!(summary.translated, 1);
+        assert_eq!(summary.failures[0].id, "2");
+
+        let done = completed_rows(BatchFormat::Text, std::str::from_utf8(&output).unwrap());
+        let summary = translate_records(
+            &MockTranslator::new(),
+            &TranslationOptions::default(),
+            BatchFormat::Text,
+            &records,
+            &done,
+            &mut output,
+            &mut Progress::new(false),
+        )
+        .unwrap();
+
+        assert_eq!(summary.skipped, 1);
+        assert!(summary.failures.is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "ONE\nTWO\nTHREE\n");
+    }
+
+    /// Upper-cases its input and records the size of every batch
+    #[derive(Default)]
+    struct BatchRecorder {
+        batches: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl Translator for BatchRecorder {
+        fn translate_with_options(
+            &self,
+            text: &str,
+            source_lang: &str,
+            target_lang: &str,
+            _options: &TranslationOptions,
+        ) -> petit_core::Result<TranslationOutput> {
+            Ok(TranslationOutput::from_text(
+                text.to_uppercase(),
+                source_lang,
+                target_lang,
+            ))
+        }
+
+        fn translate_batch(
+            &self,
+            requests: &[TranslationRequest],
+        ) -> Vec<petit_core::Result<TranslationOutput>> {
+            self.batches.lock().unwrap().push(requests.len());
+            requests
+                .iter()
+                .map(|request| {
+                    self.translate_with_options(
+                        &request.text,
+                        &request.source_lang,
+                        &request.target_lang,
+                        &request.options,
+                    )
+                })
+                .collect()
+        }
+
+        fn supported_languages(&self) -> &[&str] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn records_are_translated_in_batches_and_written_in_order() {
+        let input: String = (1..=40)
+            .map(|line| {
+                if line == 2 {
+                    "\n".to_string()
+                } else {
+                    format!("line {line}\n")
+                }
+            })
+            .collect();
+        let records = parse_records(BatchFormat::Text, &input, "en", "fr").unwrap();
+        let translator = BatchRecorder::default();
+        let mut output = Vec::new();
+
+        let summary = translate_records(
+            &translator,
+            &TranslationOptions::default(),
+            BatchFormat::Text,
+            &records,
+            &Done::default(),
+            &mut output,
+            &mut Progress::new(false),
+        )
+        .unwrap();
+
+        assert_eq!(summary.translated, 39);
+        assert_eq!(*translator.batches.lock().unwrap(), [15, 16, 8]);
+        assert_eq!(String::from_utf8(output).unwrap(), input.to_uppercase());
+    }
+
+    #[test]
+    fn blank_tsv_and_jsonl_records_are_written_as_rows() {
+        use petit_core::MockTranslator;
+
+        for (format, input, expected) in [
+            (
+                BatchFormat::Tsv,
+                "a\t \nb\tHi\n",
+                "a\tauto\tfr\t \t\nb\tauto\tfr\tHi\tHI\n",
+            ),
+            (
+                BatchFormat::Jsonl,
+                "{\"id\":\"a\",\"text\":\"\"}\n",
+                "{\"id\":\"a\",\"source_lang\":\"auto\",\"target_lang\":\"fr\",\"text\":\"\",\"translation\":\"\"}\n",
+            ),
+        ] {
+            let records = parse_records(format, input, "auto", "fr").unwrap();
+            let mut output = Vec::new();
+            translate_records(
+                &MockTranslator::new(),
+                &TranslationOptions::default(),
+                format,
+                &records,
+                &Done::default(),
+                &mut output,
+                &mut Progress::new(false),
+            )
+            .unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(output, expected);
+
+            let done = completed_rows(format, &output);
+            assert!(done.ids.contains("a"), "{output}");
+        }
+    }
+}
@@ -3,10 +3,415 @@
 //! CLI argument parsing for petit-tui.
 
 use anyhow::{Result, anyhow};
+use std::iter::Peekable;
 use std::path::PathBuf;
 
+use petit_core::{DocumentFormat, PlaceholderMode};
+use petit_server::ServerApi;
+
+use crate::batch::BatchFormat;
+use crate::config::Backend;
+
+/// Mode chosen by a subcommand, or by a mode flag on a bare `petit`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Command {
+    /// Bare `petit`: the TUI, or stdin translation when input is piped
+    #[default]
+    Default,
+    Tui,
+    Translate,
+    Batch,
+    Bench,
+    Glossary(GlossaryAction),
+    Config(ConfigAction),
+    Serve,
+    Daemon,
+    Rpc,
+    Mcp,
+}
+
+/// `petit glossary` actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlossaryAction {
+    /// Load the glossary and its embedding model, then report the term count
+    Check,
+    /// Print the glossary terms that apply to a text
+    Lookup,
+}
+
+/// `petit config` actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigAction {
+    /// Print the effective configuration as TOML
+    Show,
+    /// Print the config files that are read
+    Path,
+}
+
+const CONFIG_FLAGS: &[&str] = &["--config", "--no-config", "--help", "-h"];
+const MODEL_FLAGS: &[&str] = &[
+    "--backend",
+    "--backend-url",
+    "--model",
+    "--gpu-layers",
+    "--context-size",
+    "--threads",
+];
+const LANGUAGE_FLAGS: &[&str] = &["--source-lang", "--src", "--target-lang", "--tgt"];
+const GLOSSARY_FLAGS: &[&str] = &[
+    "--glossary",
+    "--no-glossary",
+    "--glossary-path",
+    "--glossary-embedding-model-dir",
+    "--glossary-max-matches",
+];
+const SERVER_FLAGS: &[&str] = &["--bind", "--server-api", "--deepl", "--queue-size"];
+const TRANSLATE_FLAGS: &[&str] = &[
+    "--json",
+    "--socket",
+    "--no-daemon",
+    "--input",
+    "--output",
+    "--format",
+    "--placeholders",
+];
+const BATCH_FLAGS: &[&str] = &["--input", "--output", "--format", "--placeholders"];
+const BENCH_FLAGS: &[&str] = &[
+    "--text",
+    "--warmup-runs",
+    "--runs",
+    "--max-new-tokens",
+    "--stdin",
+];
+const DAEMON_FLAGS: &[&str] = &["--socket", "--queue-size"];
+/// Flags only a bare `petit` takes
+const TOP_LEVEL_FLAGS: &[&str] = &[
+    "--stdin",
+    "--json",
+    "--benchmark",
+    "--serve",
+    "--rpc",
+    "--mcp",
+    "--version",
+    "-V",
+];
+
+fn is_known_flag(flag: &str) -> bool {
+    [
+        CONFIG_FLAGS,
+        MODEL_FLAGS,
+        LANGUAGE_FLAGS,
+        GLOSSARY_FLAGS,
+        SERVER_FLAGS,
+        TRANSLATE_FLAGS,
+        BATCH_FLAGS,
+        BENCH_FLAGS,
+        DAEMON_FLAGS,
+        TOP_LEVEL_FLAGS,
+    ]
+    .iter()
+    .any(|group| group.contains(&flag))
+}
+
+macro_rules! config_options {
+    () => {
+        concat!(
+            "  --config <path>        Config file path\n",
+            "  --no-config            Ignore config file\n",
+            "  --help, -h             Print help\n",
+        )
+    };
+}
+
+macro_rules! model_options {
+    () => {
+        concat!(
+            "  --backend <name>       Translation backend: gemma (default), http or mock\n",
+            "  --backend-url <url>    Base URL of the server used by the http backend\n",
+            "  --model <path>         Path to GGUF model\n",
+            "  --gpu-layers <n>       GPU layers to offload\n",
+            "  --context-size <n>     Context window size\n",
+            "  --threads <n>          CPU threads for inference\n",
+        )
+    };
+}
+
+macro_rules! language_options {
+    () => {
+        concat!(
+            "  --source-lang <code>   Source language (e.g. en)\n",
+            "  --src <code>           Alias for --source-lang\n",
+            "  --target-lang <code>   Target language (e.g. fr)\n",
+            "  --tgt <code>           Alias for --target-lang\n",
+        )
+    };
+}
+
+macro_rules! glossary_options {
+    () => {
+        concat!(
+            "  --glossary             Enable glossary-constrained translation\n",
+            "  --no-glossary          Disable glossary-constrained translation\n",
+            "  --glossary-path <path> Path to glossary TSV file\n",
+            "  --glossary-embedding-model-dir <path> Glossary embedding model directory\n",
+            "  --glossary-max-matches <n> Max glossary candidates to inject\n",
+        )
+    };
+}
+
+macro_rules! server_options {
+    () => {
+        concat!(
+            "  --bind <addr>          Server listen address (default 127.0.0.1:8000)\n",
+            "  --server-api <name>    Server API: native (default) or libretranslate\n",
+            "  --deepl                Also serve the DeepL-compatible /v2 endpoints\n",
+            "  --queue-size <n>       Requests that may wait for the translator\n",
+        )
+    };
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "tui" => Command::Tui,
+            "translate" => Command::Translate,
+            "batch" => Command::Batch,
+            "bench" => Command::Bench,
+            "glossary" => Command::Glossary(GlossaryAction::Check),
+            "config" => Command::Config(ConfigAction::Show),
+            "serve" => Command::Serve,
+            "daemon" => Command::Daemon,
+            "rpc" => Command::Rpc,
+            "mcp" => Command::Mcp,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Default => "petit",
+            Command::Tui => "petit tui",
+            Command::Translate => "petit translate",
+            Command::Batch => "petit batch",
+            Command::Bench => "petit bench",
+            Command::Glossary(_) => "petit glossary",
+            Command::Config(_) => "petit config",
+            Command::Serve => "petit serve",
+            Command::Daemon => "petit daemon",
+            Command::Rpc => "petit rpc",
+            Command::Mcp => "petit mcp",
+        }
+    }
+
+    /// Whether a subcommand takes `flag`; a bare `petit` takes the historical flat list
+    fn accepts(self, flag: &str) -> bool {
+        let groups: &[&[&str]] = match self {
+            Command::Default => return !BATCH_FLAGS.contains(&flag),
+            Command::Tui => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                &["--placeholders"],
+            ],
+            Command::Rpc | Command::Mcp => {
+                &[CONFIG_FLAGS, MODEL_FLAGS, LANGUAGE_FLAGS, GLOSSARY_FLAGS]
+            }
+            Command::Translate => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                TRANSLATE_FLAGS,
+            ],
+            Command::Batch => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                BATCH_FLAGS,
+            ],
+            Command::Bench => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                BENCH_FLAGS,
+            ],
+            Command::Glossary(_) => &[CONFIG_FLAGS, LANGUAGE_FLAGS, GLOSSARY_FLAGS],
+            Command::Config(_) => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                SERVER_FLAGS,
+                &["--socket"],
+            ],
+            Command::Serve => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                SERVER_FLAGS,
+            ],
+            Command::Daemon => &[
+                CONFIG_FLAGS,
+                MODEL_FLAGS,
+                LANGUAGE_FLAGS,
+                GLOSSARY_FLAGS,
+                DAEMON_FLAGS,
+            ],
+        };
+        groups.iter().any(|group| group.contains(&flag))
+    }
+
+    /// Help text printed for `--help`
+    pub fn usage(self) -> &'static str {
+        match self {
+            Command::Default => CliArgs::usage(),
+            Command::Tui => concat!(
+                "petit tui - Open the interactive TUI\n\n",
+                "Usage:\n",
+                "  petit tui [options]\n\n",
+                "Options:\n",
+                "  --placeholders <mode>  Protect placeholders and markup: off, warn or strict\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Translate => concat!(
+                "petit translate - Translate text and exit\n\n",
+                "Usage:\n",
+                "  petit translate [options] [text...]\n\n",
+                "Translates the arguments, or stdin when no text is given.\n",
+                "Markdown keeps code, links, HTML and front matter and translates the prose.\n",
+                "Subtitles keep cue numbers, timings and styling tags and translate the cue text.\n",
+                "PO/POT catalogs get their untranslated entries filled in and marked fuzzy,\n",
+                "and XLIFF files their missing targets, marked for review. JSON and YAML resources\n",
+                "translate string values only; keys already in --output are kept.\n\n",
+                "Options:\n",
+                "  --input <path>         File to translate instead of the arguments or stdin\n",
+                "  --output <path>        File to write (default stdout)\n",
+                "  --format <name>        text, markdown, srt, vtt, po, xliff, json or yaml\n",
+                "                         (default from the input extension)\n",
+                "  --json                 Print the translation as JSON with token stats\n",
+                "  --socket <path>        Daemon socket (default $XDG_RUNTIME_DIR/petit.sock)\n",
+                "  --no-daemon            Translate in-process even when a daemon is running\n",
+                "  --placeholders <mode>  Protect placeholders and markup: off, warn or strict\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Batch => concat!(
+                "petit batch - Translate a TSV, JSONL or plain text file\n\n",
+                "Usage:\n",
+                "  petit batch --input <path> --output <path> [options]\n\n",
+                "TSV rows follow eval/fixtures: case_id, source_lang, target_lang, input_text.\n",
+                "JSONL objects need `text` and may set `id`, `source_lang` and `target_lang`.\n",
+                "Text files hold one segment per line. An existing output file is resumed.\n\n",
+                "Options:\n",
+                "  --input <path>         File to translate (default stdin)\n",
+                "  --output <path>        File to write or resume (default stdout)\n",
+                "  --format <name>        tsv, jsonl or text (default from the input extension)\n",
+                "  --placeholders <mode>  Protect placeholders and markup: off, warn or strict\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Bench => concat!(
+                "petit bench - Measure translation speed\n\n",
+                "Usage:\n",
+                "  petit bench [options]\n\n",
+                "Options:\n",
+                "  --text <value>         Input text (default \"Hello, how are you?\")\n",
+                "  --stdin                Read the input text from stdin\n",
+                "  --warmup-runs <n>      Warmup runs before measured runs (default 0)\n",
+                "  --runs <n>             Measured runs (default 1)\n",
+                "  --max-new-tokens <n>   Max output tokens per run (default 256)\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Glossary(_) => concat!(
+                "petit glossary - Inspect the glossary\n\n",
+                "Usage:\n",
+                "  petit glossary check [options]         Load the glossary and count its terms\n",
+                "  petit glossary lookup [options] <text> Print the terms that apply to a text\n\n",
+                "Options:\n",
+                config_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Config(_) => concat!(
+                "petit config - Inspect the configuration\n\n",
+                "Usage:\n",
+                "  petit config show [options]  Print the effective configuration as TOML\n",
+                "  petit config path [options]  Print the config files that are read\n\n",
+                "Flags override the printed values like they do for other commands.\n\n",
+                "Options:\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+                server_options!(),
+                "  --socket <path>        Daemon socket\n",
+            ),
+            Command::Serve => concat!(
+                "petit serve - Run the local HTTP translation server\n\n",
+                "Usage:\n",
+                "  petit serve [options]\n\n",
+                "Options:\n",
+                server_options!(),
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Daemon => concat!(
+                "petit daemon - Keep the translator loaded behind a Unix socket\n\n",
+                "Usage:\n",
+                "  petit daemon [options]\n\n",
+                "Options:\n",
+                "  --socket <path>        Daemon socket (default $XDG_RUNTIME_DIR/petit.sock)\n",
+                "  --queue-size <n>       Requests that may wait for the translator\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Rpc => concat!(
+                "petit rpc - Speak line-delimited JSON-RPC 2.0 on stdin/stdout\n\n",
+                "Usage:\n",
+                "  petit rpc [options]\n\n",
+                "Options:\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+            Command::Mcp => concat!(
+                "petit mcp - Run a Model Context Protocol server on stdin/stdout\n\n",
+                "Usage:\n",
+                "  petit mcp [options]\n\n",
+                "Options:\n",
+                config_options!(),
+                model_options!(),
+                language_options!(),
+                glossary_options!(),
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct CliArgs {
+    pub command: Command,
+    pub backend: Option<Backend>,
+    pub backend_url: Option<String>,
     pub model: Option<PathBuf>,
     pub source_lang: Option<String>,
     pub target_lang: Option<String>,
@@ -16,7 +421,21 @@ pub struct CliArgs {
     pub config: Option<PathBuf>,
     pub no_config: bool,
     pub stdin: bool,
-    pub benchmark: bool,
+    pub json: bool,
+    pub bind: Option<String>,
+    pub server_api: Option<ServerApi>,
+    pub deepl: bool,
+    pub queue_size: Option<usize>,
+    pub socket: Option<PathBuf>,
+    pub no_daemon: bool,
+    /// Positional text of `petit translate` and `petit glossary lookup`
+    pub text: Option<String>,
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub batch_format: Option<BatchFormat>,
+    /// `--format` of `petit translate`
+    pub document_format: Option<DocumentFormat>,
+    pub placeholders: Option<PlaceholderMode>,
     pub benchmark_text: Option<String>,
     pub benchmark_warmup_runs: Option<u32>,
     pub benchmark_runs: Option<u32>,
@@ -35,11 +454,35 @@ impl CliArgs {
     }
 
     pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
-        let mut args = args.into_iter();
+        let mut args = args.into_iter().peekable();
         let mut cli = CliArgs::default();
 
+        let subcommand = args.peek().and_then(|arg| Command::from_name(arg));
+        if let Some(command) = subcommand {
+            args.next();
+            cli.command = parse_action(command, &mut args, &mut cli)?;
+        }
+
+        let mut words = Vec::new();
         while let Some(arg) = args.next() {
+            // Mode flags on a bare `petit` keep the flat flag list
+            let scope = if subcommand.is_some() {
+                cli.command
+            } else {
+                Command::Default
+            };
+            if arg.starts_with('-') && !scope.accepts(&arg) {
+                return Err(if is_known_flag(&arg) {
+                    anyhow!("{arg} is not an option of `{}`", scope.name())
+                } else {
+                    anyhow!("Unknown argument: {arg}")
+                });
+            }
             match arg.as_str() {
+                "--backend" => cli.backend = Some(parse_string(&mut args, "--backend")?.parse()?),
+                "--backend-url" => {
+                    cli.backend_url = Some(parse_string(&mut args, "--backend-url")?)
+                }
                 "--model" => cli.model = Some(parse_path(&mut args, "--model")?),
                 "--source-lang" => {
                     cli.source_lang = Some(parse_string(&mut args, "--source-lang")?)
@@ -57,7 +500,30 @@ impl CliArgs {
                 "--config" => cli.config = Some(parse_path(&mut args, "--config")?),
                 "--no-config" => cli.no_config = true,
                 "--stdin" => cli.stdin = true,
-                "--benchmark" => cli.benchmark = true,
+                "--json" => cli.json = true,
+                "--benchmark" => set_mode(&mut cli, Command::Bench, "--benchmark")?,
+                "--serve" => set_mode(&mut cli, Command::Serve, "--serve")?,
+                "--rpc" => set_mode(&mut cli, Command::Rpc, "--rpc")?,
+                "--mcp" => set_mode(&mut cli, Command::Mcp, "--mcp")?,
+                "--bind" => cli.bind = Some(parse_string(&mut args, "--bind")?),
+                "--server-api" => {
+                    cli.server_api = Some(parse_string(&mut args, "--server-api")?.parse()?)
+                }
+                "--deepl" => cli.deepl = true,
+                "--queue-size" => cli.queue_size = Some(parse_usize(&mut args, "--queue-size")?),
+                "--socket" => cli.socket = Some(parse_path(&mut args, "--socket")?),
+                "--no-daemon" => cli.no_daemon = true,
+                "--input" => cli.input = Some(parse_path(&mut args, "--input")?),
+                "--output" => cli.output = Some(parse_path(&mut args, "--output")?),
+                "--format" if cli.command == Command::Batch => {
+                    cli.batch_format = Some(parse_string(&mut args, "--format")?.parse()?)
+                }
+                "--format" => {
+                    cli.document_format = Some(parse_string(&mut args, "--format")?.parse()?)
+                }
+                "--placeholders" => {
+                    cli.placeholders = Some(parse_string(&mut args, "--placeholders")?.parse()?)
+                }
                 "--text" => cli.benchmark_text = Some(parse_string(&mut args, "--text")?),
                 "--warmup-runs" => {
                     cli.benchmark_warmup_runs = Some(parse_u32(&mut args, "--warmup-runs")?)
@@ -81,23 +547,71 @@ impl CliArgs {
                 }
                 "--version" | "-V" => cli.show_version = true,
                 "--help" | "-h" => cli.show_help = true,
+                word if takes_text(cli.command) && !word.starts_with('-') => {
+                    words.push(arg.clone())
+                }
                 unknown => return Err(anyhow!("Unknown argument: {unknown}")),
             }
         }
-
-        if cli.no_config && cli.config.is_some() {
-            return Err(anyhow!("--no-config cannot be used with --config"));
+        if !words.is_empty() {
+            cli.text = Some(words.join(" "));
         }
 
+        if cli.show_help {
+            return Ok(cli);
+        }
+        cli.validate()?;
         Ok(cli)
     }
 
+    /// Checks that depend on the chosen command
+    fn validate(&self) -> Result<()> {
+        if self.no_config && self.config.is_some() {
+            return Err(anyhow!("--no-config cannot be used with --config"));
+        }
+        match self.command {
+            Command::Bench if self.benchmark_runs == Some(0) => {
+                Err(anyhow!("--runs must be at least 1"))
+            }
+            Command::Bench if self.benchmark_text.is_some() && self.stdin => {
+                Err(anyhow!("--text cannot be used with --stdin"))
+            }
+            Command::Glossary(GlossaryAction::Lookup) if self.text.is_none() => {
+                Err(anyhow!("`petit glossary lookup` needs the text to look up"))
+            }
+            Command::Batch | Command::Translate
+                if self.input.is_some() && self.input == self.output =>
+            {
+                Err(anyhow!("--input and --output must be different files"))
+            }
+            Command::Translate if self.text.is_some() && self.input.is_some() => {
+                Err(anyhow!("text arguments cannot be used with --input"))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn usage() -> &'static str {
         concat!(
             "petit - Local TranslateGemma TUI\n\n",
             "Usage:\n",
-            "  petit [options]\n\n",
+            "  petit [options]            Open the TUI, or translate piped stdin and exit\n",
+            "  petit <command> [options]\n\n",
+            "Commands:\n",
+            "  tui                    Open the interactive TUI\n",
+            "  translate [text...]    Translate the arguments or stdin and exit\n",
+            "  batch                  Translate a TSV, JSONL or plain text file\n",
+            "  bench                  Measure translation speed\n",
+            "  glossary check|lookup  Check the glossary or look up terms for a text\n",
+            "  config show|path       Print the effective configuration or its files\n",
+            "  serve                  Run the local HTTP translation server\n",
+            "  daemon                 Keep the translator loaded behind a Unix socket\n",
+            "  rpc                    Speak line-delimited JSON-RPC 2.0 on stdin/stdout\n",
+            "  mcp                    Run a Model Context Protocol server on stdin/stdout\n\n",
+            "Run `petit <command> --help` for the options of a command.\n\n",
             "Options:\n",
+            "  --backend <name>       Translation backend: gemma (default), http or mock\n",
+            "  --backend-url <url>    Base URL of the server used by the http backend\n",
             "  --model <path>         Path to GGUF model\n",
             "  --source-lang <code>   Source language (e.g. en)\n",
             "  --src <code>           Alias for --source-lang\n",
@@ -109,11 +623,22 @@ impl CliArgs {
             "  --config <path>        Config file path\n",
             "  --no-config            Ignore config file\n",
             "  --stdin                Read text from stdin and exit\n",
-            "  --benchmark            Run benchmark mode and exit (uses config precedence)\n",
+            "  --json                 Print the stdin translation as JSON with token stats\n",
+            "  --rpc                  Same as `petit rpc`\n",
+            "  --mcp                  Same as `petit mcp`\n",
+            "  --benchmark            Same as `petit bench`\n",
             "  --text <value>         Benchmark input text (benchmark mode)\n",
             "  --warmup-runs <n>      Warmup runs before measured runs (benchmark mode)\n",
             "  --runs <n>             Measured benchmark runs (benchmark mode)\n",
             "  --max-new-tokens <n>   Max output tokens for benchmark run (benchmark mode)\n",
+            "  --serve                Same as `petit serve`\n",
+            "  --bind <addr>          Server listen address (default 127.0.0.1:8000)\n",
+            "  --server-api <name>    Server API: native (default) or libretranslate\n",
+            "  --deepl                Also serve the DeepL-compatible /v2 endpoints (server mode)\n",
+            "  --queue-size <n>       Requests that may wait for the translator (server mode)\n",
+            "  --socket <path>        Daemon socket (default $XDG_RUNTIME_DIR/petit.sock)\n",
+            "  --no-daemon            Translate stdin in-process even when a daemon is running\n",
+            "  --placeholders <mode>  Protect placeholders and markup: off, warn or strict\n",
             "  --glossary             Enable glossary-constrained translation\n",
             "  --no-glossary          Disable glossary-constrained translation\n",
             "  --glossary-path <path> Path to glossary TSV file\n",
@@ -125,6 +650,70 @@ impl CliArgs {
     }
 }
 
+/// Read the action word of `petit glossary` and `petit config`
+fn parse_action(
+    command: Command,
+    args: &mut Peekable<impl Iterator<Item = String>>,
+    cli: &mut CliArgs,
+) -> Result<Command> {
+    let (actions, expected) = match command {
+        Command::Glossary(_) => (
+            &[
+                ("check", Command::Glossary(GlossaryAction::Check)),
+                ("lookup", Command::Glossary(GlossaryAction::Lookup)),
+            ][..],
+            "check or lookup",
+        ),
+        Command::Config(_) => (
+            &[
+                ("show", Command::Config(ConfigAction::Show)),
+                ("path", Command::Config(ConfigAction::Path)),
+            ][..],
+            "show or path",
+        ),
+        other => return Ok(other),
+    };
+
+    match args.peek().map(String::as_str) {
+        Some("--help" | "-h") => {
+            cli.show_help = true;
+            Ok(command)
+        }
+        Some(word) => {
+            let action = actions
+                .iter()
+                .find(|(name, _)| *name == word)
+                .map(|(_, action)| *action)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Unknown `{}` action: {word} (expected {expected})",
+                        command.name()
+                    )
+                })?;
+            args.next();
+            Ok(action)
+        }
+        None => Err(anyhow!("`{}` needs an action: {expected}", command.name())),
+    }
+}
+
+/// Commands that take free text as positional arguments
+fn takes_text(command: Command) -> bool {
+    matches!(
+        command,
+        Command::Translate | Command::Glossary(GlossaryAction::Lookup)
+    )
+}
+
+/// Select a mode with one of the flags kept from before subcommands existed
+fn set_mode(cli: &mut CliArgs, command: Command, flag: &str) -> Result<()> {
+    if cli.command != Command::Default && cli.command != command {
+        return Err(anyhow!("{flag} cannot be combined with another mode flag"));
+    }
+    cli.command = command;
+    Ok(())
+}
+
 fn parse_string(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String> {
     args.next()
         .ok_or_else(|| anyhow!("Missing value for {name}"))
@@ -202,6 +791,226 @@ mod tests {
         assert_eq!(cli.glossary_max_matches, Some(6));
     }
 
+    #[test]
+    fn parse_accepts_backend() {
+        let cli = CliArgs::parse_from(args(&["--backend", "Mock"])).expect("backend should parse");
+
+        assert_eq!(cli.backend, Some(Backend::Mock));
+        assert!(CliArgs::usage().contains("--backend"));
+    }
+
+    #[test]
+    fn parse_accepts_server_flags() {
+        let cli = CliArgs::parse_from(args(&[
+            "--serve",
+            "--bind",
+            "0.0.0.0:9000",
+            "--queue-size",
+            "4",
+            "--server-api",
+            "LibreTranslate",
+            "--deepl",
+        ]))
+        .expect("server flags should parse");
+
+        assert_eq!(cli.command, Command::Serve);
+        assert_eq!(cli.bind.as_deref(), Some("0.0.0.0:9000"));
+        assert_eq!(cli.queue_size, Some(4));
+        assert_eq!(cli.server_api, Some(ServerApi::LibreTranslate));
+        assert!(cli.deepl);
+    }
+
+    #[test]
+    fn parse_accepts_daemon_subcommand_and_socket() {
+        let cli = CliArgs::parse_from(args(&["daemon", "--socket", "/tmp/petit-test.sock"]))
+            .expect("daemon subcommand should parse");
+
+        assert_eq!(cli.command, Command::Daemon);
+        assert_eq!(cli.socket, Some(PathBuf::from("/tmp/petit-test.sock")));
+
+        let err = CliArgs::parse_from(args(&["--stdin", "daemon"]))
+            .expect_err("daemon is only a leading subcommand");
+        assert!(err.to_string().contains("Unknown argument: daemon"));
+
+        let err = CliArgs::parse_from(args(&["daemon", "--no-daemon"]))
+            .expect_err("conflicting daemon flags should be rejected");
+        assert!(err.to_string().contains("--no-daemon"));
+    }
+
+    #[test]
+    fn parse_accepts_http_backend_url() {
+        let cli = CliArgs::parse_from(args(&[
+            "--backend",
+            "http",
+            "--backend-url",
+            "http://127.0.0.1:8081",
+        ]))
+        .expect("http backend should parse");
+
+        assert_eq!(cli.backend, Some(Backend::Http));
+        assert_eq!(cli.backend_url.as_deref(), Some("http://127.0.0.1:8081"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_backend() {
+        let err = CliArgs::parse_from(args(&["--backend", "candle"]))
+            .expect_err("unknown backend should be rejected");
+
+        assert!(err.to_string().contains("Unknown backend: candle"));
+    }
+
+    #[test]
+    fn parse_accepts_json_flag() {
+        let cli = CliArgs::parse_from(args(&["--stdin", "--json"])).expect("flags should parse");
+
+        assert!(cli.stdin);
+        assert!(cli.json);
+        assert!(CliArgs::usage().contains("--json"));
+    }
+
+    #[test]
+    fn parse_accepts_rpc_flag() {
+        let cli = CliArgs::parse_from(args(&["--rpc", "--tgt", "de"])).expect("flags should parse");
+
+        assert_eq!(cli.command, Command::Rpc);
+        assert!(CliArgs::usage().contains("--rpc"));
+
+        let cli = CliArgs::parse_from(args(&["--mcp"])).expect("flags should parse");
+        assert_eq!(cli.command, Command::Mcp);
+        assert!(CliArgs::usage().contains("--mcp"));
+    }
+
+    #[test]
+    fn parse_accepts_subcommands_with_their_own_flags() {
+        let cli = CliArgs::parse_from(args(&[
+            "translate",
+            "--tgt",
+            "de",
+            "--json",
+            "Hello",
+            "world",
+        ]))
+        .expect("translate should parse");
+        assert_eq!(cli.command, Command::Translate);
+        assert_eq!(cli.text.as_deref(), Some("Hello world"));
+        assert!(cli.json);
+
+        let cli = CliArgs::parse_from(args(&["bench", "--runs", "3", "--text", "Hi"]))
+            .expect("bench should parse");
+        assert_eq!(cli.command, Command::Bench);
+        assert_eq!(cli.benchmark_runs, Some(3));
+
+        let cli = CliArgs::parse_from(args(&[
+            "glossary", "lookup", "--src", "en", "cloud", "sync",
+        ]))
+        .expect("glossary lookup should parse");
+        assert_eq!(cli.command, Command::Glossary(GlossaryAction::Lookup));
+        assert_eq!(cli.text.as_deref(), Some("cloud sync"));
+
+        let cli = CliArgs::parse_from(args(&["config", "path", "--no-config"]))
+            .expect("config path should parse");
+        assert_eq!(cli.command, Command::Config(ConfigAction::Path));
+
+        let cli = CliArgs::parse_from(args(&["batch", "--input", "in.txt", "--output", "out.txt"]))
+            .expect("batch should parse");
+        assert_eq!(cli.command, Command::Batch);
+        assert_eq!(cli.input, Some(PathBuf::from("in.txt")));
+
+        let cli = CliArgs::parse_from(args(&["batch", "--format", "JSONL"]))
+            .expect("batch format should parse");
+        assert_eq!(cli.batch_format, Some(BatchFormat::Jsonl));
+
+        let cli = CliArgs::parse_from(args(&[])).expect("bare petit should parse");
+        assert_eq!(cli.command, Command::Default);
+    }
+
+    #[test]
+    fn parse_translate_document_flags() {
+        let cli = CliArgs::parse_from(args(&[
+            "translate",
+            "--input",
+            "README.md",
+            "--output",
+            "README.fr.md",
+            "--format",
+            "Markdown",
+            "--placeholders",
+            "strict",
+        ]))
+        .expect("translate should take a document");
+        assert_eq!(cli.document_format, Some(DocumentFormat::Markdown));
+        assert_eq!(cli.placeholders, Some(PlaceholderMode::Strict));
+        assert_eq!(cli.batch_format, None);
+        assert_eq!(cli.input, Some(PathBuf::from("README.md")));
+
+        let err = CliArgs::parse_from(args(&["translate", "--format", "docx"]))
+            .expect_err("unknown document format should fail");
+        assert!(err.to_string().contains("docx"));
+
+        let err = CliArgs::parse_from(args(&["translate", "hello", "--input", "a.md"]))
+            .expect_err("text and --input should conflict");
+        assert!(err.to_string().contains("--input"));
+    }
+
+    #[test]
+    fn parse_rejects_flags_of_other_subcommands() {
+        let err = CliArgs::parse_from(args(&["tui", "--runs", "3"]))
+            .expect_err("tui takes no benchmark flags");
+        assert!(
+            err.to_string()
+                .contains("--runs is not an option of `petit tui`")
+        );
+
+        let err = CliArgs::parse_from(args(&["serve", "--frobnicate"]))
+            .expect_err("unknown flags should be rejected");
+        assert!(err.to_string().contains("Unknown argument: --frobnicate"));
+
+        let err = CliArgs::parse_from(args(&["bench", "extra"]))
+            .expect_err("bench takes no text arguments");
+        assert!(err.to_string().contains("Unknown argument: extra"));
+
+        let err = CliArgs::parse_from(args(&["--input", "in.txt"]))
+            .expect_err("--input belongs to petit batch");
+        assert!(err.to_string().contains("--input"));
+    }
+
+    #[test]
+    fn parse_validates_each_subcommand() {
+        let err = CliArgs::parse_from(args(&["bench", "--runs", "0"]))
+            .expect_err("zero runs should be rejected");
+        assert!(err.to_string().contains("--runs must be at least 1"));
+
+        let err = CliArgs::parse_from(args(&["glossary"])).expect_err("glossary needs an action");
+        assert!(err.to_string().contains("check or lookup"));
+
+        let err =
+            CliArgs::parse_from(args(&["glossary", "lookup"])).expect_err("lookup needs text");
+        assert!(err.to_string().contains("needs the text"));
+
+        let err = CliArgs::parse_from(args(&["config", "edit"]))
+            .expect_err("unknown config action should be rejected");
+        assert!(err.to_string().contains("show or path"));
+
+        let err = CliArgs::parse_from(args(&["--serve", "--rpc"]))
+            .expect_err("two modes should be rejected");
+        assert!(err.to_string().contains("--rpc"));
+
+        let cli = CliArgs::parse_from(args(&["glossary", "--help"])).expect("help needs no action");
+        assert!(cli.show_help);
+        assert!(cli.command.usage().contains("petit glossary lookup"));
+    }
+
+    #[test]
+    fn each_subcommand_has_its_own_help() {
+        let tui = Command::Tui.usage();
+        assert!(tui.contains("petit tui"));
+        assert!(!tui.contains("--runs"));
+        assert!(Command::Bench.usage().contains("--runs"));
+        assert!(Command::Serve.usage().contains("--bind"));
+        assert!(Command::Batch.usage().contains("--input"));
+        assert!(CliArgs::usage().contains("Commands:"));
+    }
+
     #[test]
     fn parse_rejects_conflicting_glossary_flags() {
         let err = CliArgs::parse_from(args(&["--glossary", "--no-glossary"]))
@@ -3,9 +3,12 @@
 //! Config loading and precedence handling for petit-tui.
 
 use anyhow::{Result, anyhow};
-use petit_core::Config;
-use petit_core::config::GlossaryConfig as CoreGlossaryConfig;
+use petit_core::config::{
+    GlossaryConfig as CoreGlossaryConfig, HttpApi, HttpConfig, SamplingConfig,
+};
 use petit_core::language::{normalize_lang, validate_pair};
+use petit_core::{Config, PlaceholderMode, TranslationOptions};
+use petit_server::{ServerApi, ServerConfig};
 use serde::Deserialize;
 use std::env;
 use std::fs;
@@ -14,15 +17,69 @@ use std::path::{Path, PathBuf};
 
 use crate::cli::CliArgs;
 
+/// Translation backend selected for the app
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// TranslateGemma through llama.cpp
+    #[default]
+    Gemma,
+    /// Self-hosted OpenAI-compatible server configured in `[backend]`
+    Http,
+    /// Model-free mock translator (requires the `mock` feature)
+    Mock,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "gemma" => Ok(Backend::Gemma),
+            "http" => Ok(Backend::Http),
+            "mock" => Ok(Backend::Mock),
+            other => Err(anyhow!(
+                "Unknown backend: {other} (expected `gemma`, `http` or `mock`)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Gemma => f.write_str("gemma"),
+            Backend::Http => f.write_str("http"),
+            Backend::Mock => f.write_str("mock"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AppConfig {
+    pub backend: Backend,
+    pub http: HttpConfig,
+    pub server: ServerConfig,
+    pub daemon_socket: PathBuf,
     pub core: Config,
     pub source_lang: String,
     pub target_lang: String,
+    /// Placeholder protection; unset keeps the core default (off for text, warn for documents)
+    pub placeholders: Option<PlaceholderMode>,
     pub stdin_mode: bool,
     pub compact_lang_display: bool,
 }
 
+impl AppConfig {
+    /// Options every translation started from the command line or the TUI uses
+    pub fn translation_options(&self) -> TranslationOptions {
+        TranslationOptions {
+            placeholders: self.placeholders,
+            ..TranslationOptions::default()
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct FileConfig {
     #[serde(default)]
@@ -33,6 +90,12 @@ struct FileConfig {
     ui: UiConfig,
     #[serde(default)]
     glossary: GlossaryFileConfig,
+    #[serde(default)]
+    backend: BackendFileConfig,
+    #[serde(default)]
+    server: ServerFileConfig,
+    #[serde(default)]
+    daemon: DaemonFileConfig,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -43,12 +106,38 @@ struct ModelConfig {
     threads: Option<u32>,
     log_to_file: Option<bool>,
     log_path: Option<PathBuf>,
+    temperature: Option<f32>,
+    top_k: Option<u32>,
+    top_p: Option<f32>,
+    min_p: Option<f32>,
+    repeat_penalty: Option<f32>,
+    repeat_last_n: Option<u32>,
+    seed: Option<u32>,
+}
+
+impl ModelConfig {
+    fn take_sampling(&mut self) -> SamplingConfig {
+        let defaults = SamplingConfig::default();
+        SamplingConfig {
+            temperature: self.temperature.take().unwrap_or(defaults.temperature),
+            top_k: self.top_k.take().unwrap_or(defaults.top_k),
+            top_p: self.top_p.take().unwrap_or(defaults.top_p),
+            min_p: self.min_p.take().unwrap_or(defaults.min_p),
+            repeat_penalty: self
+                .repeat_penalty
+                .take()
+                .unwrap_or(defaults.repeat_penalty),
+            repeat_last_n: self.repeat_last_n.take().unwrap_or(defaults.repeat_last_n),
+            seed: self.seed.take().or(defaults.seed),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct TranslationConfig {
     default_source: Option<String>,
     default_target: Option<String>,
+    placeholders: Option<PlaceholderMode>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -75,6 +164,52 @@ impl GlossaryFileConfig {
     }
 }
 
+#[derive(Debug, Deserialize, Default)]
+struct BackendFileConfig {
+    #[serde(rename = "type")]
+    kind: Option<Backend>,
+    url: Option<String>,
+    api: Option<HttpApi>,
+    model: Option<String>,
+    api_key: Option<String>,
+    timeout_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+    max_retries: Option<u32>,
+    retry_backoff_ms: Option<u64>,
+}
+
+impl BackendFileConfig {
+    fn into_http(self) -> HttpConfig {
+        let defaults = HttpConfig::default();
+        HttpConfig {
+            url: self.url.unwrap_or(defaults.url),
+            api: self.api.unwrap_or(defaults.api),
+            model: self.model.or(defaults.model),
+            api_key: self.api_key.or(defaults.api_key),
+            timeout_secs: self.timeout_secs.unwrap_or(defaults.timeout_secs),
+            connect_timeout_secs: self
+                .connect_timeout_secs
+                .unwrap_or(defaults.connect_timeout_secs),
+            max_retries: self.max_retries.unwrap_or(defaults.max_retries),
+            retry_backoff_ms: self.retry_backoff_ms.unwrap_or(defaults.retry_backoff_ms),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ServerFileConfig {
+    bind: Option<String>,
+    api: Option<ServerApi>,
+    queue_size: Option<usize>,
+    max_batch_size: Option<usize>,
+    deepl: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct DaemonFileConfig {
+    socket: Option<PathBuf>,
+}
+
 pub fn load_config(cli: &CliArgs) -> Result<AppConfig> {
     let mut file = load_merged_file_config(cli)?;
 
@@ -86,6 +221,7 @@ pub fn load_config(cli: &CliArgs) -> Result<AppConfig> {
         log_to_file: take_required(file.model.log_to_file.take(), "model.log_to_file")?,
         log_path: take_required(file.model.log_path.take(), "model.log_path")?,
         glossary: file.glossary.into_core(),
+        sampling: file.model.take_sampling(),
     };
     let mut source_lang = take_required(
         file.translation.default_source.take(),
@@ -95,6 +231,31 @@ pub fn load_config(cli: &CliArgs) -> Result<AppConfig> {
         file.translation.default_target.take(),
         "translation.default_target",
     )?;
+    let mut placeholders = file.translation.placeholders.take();
+    let mut backend = file.backend.kind.take().unwrap_or_default();
+    let mut http = file.backend.into_http();
+    let server_defaults = ServerConfig::default();
+    let mut server = ServerConfig {
+        bind: file.server.bind.take().unwrap_or(server_defaults.bind),
+        api: file.server.api.take().unwrap_or(server_defaults.api),
+        queue_size: file
+            .server
+            .queue_size
+            .take()
+            .unwrap_or(server_defaults.queue_size),
+        max_batch_size: file
+            .server
+            .max_batch_size
+            .take()
+            .unwrap_or(server_defaults.max_batch_size),
+        deepl: file.server.deepl.take().unwrap_or(server_defaults.deepl),
+        ..server_defaults
+    };
+    let mut daemon_socket = file
+        .daemon
+        .socket
+        .take()
+        .unwrap_or_else(petit_server::daemon::default_socket_path);
     let mut stdin_mode = cli.stdin;
     let mut compact_lang_display = file.ui.compact_lang_display.unwrap_or(false);
 
@@ -105,25 +266,154 @@ pub fn load_config(cli: &CliArgs) -> Result<AppConfig> {
         &mut compact_lang_display,
     );
     apply_cli_config(cli, &mut core, &mut source_lang, &mut target_lang);
+    apply_backend_overrides(cli, &mut backend, &mut http);
+    apply_server_overrides(cli, &mut server);
+    if let Some(value) = env_var("PETIT_TRAD_DAEMON_SOCKET") {
+        daemon_socket = PathBuf::from(value);
+    }
+    if let Some(value) = &cli.socket {
+        daemon_socket = value.clone();
+    }
+    if let Some(value) = env_var("PETIT_TRAD_PLACEHOLDERS").and_then(|value| value.parse().ok()) {
+        placeholders = Some(value);
+    }
+    if let Some(value) = cli.placeholders {
+        placeholders = Some(value);
+    }
 
     source_lang = normalize_lang(&source_lang);
     target_lang = normalize_lang(&target_lang);
     validate_pair(&source_lang, &target_lang)
         .map_err(|err| anyhow!("Invalid language pair: {err}"))?;
+    server.default_source = source_lang.clone();
+    server.default_target = target_lang.clone();
 
     if !stdin_mode && !std::io::stdin().is_terminal() {
         stdin_mode = true;
     }
 
     Ok(AppConfig {
+        backend,
+        http,
+        server,
+        daemon_socket,
         core,
         source_lang,
         target_lang,
+        placeholders,
         stdin_mode,
         compact_lang_display,
     })
 }
 
+/// Config files read for `cli`, in the order they are merged
+pub fn config_files(cli: &CliArgs) -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from("config/default.toml")];
+    if cli.no_config || env_bool("PETIT_TRAD_NO_CONFIG").unwrap_or(false) {
+        return files;
+    }
+    files.extend(cli.config.clone().or_else(xdg_user_config_path));
+    files
+}
+
+/// Render the effective configuration in the layout of `config/default.toml`
+pub fn render_config(config: &AppConfig) -> String {
+    let core = &config.core;
+    let sampling = &core.sampling;
+    let http = &config.http;
+    let server = &config.server;
+    let mut out = String::new();
+
+    out.push_str("[model]\n");
+    push_value(&mut out, "path", toml_path(&core.model_path));
+    push_value(&mut out, "gpu_layers", core.gpu_layers);
+    push_value(&mut out, "context_size", core.context_size);
+    push_value(&mut out, "threads", core.threads);
+    push_value(&mut out, "log_to_file", core.log_to_file);
+    push_value(&mut out, "log_path", toml_path(&core.log_path));
+    push_value(&mut out, "temperature", float(sampling.temperature));
+    push_value(&mut out, "top_k", sampling.top_k);
+    push_value(&mut out, "top_p", float(sampling.top_p));
+    push_value(&mut out, "min_p", float(sampling.min_p));
+    push_value(&mut out, "repeat_penalty", float(sampling.repeat_penalty));
+    push_value(&mut out, "repeat_last_n", sampling.repeat_last_n);
+    if let Some(seed) = sampling.seed {
+        push_value(&mut out, "seed", seed);
+    }
+
+    out.push_str("\n[translation]\n");
+    push_value(&mut out, "default_source", config.source_lang.as_str());
+    push_value(&mut out, "default_target", config.target_lang.as_str());
+    if let Some(placeholders) = &config.placeholders {
+        push_value(&mut out, "placeholders", enum_value(placeholders));
+    }
+
+    out.push_str("\n[glossary]\n");
+    push_value(&mut out, "enabled", core.glossary.enabled);
+    push_value(&mut out, "path", toml_path(&core.glossary.path));
+    push_value(
+        &mut out,
+        "embedding_model_dir",
+        toml_path(&core.glossary.embedding_model_dir),
+    );
+    push_value(&mut out, "max_matches", core.glossary.max_matches as i64);
+
+    out.push_str("\n[backend]\n");
+    push_value(&mut out, "type", config.backend.to_string());
+    push_value(&mut out, "url", http.url.as_str());
+    push_value(&mut out, "api", enum_value(&http.api));
+    if let Some(model) = &http.model {
+        push_value(&mut out, "model", model.as_str());
+    }
+    if http.api_key.is_some() {
+        // Keep tokens out of terminals and bug reports
+        out.push_str("# api_key is set\n");
+    }
+    push_value(&mut out, "timeout_secs", http.timeout_secs as i64);
+    push_value(
+        &mut out,
+        "connect_timeout_secs",
+        http.connect_timeout_secs as i64,
+    );
+    push_value(&mut out, "max_retries", http.max_retries);
+    push_value(&mut out, "retry_backoff_ms", http.retry_backoff_ms as i64);
+
+    out.push_str("\n[server]\n");
+    push_value(&mut out, "bind", server.bind.as_str());
+    push_value(&mut out, "api", enum_value(&server.api));
+    push_value(&mut out, "queue_size", server.queue_size as i64);
+    push_value(&mut out, "max_batch_size", server.max_batch_size as i64);
+    push_value(&mut out, "deepl", server.deepl);
+
+    out.push_str("\n[daemon]\n");
+    push_value(&mut out, "socket", toml_path(&config.daemon_socket));
+
+    out.push_str("\n[ui]\n");
+    push_value(
+        &mut out,
+        "compact_lang_display",
+        config.compact_lang_display,
+    );
+    out
+}
+
+fn push_value(out: &mut String, key: &str, value: impl Into<toml::Value>) {
+    out.push_str(&format!("{key} = {}\n", value.into()));
+}
+
+/// Widen without exposing binary noise, so `0.9` does not print as `0.8999999761581421`
+fn float(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(f64::from(value))
+}
+
+fn toml_path(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn enum_value<T: serde::Serialize>(value: &T) -> toml::Value {
+    toml::Value::try_from(value).unwrap_or_else(|_| toml::Value::String(String::new()))
+}
+
 fn load_merged_file_config(cli: &CliArgs) -> Result<FileConfig> {
     let mut merged = load_file_config(Path::new("config/default.toml"))?;
 
@@ -171,6 +461,27 @@ fn merge_file_config(base: &mut FileConfig, overlay: FileConfig) {
     if overlay.model.log_path.is_some() {
         base.model.log_path = overlay.model.log_path;
     }
+    if overlay.model.temperature.is_some() {
+        base.model.temperature = overlay.model.temperature;
+    }
+    if overlay.model.top_k.is_some() {
+        base.model.top_k = overlay.model.top_k;
+    }
+    if overlay.model.top_p.is_some() {
+        base.model.top_p = overlay.model.top_p;
+    }
+    if overlay.model.min_p.is_some() {
+        base.model.min_p = overlay.model.min_p;
+    }
+    if overlay.model.repeat_penalty.is_some() {
+        base.model.repeat_penalty = overlay.model.repeat_penalty;
+    }
+    if overlay.model.repeat_last_n.is_some() {
+        base.model.repeat_last_n = overlay.model.repeat_last_n;
+    }
+    if overlay.model.seed.is_some() {
+        base.model.seed = overlay.model.seed;
+    }
 
     if overlay.translation.default_source.is_some() {
         base.translation.default_source = overlay.translation.default_source;
@@ -178,6 +489,9 @@ fn merge_file_config(base: &mut FileConfig, overlay: FileConfig) {
     if overlay.translation.default_target.is_some() {
         base.translation.default_target = overlay.translation.default_target;
     }
+    if overlay.translation.placeholders.is_some() {
+        base.translation.placeholders = overlay.translation.placeholders;
+    }
     if overlay.ui.compact_lang_display.is_some() {
         base.ui.compact_lang_display = overlay.ui.compact_lang_display;
     }
@@ -193,6 +507,53 @@ fn merge_file_config(base: &mut FileConfig, overlay: FileConfig) {
     if let Some(value) = overlay.glossary.max_matches {
         base.glossary.max_matches = Some(value);
     }
+
+    if overlay.server.bind.is_some() {
+        base.server.bind = overlay.server.bind;
+    }
+    if overlay.server.api.is_some() {
+        base.server.api = overlay.server.api;
+    }
+    if overlay.server.queue_size.is_some() {
+        base.server.queue_size = overlay.server.queue_size;
+    }
+    if overlay.server.max_batch_size.is_some() {
+        base.server.max_batch_size = overlay.server.max_batch_size;
+    }
+    if overlay.server.deepl.is_some() {
+        base.server.deepl = overlay.server.deepl;
+    }
+    if overlay.daemon.socket.is_some() {
+        base.daemon.socket = overlay.daemon.socket;
+    }
+
+    if overlay.backend.kind.is_some() {
+        base.backend.kind = overlay.backend.kind;
+    }
+    if overlay.backend.url.is_some() {
+        base.backend.url = overlay.backend.url;
+    }
+    if overlay.backend.api.is_some() {
+        base.backend.api = overlay.backend.api;
+    }
+    if overlay.backend.model.is_some() {
+        base.backend.model = overlay.backend.model;
+    }
+    if overlay.backend.api_key.is_some() {
+        base.backend.api_key = overlay.backend.api_key;
+    }
+    if overlay.backend.timeout_secs.is_some() {
+        base.backend.timeout_secs = overlay.backend.timeout_secs;
+    }
+    if overlay.backend.connect_timeout_secs.is_some() {
+        base.backend.connect_timeout_secs = overlay.backend.connect_timeout_secs;
+    }
+    if overlay.backend.max_retries.is_some() {
+        base.backend.max_retries = overlay.backend.max_retries;
+    }
+    if overlay.backend.retry_backoff_ms.is_some() {
+        base.backend.retry_backoff_ms = overlay.backend.retry_backoff_ms;
+    }
 }
 
 fn take_required<T>(value: Option<T>, field: &str) -> Result<T> {
@@ -301,6 +662,53 @@ fn apply_cli_config(cli: &CliArgs, core: &mut Config, source: &mut String, targe
     }
 }
 
+fn apply_backend_overrides(cli: &CliArgs, backend: &mut Backend, http: &mut HttpConfig) {
+    if let Some(value) = env_var("PETIT_TRAD_BACKEND").and_then(|value| value.parse().ok()) {
+        *backend = value;
+    }
+    if let Some(value) = env_var("PETIT_TRAD_BACKEND_URL") {
+        http.url = value;
+    }
+    if let Some(value) = env_var("PETIT_TRAD_BACKEND_API_KEY") {
+        http.api_key = Some(value);
+    }
+
+    if let Some(value) = cli.backend {
+        *backend = value;
+    }
+    if let Some(value) = &cli.backend_url {
+        http.url = value.clone();
+    }
+}
+
+fn apply_server_overrides(cli: &CliArgs, server: &mut ServerConfig) {
+    if let Some(value) = env_var("PETIT_TRAD_SERVER_BIND") {
+        server.bind = value;
+    }
+    if let Some(value) = env_var("PETIT_TRAD_SERVER_API").and_then(|value| value.parse().ok()) {
+        server.api = value;
+    }
+    if let Some(value) = env_usize("PETIT_TRAD_SERVER_QUEUE_SIZE") {
+        server.queue_size = value;
+    }
+    if let Some(value) = env_bool("PETIT_TRAD_SERVER_DEEPL") {
+        server.deepl = value;
+    }
+
+    if let Some(value) = &cli.bind {
+        server.bind = value.clone();
+    }
+    if let Some(value) = cli.server_api {
+        server.api = value;
+    }
+    if let Some(value) = cli.queue_size {
+        server.queue_size = value;
+    }
+    if cli.deepl {
+        server.deepl = true;
+    }
+}
+
 fn env_var(key: &str) -> Option<String> {
     env::var(key).ok().filter(|value| !value.is_empty())
 }
@@ -334,6 +742,9 @@ fn expand_file_config_paths(config: &mut FileConfig) {
     if let Some(path) = config.glossary.embedding_model_dir.take() {
         config.glossary.embedding_model_dir = Some(expand_home_path(path));
     }
+    if let Some(path) = config.daemon.socket.take() {
+        config.daemon.socket = Some(expand_home_path(path));
+    }
 }
 
 fn expand_home_path(path: PathBuf) -> PathBuf {
@@ -493,6 +904,61 @@ max_matches = 3
         let _ = std::fs::remove_file(config_path);
     }
 
+    #[test]
+    fn load_config_should_read_sampling_from_model_section() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let config_path = write_temp_config(
+            r#"
+[model]
+temperature = 0.6
+top_k = 40
+top_p = 0.95
+repeat_penalty = 1.1
+seed = 1234
+"#,
+        );
+
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            ..CliArgs::default()
+        };
+
+        let result = load_config(&cli).expect("config should load");
+
+        assert_eq!(
+            result.core.sampling,
+            SamplingConfig {
+                temperature: 0.6,
+                top_k: 40,
+                top_p: 0.95,
+                repeat_penalty: 1.1,
+                seed: Some(1234),
+                ..SamplingConfig::default()
+            }
+        );
+
+        let _ = std::fs::remove_file(config_path);
+    }
+
+    #[test]
+    fn load_config_should_default_to_greedy_sampling() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+
+        let cli = CliArgs {
+            no_config: true,
+            ..CliArgs::default()
+        };
+
+        let result = load_config(&cli).expect("config should load");
+
+        assert!(result.core.sampling.is_greedy());
+        assert_eq!(result.core.sampling.repeat_penalty, 1.0);
+    }
+
     #[test]
     fn load_config_should_default_source_to_auto() {
         let _guard = env_guard();
@@ -548,6 +1014,157 @@ max_matches = 6
         let _ = std::fs::remove_file(config_path);
     }
 
+    #[test]
+    fn load_config_should_read_backend_section() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let _backend = EnvVarGuard::remove("PETIT_TRAD_BACKEND");
+        let _url = EnvVarGuard::remove("PETIT_TRAD_BACKEND_URL");
+        let _api_key = EnvVarGuard::remove("PETIT_TRAD_BACKEND_API_KEY");
+        let config_path = write_temp_config(
+            r#"
+[backend]
+type = "http"
+url = "http://gpu-box:8080"
+api = "chat"
+model = "translategemma-12b"
+timeout_secs = 30
+"#,
+        );
+
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            ..CliArgs::default()
+        };
+
+        let result = load_config(&cli).expect("config should load");
+
+        assert_eq!(result.backend, Backend::Http);
+        assert_eq!(
+            result.http,
+            HttpConfig {
+                url: "http://gpu-box:8080".to_string(),
+                api: HttpApi::Chat,
+                model: Some("translategemma-12b".to_string()),
+                timeout_secs: 30,
+                ..HttpConfig::default()
+            }
+        );
+
+        let _ = std::fs::remove_file(config_path);
+    }
+
+    #[test]
+    fn load_config_should_apply_backend_precedence_over_env_and_file() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let config_path = write_temp_config(
+            r#"
+[backend]
+type = "gemma"
+url = "http://file:8080"
+"#,
+        );
+
+        let _backend = EnvVarGuard::set("PETIT_TRAD_BACKEND", "http");
+        let _url = EnvVarGuard::set("PETIT_TRAD_BACKEND_URL", "http://env:8080");
+        let _api_key = EnvVarGuard::set("PETIT_TRAD_BACKEND_API_KEY", "secret");
+
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            backend_url: Some("http://cli:8080".to_string()),
+            ..CliArgs::default()
+        };
+
+        let result = load_config(&cli).expect("config should load");
+
+        assert_eq!(result.backend, Backend::Http);
+        assert_eq!(result.http.url, "http://cli:8080");
+        assert_eq!(result.http.api_key.as_deref(), Some("secret"));
+
+        let _ = std::fs::remove_file(config_path);
+    }
+
+    #[test]
+    fn load_config_should_apply_server_precedence_and_default_languages() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let config_path = write_temp_config(
+            r#"
+[translation]
+default_target = "de"
+
+[server]
+bind = "127.0.0.1:9000"
+api = "libretranslate"
+queue_size = 8
+max_batch_size = 16
+"#,
+        );
+
+        let _bind = EnvVarGuard::set("PETIT_TRAD_SERVER_BIND", "127.0.0.1:9100");
+        let _queue_size = EnvVarGuard::remove("PETIT_TRAD_SERVER_QUEUE_SIZE");
+        let _api = EnvVarGuard::remove("PETIT_TRAD_SERVER_API");
+        let _target_lang = EnvVarGuard::remove("PETIT_TRAD_TARGET_LANG");
+
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            queue_size: Some(2),
+            ..CliArgs::default()
+        };
+
+        let result = load_config(&cli).expect("config should load");
+
+        assert_eq!(result.server.bind, "127.0.0.1:9100");
+        assert_eq!(result.server.queue_size, 2);
+        assert_eq!(result.server.max_batch_size, 16);
+        assert_eq!(result.server.api, ServerApi::LibreTranslate);
+        assert_eq!(result.server.default_target, "de");
+
+        let _ = std::fs::remove_file(config_path);
+    }
+
+    #[test]
+    fn load_config_should_apply_daemon_socket_precedence() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let config_path = write_temp_config(
+            r#"
+[daemon]
+socket = "/tmp/petit-file.sock"
+"#,
+        );
+        let _socket = EnvVarGuard::remove("PETIT_TRAD_DAEMON_SOCKET");
+
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            ..CliArgs::default()
+        };
+        let from_file = load_config(&cli).expect("config should load");
+
+        let _socket = EnvVarGuard::set("PETIT_TRAD_DAEMON_SOCKET", "/tmp/petit-env.sock");
+        let from_env = load_config(&cli).expect("config should load");
+
+        let cli = CliArgs {
+            socket: Some(PathBuf::from("/tmp/petit-cli.sock")),
+            ..cli
+        };
+        let from_cli = load_config(&cli).expect("config should load");
+
+        assert_eq!(
+            from_file.daemon_socket,
+            PathBuf::from("/tmp/petit-file.sock")
+        );
+        assert_eq!(from_env.daemon_socket, PathBuf::from("/tmp/petit-env.sock"));
+        assert_eq!(from_cli.daemon_socket, PathBuf::from("/tmp/petit-cli.sock"));
+
+        let _ = std::fs::remove_file(config_path);
+    }
+
     #[test]
     fn load_config_should_expand_home_in_glossary_paths_from_file() {
         let _guard = env_guard();
@@ -583,4 +1200,56 @@ max_matches = 6
 
         let _ = std::fs::remove_file(config_path);
     }
+
+    #[test]
+    fn render_config_should_load_back_to_the_same_config() {
+        let _guard = env_guard();
+        let (old_cwd, _repo_root) = with_repo_root();
+        let _cwd_guard = CwdGuard { old_cwd };
+        let config_path = write_temp_config(
+            r#"
+[model]
+temperature = 0.9
+seed = 7
+
+[translation]
+default_target = "de"
+placeholders = "strict"
+
+[backend]
+api = "chat"
+api_key = "secret"
+
+[server]
+api = "libretranslate"
+"#,
+        );
+        let cli = CliArgs {
+            config: Some(config_path.clone()),
+            ..CliArgs::default()
+        };
+        let original = load_config(&cli).expect("config should load");
+
+        let rendered = render_config(&original);
+        let _ = std::fs::remove_file(&config_path);
+        let rendered_path = write_temp_config(&rendered);
+        let reloaded = load_config(&CliArgs {
+            config: Some(rendered_path.clone()),
+            ..CliArgs::default()
+        })
+        .expect("rendered config should load");
+        let _ = std::fs::remove_file(rendered_path);
+
+        assert!(rendered.contains("temperature = 0.9\n"));
+        assert!(!rendered.contains("secret"));
+        assert_eq!(reloaded.core, original.core);
+        assert_eq!(reloaded.server, original.server);
+        assert_eq!(reloaded.target_lang, "de");
+        assert_eq!(reloaded.placeholders, Some(PlaceholderMode::Strict));
+        assert_eq!(reloaded.http.api, HttpApi::Chat);
+        assert_eq!(
+            config_files(&cli),
+            vec![PathBuf::from("config/default.toml"), config_path]
+        );
+    }
 }
@@ -4,16 +4,26 @@
 //!
 //! A TUI application for translating text using TranslateGemma.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
-use petit_core::{Config, GemmaTranslator, Translator};
+#[cfg(any(test, feature = "mock"))]
+use petit_core::MockTranslator;
+use petit_core::{
+    Config, DocumentFormat, Error as CoreError, GemmaTranslator, GenerationStats, GlossaryStore,
+    HttpConfig, HttpTranslator, StopReason, TranslationOptions, TranslationOutput, Translator,
+    update_document,
+};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::fs;
 use std::io::{self, Read, Stdout, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -21,12 +31,15 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::app::{App, Focus, LangTarget, TranslationRequest};
-use crate::cli::CliArgs;
-use crate::config::{AppConfig, load_config};
+use crate::cli::{CliArgs, Command, ConfigAction, GlossaryAction};
+use crate::config::{AppConfig, Backend, config_files, load_config, render_config};
 
 mod app;
+mod batch;
 mod cli;
 mod config;
+mod mcp;
+mod rpc;
 mod ui;
 
 fn main() -> Result<()> {
@@ -40,32 +53,54 @@ fn main() -> Result<()> {
 fn run() -> Result<()> {
     let cli = CliArgs::parse()?;
     if cli.show_help {
-        println!("{}", CliArgs::usage());
+        println!("{}", cli.command.usage());
         return Ok(());
     }
     if cli.show_version {
         println!("petit {}", env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
+    // Listed before loading, so a broken config file can still be located
+    if cli.command == Command::Config(ConfigAction::Path) {
+        for path in config_files(&cli) {
+            let note = if path.exists() { "" } else { " (not found)" };
+            println!("{}{note}", path.display());
+        }
+        return Ok(());
+    }
 
     let app_config = load_config(&cli)?;
     let _compact_lang_display = app_config.compact_lang_display;
-    if cli.benchmark {
-        return run_benchmark(app_config, &cli);
-    }
-    if app_config.stdin_mode {
-        return run_stdin(app_config);
+    match cli.command {
+        Command::Default if app_config.stdin_mode => run_translate(app_config, &cli),
+        Command::Default | Command::Tui => run_tui(app_config),
+        Command::Translate => run_translate(app_config, &cli),
+        Command::Batch => batch::run_batch(app_config, &cli),
+        Command::Bench => run_benchmark(app_config, &cli),
+        Command::Glossary(action) => run_glossary(app_config, action, cli.text.as_deref()),
+        Command::Config(_) => {
+            print!("{}", render_config(&app_config));
+            Ok(())
+        }
+        Command::Serve => run_server(app_config),
+        Command::Daemon => run_daemon(app_config),
+        Command::Rpc => rpc::run_rpc(app_config),
+        Command::Mcp => mcp::run_mcp(app_config),
     }
+}
 
+fn run_tui(app_config: AppConfig) -> Result<()> {
     let (mut terminal, guard) = setup_terminal()?;
     guard.install_panic_hook();
 
     let mut app = App::with_languages(
-        app_config.source_lang,
-        app_config.target_lang,
+        app_config.source_lang.clone(),
+        app_config.target_lang.clone(),
         app_config.compact_lang_display,
     );
-    let (tx, rx, worker) = start_translation_worker(app_config.core);
+    app.options = app_config.translation_options();
+    let (tx, rx, worker) =
+        start_translation_worker(app_config.backend, app_config.http, app_config.core);
     let result = run_app(&mut terminal, &mut app, &tx, &rx);
     shutdown_worker(tx, worker);
 
@@ -95,6 +130,7 @@ fn run_benchmark(config: AppConfig, cli: &CliArgs) -> Result<()> {
         "Hello, how are you?".to_string()
     };
 
+    println!("Backend: {}", config.backend);
     println!("Model: {}", config.core.model_path.display());
     println!(
         "Config: gpu_layers={} context_size={} threads={}",
@@ -110,27 +146,61 @@ fn run_benchmark(config: AppConfig, cli: &CliArgs) -> Result<()> {
     println!("Max new tokens: {max_new_tokens}");
 
     let startup_start = Instant::now();
-    let translator = GemmaTranslator::new(config.core)?.with_max_new_tokens(max_new_tokens);
+    let translator = create_translator(config.backend, config.http, config.core)?;
+    let options = TranslationOptions::new().with_max_new_tokens(max_new_tokens);
     let startup_elapsed = startup_start.elapsed();
     println!("Startup: {:.2?}", startup_elapsed);
 
     for run_idx in 0..warmup_runs {
         let start = Instant::now();
-        let _ = translator.translate(&text, &config.source_lang, &config.target_lang)?;
+        let output = translator.translate_with_options(
+            &text,
+            &config.source_lang,
+            &config.target_lang,
+            &options,
+        )?;
         let elapsed = start.elapsed();
-        println!("Warmup {}: {:.2?}", run_idx + 1, elapsed);
+        println!(
+            "Warmup {}: {:.2?} ({}; {})",
+            run_idx + 1,
+            elapsed,
+            format_generation(&output.stats),
+            format_prefix_cache(&output.stats)
+        );
     }
 
     let mut run_times = Vec::with_capacity(runs as usize);
-    let mut last_output = String::new();
+    let mut last_output: Option<TranslationOutput> = None;
+    let mut cache_hits = 0;
+    let mut generated_tokens = 0;
+    let mut generation_time = Duration::ZERO;
     for run_idx in 0..runs {
         let start = Instant::now();
-        let output = translator.translate(&text, &config.source_lang, &config.target_lang)?;
+        let output = translator.translate_with_options(
+            &text,
+            &config.source_lang,
+            &config.target_lang,
+            &options,
+        )?;
         let elapsed = start.elapsed();
-        println!("Run {}: {:.2?}", run_idx + 1, elapsed);
+        if output.stats.cached_prompt_tokens > 0 {
+            cache_hits += 1;
+        }
+        println!(
+            "Run {}: {:.2?} ({}; {})",
+            run_idx + 1,
+            elapsed,
+            format_generation(&output.stats),
+            format_prefix_cache(&output.stats)
+        );
         run_times.push(elapsed);
-        last_output = output;
+        generated_tokens += output.stats.generated_tokens;
+        generation_time += output.stats.generation;
+        last_output = Some(output);
     }
+    let last_output = last_output.unwrap_or_else(|| {
+        TranslationOutput::from_text("", &config.source_lang, &config.target_lang)
+    });
 
     let min = run_times.iter().copied().min().unwrap_or(Duration::ZERO);
     let max = run_times.iter().copied().max().unwrap_or(Duration::ZERO);
@@ -138,10 +208,27 @@ fn run_benchmark(config: AppConfig, cli: &CliArgs) -> Result<()> {
     let total_measured = run_times.iter().map(Duration::as_secs_f64).sum::<f64>();
 
     println!("Source: {text}");
-    println!("Target: {last_output}");
+    println!("Target: {}", last_output.text);
+    if !last_output.glossary_candidates.is_empty() {
+        let terms: Vec<String> = last_output
+            .glossary_candidates
+            .iter()
+            .map(|candidate| format!("{} -> {}", candidate.source_term, candidate.target_term))
+            .collect();
+        println!("Glossary terms: {}", terms.join(", "));
+    }
     println!("Average: {:.2?}", avg);
     println!("Min: {:.2?}", min);
     println!("Max: {:.2?}", max);
+    println!("Prefix cache hits: {cache_hits}/{runs}");
+    let total_stats = GenerationStats {
+        generated_tokens,
+        generation: generation_time,
+        ..GenerationStats::default()
+    };
+    if let Some(rate) = total_stats.tokens_per_second() {
+        println!("Generation speed: {rate:.1} tok/s");
+    }
     println!(
         "Measured total: {:.2?}",
         Duration::from_secs_f64(total_measured)
@@ -150,6 +237,29 @@ fn run_benchmark(config: AppConfig, cli: &CliArgs) -> Result<()> {
     Ok(())
 }
 
+fn format_generation(stats: &GenerationStats) -> String {
+    let stop = match stats.stop_reason {
+        StopReason::EndOfText => "end of text",
+        StopReason::MaxTokens => "max tokens",
+        StopReason::ContextFull => "context full",
+    };
+    format!(
+        "{} tokens generated, stopped at {stop}, prompt eval {:.2?}, generation {:.2?}",
+        stats.generated_tokens, stats.prompt_eval, stats.generation
+    )
+}
+
+fn format_prefix_cache(stats: &GenerationStats) -> String {
+    if stats.cached_prompt_tokens > 0 {
+        format!(
+            "prefix cache hit: {}/{} prompt tokens reused",
+            stats.cached_prompt_tokens, stats.prompt_tokens
+        )
+    } else {
+        format!("prefix cache miss: {} prompt tokens", stats.prompt_tokens)
+    }
+}
+
 fn duration_avg(values: &[Duration]) -> Duration {
     if values.is_empty() {
         return Duration::ZERO;
@@ -158,16 +268,299 @@ fn duration_avg(values: &[Duration]) -> Duration {
     Duration::from_secs_f64(total_secs / values.len() as f64)
 }
 
-fn run_stdin(config: AppConfig) -> Result<()> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+/// Translate `text`, or stdin when no text is given, and print the result
+fn run_translate(config: AppConfig, cli: &CliArgs) -> Result<()> {
+    let input = match (&cli.text, &cli.input) {
+        (Some(text), _) => text.clone(),
+        (None, Some(path)) => {
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?
+        }
+        (None, None) => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
     if input.trim().is_empty() {
-        return Err(anyhow::anyhow!("stdin is empty"));
+        return Err(anyhow::anyhow!("no input text"));
+    }
+    let format = cli
+        .document_format
+        .or_else(|| cli.input.as_deref().and_then(DocumentFormat::from_path))
+        .unwrap_or_default();
+    let use_daemon = !cli.no_daemon;
+    let options = config.translation_options();
+
+    if format != DocumentFormat::Text {
+        if cli.json {
+            return Err(anyhow::anyhow!("--json only applies to plain text"));
+        }
+        // Resource files already in --output keep their translations; only new keys are added
+        let existing = match cli.output.as_deref() {
+            Some(path)
+                if matches!(format, DocumentFormat::Json | DocumentFormat::Yaml)
+                    && path.exists() =>
+            {
+                Some(
+                    fs::read_to_string(path)
+                        .with_context(|| format!("cannot read {}", path.display()))?,
+                )
+            }
+            _ => None,
+        };
+        let translator = document_translator(&config, use_daemon)?;
+        let document = update_document(
+            translator.as_ref(),
+            format,
+            &input,
+            existing.as_deref(),
+            &config.source_lang,
+            &config.target_lang,
+            &options,
+        )?;
+        return write_translation(cli.output.as_deref(), &document);
+    }
+
+    let daemon_output = if use_daemon {
+        translate_via_daemon(
+            &config.daemon_socket,
+            &daemon_settings(&config),
+            &input,
+            &config.source_lang,
+            &config.target_lang,
+            &options,
+        )?
+    } else {
+        None
+    };
+    let output = match daemon_output {
+        Some(output) => output,
+        None => {
+            let translator = create_translator(config.backend, config.http, config.core)?;
+            translator.translate_with_options(
+                &input,
+                &config.source_lang,
+                &config.target_lang,
+                &options,
+            )?
+        }
+    };
+    for warning in &output.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    let rendered = if cli.json {
+        serde_json::to_string(&output)?
+    } else {
+        output.text
+    };
+    write_translation(cli.output.as_deref(), &format!("{rendered}\n"))
+}
+
+/// Write a translation to `path`, or to stdout without one
+fn write_translation(path: Option<&Path>, text: &str) -> Result<()> {
+    match path {
+        Some(path) => {
+            fs::write(path, text).with_context(|| format!("cannot write {}", path.display()))
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(text.as_bytes())?;
+            stdout.flush()?;
+            Ok(())
+        }
+    }
+}
+
+/// The running daemon when one answers, otherwise a translator loaded in-process
+fn document_translator(config: &AppConfig, use_daemon: bool) -> Result<Box<dyn Translator>> {
+    #[cfg(unix)]
+    if use_daemon
+        && let Some(client) = connect_daemon(&config.daemon_socket, &daemon_settings(config))
+    {
+        return Ok(Box::new(DaemonTranslator {
+            client: RefCell::new(client),
+            socket: config.daemon_socket.clone(),
+        }));
+    }
+    #[cfg(not(unix))]
+    let _ = use_daemon;
+    Ok(create_translator(
+        config.backend,
+        config.http.clone(),
+        config.core.clone(),
+    )?)
+}
+
+/// Translator settings a daemon must share to answer for `config`
+///
+/// Covers everything that changes a translation but cannot travel with a request: the
+/// backend and its model, context size, glossary and sampling.
+fn daemon_settings(config: &AppConfig) -> Value {
+    let backend = match config.backend {
+        Backend::Gemma => json!({
+            "model_path": config.core.model_path,
+            "context_size": config.core.context_size,
+        }),
+        Backend::Http => json!({
+            "url": config.http.url,
+            "api": config.http.api,
+            "model": config.http.model,
+        }),
+        Backend::Mock => json!({}),
+    };
+    json!({
+        "backend": config.backend.to_string(),
+        "model": backend,
+        "glossary": config.core.glossary,
+        "sampling": config.core.sampling,
+    })
+}
+
+/// Connect to the daemon behind `socket` when it runs with `settings`
+///
+/// `None` when no daemon answers there or when it was started with other settings.
+#[cfg(unix)]
+fn connect_daemon(socket: &Path, settings: &Value) -> Option<petit_server::daemon::DaemonClient> {
+    if !socket.exists() {
+        return None;
+    }
+    let mut client = match petit_server::daemon::DaemonClient::connect(socket) {
+        Ok(client) => client,
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("Note: not using the daemon: {err}; translating in-process");
+            return None;
+        }
+        // A socket file without a listener is left over from a daemon that did not shut down
+        Err(_) => return None,
+    };
+    if client.settings().ok().as_ref() != Some(settings) {
+        eprintln!(
+            "Note: the daemon at {} runs with other settings; translating in-process",
+            socket.display()
+        );
+        return None;
     }
+    Some(client)
+}
+
+/// Translates document segments one request at a time on a running daemon
+#[cfg(unix)]
+struct DaemonTranslator {
+    client: RefCell<petit_server::daemon::DaemonClient>,
+    socket: PathBuf,
+}
 
-    let translator = GemmaTranslator::new(config.core)?;
-    let output = translator.translate(&input, &config.source_lang, &config.target_lang)?;
-    println!("{output}");
+#[cfg(unix)]
+impl Translator for DaemonTranslator {
+    fn translate_with_options(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        options: &TranslationOptions,
+    ) -> petit_core::Result<TranslationOutput> {
+        self.client
+            .borrow_mut()
+            .translate(text, source_lang, target_lang, options)
+            .map_err(|err| {
+                CoreError::Inference(format!("daemon at {}: {err}", self.socket.display()))
+            })
+    }
+
+    fn supported_languages(&self) -> &[&str] {
+        petit_core::language::supported_languages()
+    }
+}
+
+/// Translate on the daemon behind `socket`, or `None` when no daemon with `settings` answers
+#[cfg(unix)]
+fn translate_via_daemon(
+    socket: &Path,
+    settings: &Value,
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+    options: &TranslationOptions,
+) -> Result<Option<TranslationOutput>> {
+    let Some(mut client) = connect_daemon(socket, settings) else {
+        return Ok(None);
+    };
+    let output = client
+        .translate(text, source_lang, target_lang, options)
+        .map_err(|err| anyhow::anyhow!("daemon at {}: {err}", socket.display()))?;
+    Ok(Some(output))
+}
+
+#[cfg(not(unix))]
+fn translate_via_daemon(
+    _socket: &Path,
+    _settings: &Value,
+    _text: &str,
+    _source_lang: &str,
+    _target_lang: &str,
+    _options: &TranslationOptions,
+) -> Result<Option<TranslationOutput>> {
+    Ok(None)
+}
+
+/// Check the glossary or print the terms that apply to `text`
+fn run_glossary(mut config: AppConfig, action: GlossaryAction, text: Option<&str>) -> Result<()> {
+    // Inspecting the glossary should work whether or not translation uses it
+    config.core.glossary.enabled = true;
+    let store = GlossaryStore::from_config(&config.core.glossary)?;
+    match action {
+        GlossaryAction::Check => println!(
+            "{}: {} terms",
+            config.core.glossary.path.display(),
+            store.len()
+        ),
+        GlossaryAction::Lookup => {
+            let candidates = store.select_candidates(
+                &config.source_lang,
+                &config.target_lang,
+                text.unwrap_or_default(),
+            )?;
+            for candidate in candidates {
+                println!("{}\t{}", candidate.source_term, candidate.target_term);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn run_daemon(config: AppConfig) -> Result<()> {
+    let settings = daemon_settings(&config);
+    let AppConfig {
+        backend,
+        http,
+        server,
+        daemon_socket,
+        core,
+        ..
+    } = config;
+    petit_server::daemon::serve_daemon(&daemon_socket, server.queue_size, settings, move || {
+        create_translator(backend, http, core)
+    })?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_config: AppConfig) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "`petit daemon` needs Unix domain sockets, which this platform lacks"
+    ))
+}
+
+fn run_server(config: AppConfig) -> Result<()> {
+    let AppConfig {
+        backend,
+        http,
+        server,
+        core,
+        ..
+    } = config;
+    petit_server::serve(server, move || create_translator(backend, http, core))?;
     Ok(())
 }
 
@@ -205,6 +598,10 @@ fn run_app(
                 WorkerEvent::TranslatorInitializing => app.begin_worker_initialization(),
                 WorkerEvent::TranslatorReady => app.apply_worker_ready(),
                 WorkerEvent::TranslatorInitFailed(err) => app.apply_worker_init_error(err),
+                WorkerEvent::TranslationProgress(piece) => app.apply_translation_progress(&piece),
+                WorkerEvent::Translation(TranslationResponse::Cancelled) => {
+                    app.apply_translation_cancelled()
+                }
                 WorkerEvent::Translation(response) => {
                     app.apply_translation_result(response.into_result())
                 }
@@ -234,6 +631,11 @@ fn handle_key_event(app: &mut App, key: KeyEvent, tx: &Sender<TranslationRequest
         return;
     }
 
+    if app.is_loading && is_cancel_shortcut(&key) {
+        app.cancel_translation();
+        return;
+    }
+
     if is_translate_shortcut(&key) {
         request_translation(app, tx);
         return;
@@ -287,11 +689,7 @@ fn handle_key_event(app: &mut App, key: KeyEvent, tx: &Sender<TranslationRequest
             Focus::Input => app.scroll_input(3),
             Focus::Output => app.scroll_output(3),
         },
-        KeyCode::Char(ch) => {
-            if is_text_input(&key) {
-                app.insert_char(ch);
-            }
-        }
+        KeyCode::Char(ch) if is_text_input(&key) => app.insert_char(ch),
         _ => {}
     }
 }
@@ -314,11 +712,7 @@ fn handle_language_edit_key(app: &mut App, key: KeyEvent) {
         KeyCode::Right => app.move_right(),
         KeyCode::Home => app.move_home(),
         KeyCode::End => app.move_end(),
-        KeyCode::Char(ch) => {
-            if is_text_input(&key) {
-                app.insert_char(ch);
-            }
-        }
+        KeyCode::Char(ch) if is_text_input(&key) => app.insert_char(ch),
         _ => {}
     }
 }
@@ -331,6 +725,11 @@ fn is_translate_shortcut(key: &KeyEvent) -> bool {
     key.code == KeyCode::Enter
 }
 
+fn is_cancel_shortcut(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Esc
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
 fn shutdown_worker(request_tx: Sender<TranslationRequest>, worker: thread::JoinHandle<()>) {
     drop(request_tx);
     if worker.is_finished() {
@@ -353,7 +752,35 @@ fn request_translation(app: &mut App, tx: &Sender<TranslationRequest>) {
     }
 }
 
+/// Create the translator for the selected backend
+fn create_translator(
+    backend: Backend,
+    http: HttpConfig,
+    config: Config,
+) -> petit_core::Result<Box<dyn Translator>> {
+    match backend {
+        Backend::Gemma => Ok(Box::new(GemmaTranslator::new(config)?)),
+        Backend::Http => {
+            config.sampling.validate()?;
+            let glossary_store = Some(GlossaryStore::from_config(&config.glossary)?);
+            Ok(Box::new(
+                HttpTranslator::new(http)?
+                    .with_sampling(config.sampling)
+                    .with_glossary_store(glossary_store),
+            ))
+        }
+        #[cfg(any(test, feature = "mock"))]
+        Backend::Mock => Ok(Box::new(MockTranslator::new())),
+        #[cfg(not(any(test, feature = "mock")))]
+        Backend::Mock => Err(CoreError::Config(
+            "the mock backend requires building petit-tui with the `mock` feature".to_string(),
+        )),
+    }
+}
+
 fn start_translation_worker(
+    backend: Backend,
+    http: HttpConfig,
     config: Config,
 ) -> (
     Sender<TranslationRequest>,
@@ -366,9 +793,9 @@ fn start_translation_worker(
     let worker = thread::spawn(move || {
         let _ = response_tx.send(WorkerEvent::TranslatorInitializing);
 
-        let mut translator: Option<GemmaTranslator> = None;
+        let mut translator: Option<Box<dyn Translator>> = None;
         let mut init_error: Option<String> = None;
-        match GemmaTranslator::new(config) {
+        match create_translator(backend, http, config) {
             Ok(instance) => {
                 translator = Some(instance);
                 let _ = response_tx.send(WorkerEvent::TranslatorReady);
@@ -383,9 +810,22 @@ fn start_translation_worker(
         for request in request_rx {
             let response = match translator.as_ref() {
                 Some(instance) => instance
-                    .translate(&request.text, &request.source_lang, &request.target_lang)
+                    .translate_streaming(
+                        &request.text,
+                        &request.source_lang,
+                        &request.target_lang,
+                        &request.options,
+                        &request.cancel,
+                        &mut |piece| {
+                            let _ = response_tx
+                                .send(WorkerEvent::TranslationProgress(piece.to_string()));
+                        },
+                    )
                     .map(TranslationResponse::Ok)
-                    .unwrap_or_else(|err| TranslationResponse::Err(err.to_string())),
+                    .unwrap_or_else(|err| match err {
+                        CoreError::Cancelled => TranslationResponse::Cancelled,
+                        err => TranslationResponse::Err(err.to_string()),
+                    }),
                 None => {
                     let err_text = init_error
                         .as_deref()
@@ -407,20 +847,23 @@ enum WorkerEvent {
     TranslatorInitializing,
     TranslatorReady,
     TranslatorInitFailed(String),
+    TranslationProgress(String),
     Translation(TranslationResponse),
 }
 
 #[derive(Debug)]
 enum TranslationResponse {
-    Ok(String),
+    Ok(TranslationOutput),
     Err(String),
+    Cancelled,
 }
 
 impl TranslationResponse {
-    fn into_result(self) -> Result<String, String> {
+    fn into_result(self) -> Result<TranslationOutput, String> {
         match self {
-            TranslationResponse::Ok(text) => Ok(text),
+            TranslationResponse::Ok(output) => Ok(output),
             TranslationResponse::Err(err) => Err(err),
+            TranslationResponse::Cancelled => Err(CoreError::Cancelled.to_string()),
         }
     }
 }
@@ -471,6 +914,73 @@ mod tests {
     use super::*;
     use petit_core::config::GlossaryConfig;
 
+    #[cfg(unix)]
+    #[test]
+    fn stdin_falls_back_without_a_listening_daemon() {
+        let socket =
+            std::env::temp_dir().join(format!("petit-stdin-daemon-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let missing = translate_via_daemon(
+            &socket,
+            &Value::Null,
+            "Hello",
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("a missing socket should fall back");
+
+        // Leave a socket file behind without a listener, like a crashed daemon
+        drop(std::os::unix::net::UnixListener::bind(&socket).expect("socket should bind"));
+        let stale = translate_via_daemon(
+            &socket,
+            &Value::Null,
+            "Hello",
+            "en",
+            "fr",
+            &TranslationOptions::default(),
+        )
+        .expect("a stale socket should fall back");
+        let _ = std::fs::remove_file(&socket);
+
+        assert!(missing.is_none());
+        assert!(stale.is_none());
+    }
+
+    #[test]
+    fn daemon_settings_track_translation_settings() {
+        let mut config = AppConfig {
+            backend: Backend::Gemma,
+            http: HttpConfig::default(),
+            server: Default::default(),
+            daemon_socket: PathBuf::from("/tmp/petit-settings-test.sock"),
+            core: Config {
+                model_path: PathBuf::from("models/a.gguf"),
+                glossary: GlossaryConfig {
+                    enabled: true,
+                    ..GlossaryConfig::default()
+                },
+                ..Config::default()
+            },
+            source_lang: "auto".to_string(),
+            target_lang: "fr".to_string(),
+            placeholders: None,
+            stdin_mode: true,
+            compact_lang_display: false,
+        };
+        let base = daemon_settings(&config);
+
+        config.core.threads = 8;
+        config.target_lang = "de".to_string();
+        assert_eq!(daemon_settings(&config), base);
+
+        config.core.glossary.enabled = false;
+        assert_ne!(daemon_settings(&config), base);
+        config.core.glossary.enabled = true;
+        config.core.sampling.temperature = 0.7;
+        assert_ne!(daemon_settings(&config), base);
+    }
+
     #[test]
     fn translate_shortcuts_match_expected_keys() {
         let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
@@ -508,6 +1018,35 @@ mod tests {
         assert!(!is_quit_shortcut(&q));
     }
 
+    #[test]
+    fn cancel_shortcuts_match_expected_keys() {
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
+
+        assert!(is_cancel_shortcut(&esc));
+        assert!(is_cancel_shortcut(&ctrl_c));
+        assert!(!is_cancel_shortcut(&c));
+    }
+
+    #[test]
+    fn cancel_shortcut_cancels_in_flight_translation() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = App::default();
+        app.insert_str("Hello");
+        request_translation(&mut app, &tx);
+        let request = rx.try_recv().expect("request should be sent");
+
+        handle_key_event(
+            &mut app,
+            KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+            &tx,
+        );
+
+        assert!(request.cancel.is_cancelled());
+        assert!(app.is_cancelling());
+    }
+
     #[test]
     fn quit_shortcut_sets_should_quit() {
         let (tx, _rx) = mpsc::channel();
@@ -522,6 +1061,45 @@ mod tests {
         assert!(app.should_quit);
     }
 
+    #[test]
+    fn format_prefix_cache_reports_hits_and_misses() {
+        let hit = GenerationStats {
+            prompt_tokens: 120,
+            cached_prompt_tokens: 96,
+            ..GenerationStats::default()
+        };
+        let miss = GenerationStats {
+            prompt_tokens: 120,
+            ..GenerationStats::default()
+        };
+
+        assert_eq!(
+            format_prefix_cache(&hit),
+            "prefix cache hit: 96/120 prompt tokens reused"
+        );
+        assert_eq!(
+            format_prefix_cache(&miss),
+            "prefix cache miss: 120 prompt tokens"
+        );
+    }
+
+    #[test]
+    fn format_generation_reports_tokens_stop_reason_and_timings() {
+        let stats = GenerationStats {
+            prompt_tokens: 80,
+            generated_tokens: 12,
+            stop_reason: StopReason::MaxTokens,
+            prompt_eval: Duration::from_millis(40),
+            generation: Duration::from_millis(300),
+            ..GenerationStats::default()
+        };
+
+        assert_eq!(
+            format_generation(&stats),
+            "12 tokens generated, stopped at max tokens, prompt eval 40.00ms, generation 300.00ms"
+        );
+    }
+
     #[test]
     fn shutdown_worker_does_not_block_on_running_thread() {
         let (tx, _rx) = mpsc::channel();
@@ -553,9 +1131,11 @@ mod tests {
             log_to_file: false,
             log_path: std::path::PathBuf::from("logs/test-llama.log"),
             glossary: Default::default(),
+            sampling: Default::default(),
         };
 
-        let (tx, rx, worker) = start_translation_worker(config);
+        let (tx, rx, worker) =
+            start_translation_worker(Backend::Gemma, HttpConfig::default(), config);
 
         let first = rx
             .recv_timeout(Duration::from_secs(5))
@@ -579,6 +1159,48 @@ mod tests {
         worker.join().expect("worker thread should exit cleanly");
     }
 
+    #[test]
+    fn worker_translates_with_mock_backend_without_model() {
+        let config = Config {
+            model_path: std::path::PathBuf::from("models/does-not-exist.gguf"),
+            gpu_layers: 0,
+            context_size: 2048,
+            threads: 1,
+            log_to_file: false,
+            log_path: std::path::PathBuf::from("logs/test-llama.log"),
+            glossary: Default::default(),
+            sampling: Default::default(),
+        };
+
+        let (tx, rx, worker) =
+            start_translation_worker(Backend::Mock, HttpConfig::default(), config);
+        let mut app = App::default();
+        app.insert_str("hello world");
+        let request = app.begin_translation().expect("request should start");
+        tx.send(request).expect("worker should accept request");
+
+        let mut pieces = String::new();
+        loop {
+            match rx
+                .recv_timeout(Duration::from_secs(5))
+                .expect("worker should respond")
+            {
+                WorkerEvent::TranslatorInitializing | WorkerEvent::TranslatorReady => {}
+                WorkerEvent::TranslationProgress(piece) => pieces.push_str(&piece),
+                WorkerEvent::Translation(response) => {
+                    let output = response.into_result().expect("mock should translate");
+                    assert_eq!(output.text, "HELLO WORLD");
+                    break;
+                }
+                other => panic!("unexpected worker event: {other:?}"),
+            }
+        }
+        assert_eq!(pieces, "HELLO WORLD");
+
+        drop(tx);
+        worker.join().expect("worker thread should exit cleanly");
+    }
+
     #[test]
     fn worker_reports_glossary_failure_before_missing_model() {
         let missing_model = std::env::temp_dir().join(format!(
@@ -609,9 +1231,11 @@ mod tests {
                 embedding_model_dir: model_dir,
                 max_matches: 4,
             },
+            sampling: Default::default(),
         };
 
-        let (tx, rx, worker) = start_translation_worker(config);
+        let (tx, rx, worker) =
+            start_translation_worker(Backend::Gemma, HttpConfig::default(), config);
 
         let first = rx
             .recv_timeout(Duration::from_secs(5))
@@ -673,9 +1297,11 @@ mod tests {
                 embedding_model_dir: model_dir.clone(),
                 max_matches: 4,
             },
+            sampling: Default::default(),
         };
 
-        let (tx, rx, worker) = start_translation_worker(config);
+        let (tx, rx, worker) =
+            start_translation_worker(Backend::Gemma, HttpConfig::default(), config);
 
         let first = rx
             .recv_timeout(Duration::from_secs(5))
@@ -0,0 +1,475 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Model Context Protocol server over stdio
+//!
+//! `petit mcp` lets agent tooling call the local translator as an MCP tool. Messages are
+//! line-delimited JSON-RPC 2.0 on stdin and stdout, as the MCP stdio transport specifies.
+//!
+//! Tools:
+//! - `translate` `{text, target_lang?, source_lang?, formality?, domain?, instructions?}`
+//! - `list_languages`
+//! - `lookup_glossary` `{text, target_lang?, source_lang?}`: `GlossaryStore::select_candidates`
+//!
+//! The server listens on nothing but stdio and refuses the `http` backend, so inference and
+//! glossary retrieval stay in-process. The translator loads on the first `translate` call.
+
+use anyhow::{Result, bail};
+use petit_core::config::GlossaryConfig;
+use petit_core::language::validate_pair;
+use petit_core::{Config, GlossaryStore, HttpConfig, TranslationOptions, Translator};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+
+use crate::config::{AppConfig, Backend};
+use crate::create_translator;
+use crate::rpc::languages;
+
+/// Newest protocol revision this server implements
+const PROTOCOL_VERSION: &str = "2025-06-18";
+/// Revisions accepted from clients; others get [`PROTOCOL_VERSION`]
+const SUPPORTED_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve MCP on stdin and stdout until end of input
+pub fn run_mcp(config: AppConfig) -> Result<()> {
+    serve(io::stdin().lock(), io::stdout().lock(), config)
+}
+
+fn serve<R: BufRead, W: Write>(input: R, mut output: W, config: AppConfig) -> Result<()> {
+    if config.backend == Backend::Http {
+        bail!("`petit mcp` translates in-process; use the gemma backend instead of --backend http");
+    }
+
+    let mut server = McpServer::new(config);
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_line(&line) {
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+struct McpError {
+    code: i64,
+    message: String,
+}
+
+impl McpError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct TranslateArgs {
+    text: String,
+    #[serde(default)]
+    source_lang: Option<String>,
+    #[serde(default)]
+    target_lang: Option<String>,
+    #[serde(flatten)]
+    options: TranslationOptions,
+}
+
+#[derive(Deserialize)]
+struct LookupArgs {
+    text: String,
+    #[serde(default)]
+    source_lang: Option<String>,
+    #[serde(default)]
+    target_lang: Option<String>,
+}
+
+struct McpServer {
+    backend: Backend,
+    http: HttpConfig,
+    core: Config,
+    source_lang: String,
+    target_lang: String,
+    glossary_config: GlossaryConfig,
+    /// Loaded on the first `translate` call
+    translator: Option<Box<dyn Translator>>,
+    /// Loaded on the first `lookup_glossary` call
+    glossary: Option<GlossaryStore>,
+}
+
+impl McpServer {
+    fn new(config: AppConfig) -> Self {
+        Self {
+            backend: config.backend,
+            http: config.http,
+            glossary_config: config.core.glossary.clone(),
+            core: config.core,
+            source_lang: config.source_lang,
+            target_lang: config.target_lang,
+            translator: None,
+            glossary: None,
+        }
+    }
+
+    /// Answer one message, or `None` for notifications
+    fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(err) => {
+                return Some(error_response(
+                    &Value::Null,
+                    McpError::new(PARSE_ERROR, format!("parse error: {err}")),
+                ));
+            }
+        };
+
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str);
+        let (Some(method), true) = (method, message.get("jsonrpc") == Some(&json!("2.0"))) else {
+            // Responses to server requests are never expected, so ignore them quietly
+            let is_response = message.get("result").is_some() || message.get("error").is_some();
+            return (!is_response).then(|| {
+                error_response(
+                    &id.unwrap_or(Value::Null),
+                    McpError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request"),
+                )
+            });
+        };
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(params),
+            other => Err(McpError::new(
+                METHOD_NOT_FOUND,
+                format!("method not found: {other}"),
+            )),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => error_response(&id, err),
+        })
+    }
+
+    /// Run a tool; failures inside the tool become `isError` results the model can read
+    fn call_tool(&mut self, params: Value) -> Result<Value, McpError> {
+        let params: CallParams = parse_params(params)?;
+        let outcome = match params.name.as_str() {
+            "translate" => self.translate(parse_params(params.arguments)?),
+            "list_languages" => Ok(languages()),
+            "lookup_glossary" => self.lookup(parse_params(params.arguments)?),
+            other => {
+                return Err(McpError::new(
+                    INVALID_PARAMS,
+                    format!("unknown tool: {other}"),
+                ));
+            }
+        };
+
+        Ok(match outcome {
+            Ok(structured) => {
+                let text = match &structured["text"] {
+                    Value::String(text) => text.clone(),
+                    _ => structured.to_string(),
+                };
+                json!({
+                    "content": [{ "type": "text", "text": text }],
+                    "structuredContent": wrap_structured(structured),
+                    "isError": false,
+                })
+            }
+            Err(message) => json!({
+                "content": [{ "type": "text", "text": message }],
+                "isError": true,
+            }),
+        })
+    }
+
+    fn translate(&mut self, args: TranslateArgs) -> Result<Value, String> {
+        if args.text.trim().is_empty() {
+            return Err("text must not be empty".to_string());
+        }
+        let (source_lang, target_lang) = self.languages(args.source_lang, args.target_lang)?;
+        args.options.validate().map_err(|err| err.to_string())?;
+
+        let translator = match &mut self.translator {
+            Some(translator) => translator,
+            None => self.translator.insert(
+                create_translator(self.backend, self.http.clone(), self.core.clone())
+                    .map_err(|err| format!("failed to load translator: {err}"))?,
+            ),
+        };
+        let output = translator
+            .translate_with_options(&args.text, &source_lang, &target_lang, &args.options)
+            .map_err(|err| err.to_string())?;
+        serde_json::to_value(output).map_err(|err| err.to_string())
+    }
+
+    fn lookup(&mut self, args: LookupArgs) -> Result<Value, String> {
+        let (source_lang, target_lang) = self.languages(args.source_lang, args.target_lang)?;
+        let store = match &mut self.glossary {
+            Some(store) => store,
+            None => self.glossary.insert(
+                GlossaryStore::from_config(&self.glossary_config).map_err(|err| err.to_string())?,
+            ),
+        };
+        let candidates = store
+            .select_candidates(&source_lang, &target_lang, &args.text)
+            .map_err(|err| err.to_string())?;
+        Ok(json!(candidates))
+    }
+
+    /// Fill in the configured languages and validate the pair
+    fn languages(
+        &self,
+        source_lang: Option<String>,
+        target_lang: Option<String>,
+    ) -> Result<(String, String), String> {
+        let source_lang = source_lang.unwrap_or_else(|| self.source_lang.clone());
+        let target_lang = target_lang.unwrap_or_else(|| self.target_lang.clone());
+        validate_pair(&source_lang, &target_lang).map_err(|err| err.to_string())?;
+        Ok((source_lang, target_lang))
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = if SUPPORTED_VERSIONS.contains(&requested) {
+        requested
+    } else {
+        PROTOCOL_VERSION
+    };
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "petit", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Local translation with TranslateGemma. Call list_languages for the \
+            supported codes; source_lang may be `auto`.",
+    })
+}
+
+fn tools() -> Value {
+    let language = |description: &str| json!({ "type": "string", "description": description });
+    json!([
+        {
+            "name": "translate",
+            "description": "Translate text with the local TranslateGemma model.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to translate" },
+                    "source_lang": language("Source language code, or `auto` to infer it"),
+                    "target_lang": language("Target language code"),
+                    "formality": { "type": "string", "enum": ["formal", "informal"] },
+                    "domain": { "type": "string", "description": "Subject area, e.g. `legal`" },
+                    "instructions": {
+                        "type": "string",
+                        "description": "Extra instructions for the translator",
+                    },
+                },
+                "required": ["text"],
+            },
+        },
+        {
+            "name": "list_languages",
+            "description": "List the supported language codes and names.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "lookup_glossary",
+            "description": "Find configured glossary terms that apply to a text.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Source text" },
+                    "source_lang": language("Source language code, or `auto`"),
+                    "target_lang": language("Target language code"),
+                },
+                "required": ["text"],
+            },
+        },
+    ])
+}
+
+/// `structuredContent` must be an object, so lists are wrapped
+fn wrap_structured(value: Value) -> Value {
+    match value {
+        Value::Object(_) => value,
+        other => json!({ "items": other }),
+    }
+}
+
+fn error_response(id: &Value, error: McpError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, McpError> {
+    serde_json::from_value(params)
+        .map_err(|err| McpError::new(INVALID_PARAMS, format!("invalid arguments: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn test_config(backend: Backend) -> AppConfig {
+        AppConfig {
+            backend,
+            http: HttpConfig::default(),
+            server: Default::default(),
+            daemon_socket: PathBuf::from("/tmp/petit-mcp-test.sock"),
+            core: Config {
+                model_path: PathBuf::from("models/does-not-exist.gguf"),
+                gpu_layers: 0,
+                context_size: 2048,
+                threads: 1,
+                log_to_file: false,
+                log_path: PathBuf::from("logs/test-llama.log"),
+                glossary: Default::default(),
+                sampling: Default::default(),
+            },
+            source_lang: "auto".to_string(),
+            target_lang: "fr".to_string(),
+            placeholders: None,
+            stdin_mode: false,
+            compact_lang_display: false,
+        }
+    }
+
+    fn run(input: &[&str]) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve(
+            input.join("\n").as_bytes(),
+            &mut output,
+            test_config(Backend::Mock),
+        )
+        .expect("mcp should serve");
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn response(messages: &[Value], id: i64) -> &Value {
+        messages
+            .iter()
+            .find(|message| message["id"] == id)
+            .unwrap_or_else(|| panic!("no response for id {id}"))
+    }
+
+    #[test]
+    fn initialize_negotiates_version_and_lists_tools() {
+        let messages = run(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"test","version":"1"}}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"ping"}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"resources/list"}"#,
+        ]);
+
+        assert_eq!(messages.len(), 5);
+        let init = &response(&messages, 1)["result"];
+        assert_eq!(init["protocolVersion"], "2024-11-05");
+        assert_eq!(init["serverInfo"]["name"], "petit");
+        assert!(init["capabilities"]["tools"].is_object());
+        let names: Vec<&str> = response(&messages, 2)["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["translate", "list_languages", "lookup_glossary"]);
+        assert_eq!(
+            response(&messages, 3)["result"]["protocolVersion"],
+            PROTOCOL_VERSION
+        );
+        assert_eq!(response(&messages, 4)["result"], json!({}));
+        assert_eq!(response(&messages, 5)["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn tools_call_translates_and_looks_up_languages() {
+        let messages = run(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"translate","arguments":{"text":"hello world","target_lang":"de","formality":"formal"}}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"list_languages","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"lookup_glossary","arguments":{"text":"hello","source_lang":"en"}}}"#,
+        ]);
+
+        let translated = &response(&messages, 1)["result"];
+        assert_eq!(translated["isError"], false);
+        assert_eq!(translated["content"][0]["text"], "HELLO WORLD");
+        assert_eq!(translated["structuredContent"]["target_lang"], "de");
+        let languages = &response(&messages, 2)["result"]["structuredContent"]["items"];
+        assert!(
+            languages
+                .as_array()
+                .unwrap()
+                .contains(&json!({ "code": "fr", "name": "French" }))
+        );
+        assert_eq!(
+            response(&messages, 3)["result"]["structuredContent"]["items"],
+            json!([])
+        );
+    }
+
+    #[test]
+    fn tool_failures_are_reported_to_the_model() {
+        let messages = run(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"translate","arguments":{"text":"hi","target_lang":"xx"}}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"translate","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"summarize","arguments":{}}}"#,
+            "not json",
+        ]);
+
+        let unsupported = &response(&messages, 1)["result"];
+        assert_eq!(unsupported["isError"], true);
+        assert!(
+            unsupported["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("xx")
+        );
+        assert_eq!(response(&messages, 2)["error"]["code"], INVALID_PARAMS);
+        assert_eq!(response(&messages, 3)["error"]["code"], INVALID_PARAMS);
+        assert!(
+            messages
+                .iter()
+                .any(|message| message["error"]["code"] == PARSE_ERROR)
+        );
+    }
+
+    #[test]
+    fn http_backend_is_refused() {
+        let err = serve("".as_bytes(), Vec::new(), test_config(Backend::Http))
+            .expect_err("http backend should be refused");
+
+        assert!(err.to_string().contains("--backend http"));
+    }
+}
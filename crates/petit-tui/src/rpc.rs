@@ -0,0 +1,562 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! JSON-RPC 2.0 over stdio for editor integrations
+//!
+//! `petit rpc` reads one JSON-RPC message per line on stdin and writes one per line on
+//! stdout. Translations go through the TUI's worker thread (`start_translation_worker`), so
+//! they run one at a time in arrival order.
+//!
+//! Methods:
+//! - `translate` `{text, source_lang?, target_lang?, options?}`: the translation output
+//! - `translateStream`: like `translate`, sending `progress` notifications `{id, text}` for
+//!   every generated piece before the result
+//! - `cancel` `{id}`: cancel a queued or running translation, which then fails with `-32800`
+//! - `languages`: `[{code, name}]`
+//! - `glossary.lookup` `{text, source_lang?, target_lang?}`: glossary terms for the text
+//! - `shutdown`: cancel pending translations, answer `null` and exit
+//!
+//! Translator startup is reported with `status` notifications `{state, error?}`.
+
+use anyhow::Result;
+use petit_core::config::GlossaryConfig;
+use petit_core::language::{language_name, supported_languages, validate_pair};
+use petit_core::{CancellationToken, GlossaryStore, TranslationOptions};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::app::TranslationRequest;
+use crate::config::AppConfig;
+use crate::{TranslationResponse, WorkerEvent, start_translation_worker};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// Serve JSON-RPC on stdin and stdout until `shutdown` or end of input
+pub fn run_rpc(config: AppConfig) -> Result<()> {
+    serve(io::stdin().lock(), io::stdout(), config)
+}
+
+fn serve<R, W>(input: R, output: W, config: AppConfig) -> Result<()>
+where
+    R: BufRead,
+    W: Write + Send + 'static,
+{
+    let output = Output(Arc::new(Mutex::new(Box::new(output))));
+    let pending = PendingQueue::default();
+    let glossary_config = config.core.glossary.clone();
+    let (tx, events, worker) = start_translation_worker(config.backend, config.http, config.core);
+    let forwarder = {
+        let pending = pending.clone();
+        let output = output.clone();
+        thread::spawn(move || forward_events(events, &pending, &output))
+    };
+
+    let mut session = Session {
+        tx,
+        output,
+        pending,
+        source_lang: config.source_lang,
+        target_lang: config.target_lang,
+        glossary_config,
+        glossary: None,
+    };
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() && session.handle_line(&line) == Flow::Shutdown {
+            break;
+        }
+    }
+
+    // Queued translations finish (or fail as cancelled) before the process exits
+    drop(session);
+    let _ = worker.join();
+    let _ = forwarder.join();
+    Ok(())
+}
+
+/// Line-oriented writer shared by the reader loop and the event thread
+#[derive(Clone)]
+struct Output(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl Output {
+    fn send(&self, message: &Value) {
+        if let Ok(mut output) = self.0.lock() {
+            let _ = writeln!(output, "{message}");
+            let _ = output.flush();
+        }
+    }
+
+    fn result(&self, id: &Value, result: Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+    }
+
+    fn error(&self, id: &Value, error: RpcError) {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }));
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Translation sent to the worker and not answered yet
+struct Pending {
+    id: Value,
+    stream: bool,
+    cancel: CancellationToken,
+}
+
+/// Pending translations in worker order
+type PendingQueue = Arc<Mutex<VecDeque<Pending>>>;
+
+#[derive(PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Shutdown,
+}
+
+#[derive(Deserialize)]
+struct TranslateParams {
+    text: String,
+    #[serde(default)]
+    source_lang: Option<String>,
+    #[serde(default)]
+    target_lang: Option<String>,
+    #[serde(default)]
+    options: TranslationOptions,
+}
+
+#[derive(Deserialize)]
+struct CancelParams {
+    id: Value,
+}
+
+#[derive(Deserialize)]
+struct LookupParams {
+    text: String,
+    #[serde(default)]
+    source_lang: Option<String>,
+    #[serde(default)]
+    target_lang: Option<String>,
+}
+
+struct Session {
+    tx: Sender<TranslationRequest>,
+    output: Output,
+    pending: PendingQueue,
+    source_lang: String,
+    target_lang: String,
+    glossary_config: GlossaryConfig,
+    /// Loaded on the first `glossary.lookup`
+    glossary: Option<GlossaryStore>,
+}
+
+impl Session {
+    fn handle_line(&mut self, line: &str) -> Flow {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(err) => {
+                self.output.error(
+                    &Value::Null,
+                    RpcError::new(PARSE_ERROR, format!("parse error: {err}")),
+                );
+                return Flow::Continue;
+            }
+        };
+
+        // Requests without an id are notifications and never get a response
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str);
+        let (Some(method), true) = (method, message.get("jsonrpc") == Some(&json!("2.0"))) else {
+            self.output.error(
+                &id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request"),
+            );
+            return Flow::Continue;
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let (result, flow) = match method {
+            "translate" | "translateStream" => match &id {
+                Some(id) => (
+                    self.translate(id, params, method == "translateStream"),
+                    Flow::Continue,
+                ),
+                None => (Ok(None), Flow::Continue),
+            },
+            "cancel" => (self.cancel(params).map(Some), Flow::Continue),
+            "languages" => (Ok(Some(languages())), Flow::Continue),
+            "glossary.lookup" => (self.lookup(params).map(Some), Flow::Continue),
+            "shutdown" => {
+                for pending in self.lock_pending().iter() {
+                    pending.cancel.cancel();
+                }
+                (Ok(Some(Value::Null)), Flow::Shutdown)
+            }
+            other => (
+                Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("method not found: {other}"),
+                )),
+                Flow::Continue,
+            ),
+        };
+
+        if let Some(id) = id {
+            match result {
+                Ok(Some(result)) => self.output.result(&id, result),
+                // Answered later by the event thread
+                Ok(None) => {}
+                Err(err) => self.output.error(&id, err),
+            }
+        }
+        flow
+    }
+
+    fn translate(
+        &mut self,
+        id: &Value,
+        params: Value,
+        stream: bool,
+    ) -> Result<Option<Value>, RpcError> {
+        let params: TranslateParams = parse_params(params)?;
+        if params.text.trim().is_empty() {
+            return Err(RpcError::new(INVALID_PARAMS, "text must not be empty"));
+        }
+        let source_lang = params
+            .source_lang
+            .unwrap_or_else(|| self.source_lang.clone());
+        let target_lang = params
+            .target_lang
+            .unwrap_or_else(|| self.target_lang.clone());
+        validate_pair(&source_lang, &target_lang)
+            .and_then(|()| params.options.validate())
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))?;
+
+        let cancel = CancellationToken::new();
+        // Queue first, so the event thread finds the entry even for an instant answer
+        self.lock_pending().push_back(Pending {
+            id: id.clone(),
+            stream,
+            cancel: cancel.clone(),
+        });
+        let request = TranslationRequest {
+            text: params.text,
+            source_lang,
+            target_lang,
+            options: params.options,
+            cancel,
+        };
+        if self.tx.send(request).is_err() {
+            self.lock_pending().pop_back();
+            return Err(RpcError::new(SERVER_ERROR, "translator worker stopped"));
+        }
+        Ok(None)
+    }
+
+    fn cancel(&self, params: Value) -> Result<Value, RpcError> {
+        let params: CancelParams = parse_params(params)?;
+        let mut found = false;
+        for pending in self.lock_pending().iter() {
+            if pending.id == params.id {
+                pending.cancel.cancel();
+                found = true;
+            }
+        }
+        Ok(Value::Bool(found))
+    }
+
+    fn lookup(&mut self, params: Value) -> Result<Value, RpcError> {
+        let params: LookupParams = parse_params(params)?;
+        let source_lang = params
+            .source_lang
+            .unwrap_or_else(|| self.source_lang.clone());
+        let target_lang = params
+            .target_lang
+            .unwrap_or_else(|| self.target_lang.clone());
+        validate_pair(&source_lang, &target_lang)
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))?;
+
+        let store = match &mut self.glossary {
+            Some(store) => store,
+            None => self.glossary.insert(
+                GlossaryStore::from_config(&self.glossary_config)
+                    .map_err(|err| RpcError::new(SERVER_ERROR, err.to_string()))?,
+            ),
+        };
+        let candidates = store
+            .select_candidates(&source_lang, &target_lang, &params.text)
+            .map_err(|err| RpcError::new(SERVER_ERROR, err.to_string()))?;
+        Ok(json!(candidates))
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, VecDeque<Pending>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|err| RpcError::new(INVALID_PARAMS, format!("invalid params: {err}")))
+}
+
+pub(crate) fn languages() -> Value {
+    supported_languages()
+        .iter()
+        .map(|code| json!({ "code": code, "name": language_name(code).unwrap_or(code) }))
+        .collect()
+}
+
+/// Turn worker events into responses and notifications
+fn forward_events(events: Receiver<WorkerEvent>, pending: &PendingQueue, output: &Output) {
+    for event in events {
+        match event {
+            WorkerEvent::TranslatorInitializing => {
+                output.notify("status", json!({ "state": "initializing" }))
+            }
+            WorkerEvent::TranslatorReady => output.notify("status", json!({ "state": "ready" })),
+            WorkerEvent::TranslatorInitFailed(err) => {
+                output.notify("status", json!({ "state": "failed", "error": err }))
+            }
+            WorkerEvent::TranslationProgress(piece) => {
+                let pending = pending.lock().unwrap_or_else(|err| err.into_inner());
+                if let Some(current) = pending.front().filter(|current| current.stream) {
+                    output.notify("progress", json!({ "id": current.id, "text": piece }));
+                }
+            }
+            WorkerEvent::Translation(response) => {
+                let finished = pending
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .pop_front();
+                let Some(finished) = finished else {
+                    continue;
+                };
+                match response {
+                    TranslationResponse::Ok(output_value) => {
+                        output.result(&finished.id, json!(output_value))
+                    }
+                    TranslationResponse::Err(err) => {
+                        output.error(&finished.id, RpcError::new(SERVER_ERROR, err))
+                    }
+                    TranslationResponse::Cancelled => output.error(
+                        &finished.id,
+                        RpcError::new(REQUEST_CANCELLED, "translation cancelled"),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petit_core::{Config, HttpConfig};
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn messages(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn response(messages: &[Value], id: i64) -> &Value {
+        messages
+            .iter()
+            .find(|message| message["id"] == id)
+            .unwrap_or_else(|| panic!("no response for id {id}"))
+    }
+
+    fn test_session(output: &SharedBuffer) -> (Session, Receiver<TranslationRequest>) {
+        let (tx, rx) = mpsc::channel();
+        let session = Session {
+            tx,
+            output: Output(Arc::new(Mutex::new(Box::new(output.clone())))),
+            pending: PendingQueue::default(),
+            source_lang: "auto".to_string(),
+            target_lang: "fr".to_string(),
+            glossary_config: GlossaryConfig::default(),
+            glossary: None,
+        };
+        (session, rx)
+    }
+
+    #[test]
+    fn serve_answers_requests_through_the_worker() {
+        let config = AppConfig {
+            backend: crate::config::Backend::Mock,
+            http: HttpConfig::default(),
+            server: Default::default(),
+            daemon_socket: PathBuf::from("/tmp/petit-rpc-test.sock"),
+            core: Config {
+                model_path: PathBuf::from("models/does-not-exist.gguf"),
+                gpu_layers: 0,
+                context_size: 2048,
+                threads: 1,
+                log_to_file: false,
+                log_path: PathBuf::from("logs/test-llama.log"),
+                glossary: Default::default(),
+                sampling: Default::default(),
+            },
+            source_lang: "auto".to_string(),
+            target_lang: "fr".to_string(),
+            placeholders: None,
+            stdin_mode: false,
+            compact_lang_display: false,
+        };
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"languages"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"translate","params":{"text":"hello world","target_lang":"de"}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"translateStream","params":{"text":"good night"}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"translate","params":{"text":"hi","target_lang":"xx"}}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"glossary.lookup","params":{"text":"hi","source_lang":"en"}}"#,
+            r#"{"jsonrpc":"2.0","id":6,"method":"rename"}"#,
+            "not json",
+        ]
+        .join("\n");
+        let output = SharedBuffer::default();
+
+        serve(input.as_bytes(), output.clone(), config).expect("rpc should serve");
+        let messages = output.messages();
+
+        assert!(
+            response(&messages, 1)["result"]
+                .as_array()
+                .unwrap()
+                .contains(&json!({ "code": "fr", "name": "French" }))
+        );
+        assert_eq!(response(&messages, 2)["result"]["text"], "HELLO WORLD");
+        assert_eq!(response(&messages, 2)["result"]["target_lang"], "de");
+        assert_eq!(response(&messages, 3)["result"]["text"], "GOOD NIGHT");
+        let progress: String = messages
+            .iter()
+            .filter(|message| message["method"] == "progress")
+            .map(|message| {
+                assert_eq!(message["params"]["id"], 3);
+                message["params"]["text"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(progress, "GOOD NIGHT");
+        assert_eq!(response(&messages, 4)["error"]["code"], INVALID_PARAMS);
+        assert_eq!(response(&messages, 5)["result"], json!([]));
+        assert_eq!(response(&messages, 6)["error"]["code"], METHOD_NOT_FOUND);
+        assert!(
+            messages
+                .iter()
+                .any(|message| message["error"]["code"] == PARSE_ERROR)
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|message| message["params"]["state"] == "ready")
+        );
+    }
+
+    #[test]
+    fn cancel_marks_pending_translation_and_reports_it() {
+        let output = SharedBuffer::default();
+        let (mut session, requests) = test_session(&output);
+
+        session.handle_line(
+            r#"{"jsonrpc":"2.0","id":"a","method":"translate","params":{"text":"hello"}}"#,
+        );
+        session.handle_line(r#"{"jsonrpc":"2.0","id":7,"method":"cancel","params":{"id":"a"}}"#);
+        session.handle_line(r#"{"jsonrpc":"2.0","method":"cancel","params":{"id":"missing"}}"#);
+        let request = requests.try_recv().expect("translation should be queued");
+        assert!(request.cancel.is_cancelled());
+
+        let (events_tx, events_rx) = mpsc::channel();
+        events_tx
+            .send(WorkerEvent::Translation(TranslationResponse::Cancelled))
+            .unwrap();
+        drop(events_tx);
+        forward_events(events_rx, &session.pending, &session.output);
+
+        let messages = output.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(response(&messages, 7)["result"], true);
+        assert_eq!(messages[1]["id"], "a");
+        assert_eq!(messages[1]["error"]["code"], REQUEST_CANCELLED);
+    }
+
+    #[test]
+    fn shutdown_cancels_pending_translations_and_stops() {
+        let output = SharedBuffer::default();
+        let (mut session, requests) = test_session(&output);
+
+        session.handle_line(
+            r#"{"jsonrpc":"2.0","id":1,"method":"translate","params":{"text":"hello"}}"#,
+        );
+        let flow = session.handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#);
+
+        assert!(flow == Flow::Shutdown);
+        assert!(requests.try_recv().unwrap().cancel.is_cancelled());
+        assert_eq!(
+            output.messages(),
+            vec![json!({ "jsonrpc": "2.0", "id": 2, "result": null })]
+        );
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let output = SharedBuffer::default();
+        let (mut session, _requests) = test_session(&output);
+
+        session.handle_line(r#"{"id":1,"method":"languages"}"#);
+        session
+            .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"translate","params":{"txt":"hi"}}"#);
+
+        let messages = output.messages();
+        assert_eq!(response(&messages, 1)["error"]["code"], INVALID_REQUEST);
+        assert_eq!(response(&messages, 2)["error"]["code"], INVALID_PARAMS);
+    }
+}
@@ -124,8 +124,13 @@ fn header_widget(app: &App) -> Paragraph<'static> {
 }
 
 fn status_widget(app: &App) -> Paragraph<'static> {
-    let (status_text, fg) = if app.is_loading {
-        (format!("{} Translating...", spinner_symbol()), Color::White)
+    let (status_text, fg) = if app.is_loading && app.is_cancelling() {
+        (format!("{} Cancelling...", spinner_symbol()), Color::Yellow)
+    } else if app.is_loading {
+        (
+            format!("{} Translating... (Esc to cancel)", spinner_symbol()),
+            Color::White,
+        )
     } else if app.is_worker_initializing {
         (
             format!("{} Initializing translator...", spinner_symbol()),
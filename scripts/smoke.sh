@@ -227,7 +227,7 @@ run_expect_failure \
 run_expect_failure \
   "empty stdin runtime validation" \
   'printf "" | cargo run --quiet -p petit-tui -- --stdin' \
-  'stdin is empty'
+  'no input text'
 
 run_expect_failure \
   "benchmark run-count validation" \
@@ -239,6 +239,11 @@ run_expect_failure \
   'cargo run --quiet -p petit-tui -- --no-config --src xx --tgt fr' \
   'Invalid language pair: Unsupported language'
 
+run_expect_success \
+  "mock backend stdin translation" \
+  'printf "Hello\n" | cargo run --quiet -p petit-tui --features mock -- --stdin --no-config --backend mock --src en --tgt fr' \
+  'HELLO'
+
 run_optional_model_smoke
 
 printf '\n'